@@ -0,0 +1,366 @@
+use flate2::read::GzDecoder;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zip::ExtraField;
+
+use crate::filelist::{
+    BUNDLEREPO_IGNORE_FILENAME, ExclusionMatcher, is_within_scope,
+    repository_path,
+};
+use crate::language::FileSelection;
+use crate::path_spec::{path_spec, selected_path_specs};
+use crate::structs::Params;
+use crate::workspace::{CARGO_MANIFEST, NPM_MANIFEST, PNPM_WORKSPACE};
+
+/// The archive formats accepted in place of a repository.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Recognises an archive by its file name.
+    pub(crate) fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the archive format of the positional repository argument, if it
+/// names an existing archive file.
+pub(crate) fn archive_input(repo_input: &str) -> Option<ArchiveFormat> {
+    let path = Path::new(repo_input);
+    path.is_file()
+        .then(|| ArchiveFormat::from_path(path))
+        .flatten()
+}
+
+/// Returns the relative path an archive entry unpacks to, refusing absolute
+/// paths and any `..` component so that no entry can land outside the
+/// destination.
+pub(crate) fn entry_path(name: &str) -> io::Result<PathBuf> {
+    let escapes = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("archive entry '{name}' escapes the archive root"),
+        )
+    };
+    let mut path = PathBuf::new();
+    for component in Path::new(&name.replace('\\', "/")).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir
+            | Component::RootDir
+            | Component::Prefix(_) => return Err(escapes()),
+        }
+    }
+    Ok(path)
+}
+
+/// The files unpacked whatever the filters, as listing files and selecting
+/// packages read them.
+const RULE_FILES: [&str; 6] = [
+    ".gitignore",
+    ".ignore",
+    BUNDLEREPO_IGNORE_FILENAME,
+    CARGO_MANIFEST,
+    NPM_MANIFEST,
+    PNPM_WORKSPACE,
+];
+
+/// Decides which archive files are unpacked with their content: those the
+/// exclude patterns, the `--lang` and category selection, the `--path` or
+/// `--files-from` selection and the size limit keep. Every file is unpacked
+/// with `--follow`, whose imports may pass through files left out.
+pub(crate) struct EntryFilter {
+    exclusions: ExclusionMatcher,
+    selection: FileSelection,
+    /// The selected paths, or `None` for the whole archive.
+    scopes: Option<Vec<String>>,
+    /// Whether the scopes are a `--files-from` list, which the exclude
+    /// patterns and the selection do not apply to.
+    listed: bool,
+    max_size: Option<u64>,
+    everything: bool,
+}
+
+impl EntryFilter {
+    pub(crate) fn new(flags: &Params) -> io::Result<Self> {
+        let scopes = selected_path_specs(flags)
+            .map(|value| {
+                // Absolute paths and the root itself select the whole
+                // archive here.
+                let path = path_spec(value)?.path;
+                Ok(entry_path(&path)
+                    .ok()
+                    .map(|path| repository_path(&path))
+                    .filter(|path| !path.is_empty()))
+            })
+            .collect::<io::Result<Option<Vec<_>>>>()?
+            .filter(|scopes| !scopes.is_empty());
+        Ok(Self {
+            exclusions: ExclusionMatcher::new(
+                flags.extend_exclude.as_deref(),
+                flags.exclude.as_deref(),
+            ),
+            selection: FileSelection::new(flags)?,
+            scopes,
+            listed: flags.files_from.is_some(),
+            max_size: flags.max_file_size,
+            everything: flags.follow.is_some(),
+        })
+    }
+
+    /// Returns `true` if the file at `path`, relative to the bundled root,
+    /// is unpacked with its content.
+    pub(crate) fn unpacks(&self, path: &str, size: u64) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        if self.everything || RULE_FILES.contains(&name) {
+            return true;
+        }
+        if let Some(scopes) = &self.scopes
+            && !scopes.iter().any(|scope| is_within_scope(path, scope))
+        {
+            return false;
+        }
+        if !self.listed
+            && (self.exclusions.matches(path)
+                || self.selection.deselection(path).is_some())
+        {
+            return false;
+        }
+        self.max_size.is_none_or(|max| size <= max)
+    }
+}
+
+/// An archive entry unpacked as a directory or a regular file; links and
+/// special files are skipped.
+struct Entry {
+    path: PathBuf,
+    is_dir: bool,
+}
+
+/// Unpacks `archive` into `destination` and returns the directory to bundle:
+/// the single top-level directory release archives usually wrap their files
+/// in, or `destination` itself. Only directories and regular files are
+/// unpacked; links and special files are skipped. A file `filter` leaves out
+/// is created empty at its size, without its content, so that it is still
+/// listed and reported as excluded or skipped.
+pub(crate) fn extract_archive(
+    archive: &Path,
+    format: ArchiveFormat,
+    destination: &Path,
+    filter: &EntryFilter,
+) -> io::Result<PathBuf> {
+    let open = || {
+        File::open(archive).map_err(|error| {
+            io::Error::new(
+                error.kind(),
+                format!(
+                    "cannot open archive '{}': {error}",
+                    archive.display()
+                ),
+            )
+        })
+    };
+    // The entries are listed first, without unpacking anything, to find the
+    // root the filters apply below.
+    let entries = match format {
+        ArchiveFormat::Tar => tar_entries(open()?)?,
+        ArchiveFormat::TarGz => tar_entries(GzDecoder::new(open()?))?,
+        ArchiveFormat::Zip => zip_entries(&mut zip_archive(open()?)?)?,
+    };
+    let root = archive_root(&entries);
+    let unpacks = |path: &Path, size: u64| {
+        let relative = root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path);
+        filter.unpacks(&repository_path(relative), size)
+    };
+
+    fs::create_dir_all(destination)?;
+    match format {
+        ArchiveFormat::Tar => extract_tar(open()?, destination, unpacks)?,
+        ArchiveFormat::TarGz => {
+            extract_tar(GzDecoder::new(open()?), destination, unpacks)?
+        }
+        ArchiveFormat::Zip => {
+            extract_zip(zip_archive(open()?)?, destination, unpacks)?
+        }
+    }
+    Ok(root.map_or_else(
+        || destination.to_path_buf(),
+        |root| destination.join(root),
+    ))
+}
+
+/// Returns the single top-level directory holding every entry, if there is
+/// one.
+fn archive_root(entries: &[Entry]) -> Option<PathBuf> {
+    let mut roots = entries.iter().map(|entry| {
+        let mut components = entry.path.components();
+        let root = components.next()?;
+        let is_dir = entry.is_dir || components.next().is_some();
+        is_dir.then(|| PathBuf::from(root.as_os_str()))
+    });
+    let root = roots.next()??;
+    roots
+        .all(|other| other.as_ref() == Some(&root))
+        .then_some(root)
+}
+
+fn tar_entries<R: Read>(reader: R) -> io::Result<Vec<Entry>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let path = entry_path(&name)?;
+        let entry_type = entry.header().entry_type();
+        if (entry_type.is_dir() || entry_type.is_file())
+            && path.components().next().is_some()
+        {
+            entries.push(Entry {
+                path,
+                is_dir: entry_type.is_dir(),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+fn extract_tar<R: Read>(
+    reader: R,
+    destination: &Path,
+    unpacks: impl Fn(&Path, u64) -> bool,
+) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let path = entry_path(&name)?;
+        let target = destination.join(&path);
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if entry_type.is_file() {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if unpacks(&path, entry.size()) {
+                entry.unpack(&target)?;
+            } else {
+                let file = File::create(&target)?;
+                file.set_len(entry.size())?;
+                file.set_modified(
+                    UNIX_EPOCH + Duration::from_secs(entry.header().mtime()?),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn zip_archive(file: File) -> io::Result<zip::ZipArchive<File>> {
+    zip::ZipArchive::new(file).map_err(io::Error::other)
+}
+
+fn zip_entries(archive: &mut zip::ZipArchive<File>) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index).map_err(io::Error::other)?;
+        let path = entry_path(entry.name())?;
+        if !entry.is_symlink() && path.components().next().is_some() {
+            entries.push(Entry {
+                path,
+                is_dir: entry.is_dir(),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+fn extract_zip(
+    mut archive: zip::ZipArchive<File>,
+    destination: &Path,
+    unpacks: impl Fn(&Path, u64) -> bool,
+) -> io::Result<()> {
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(io::Error::other)?;
+        let path = entry_path(entry.name())?;
+        let target = destination.join(&path);
+        if entry.is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if !entry.is_symlink() {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut file = File::create(&target)?;
+            if unpacks(&path, entry.size()) {
+                io::copy(&mut entry, &mut file)?;
+            } else {
+                file.set_len(entry.size())?;
+            }
+            if let Some(modified) = zip_modified(&entry) {
+                file.set_modified(modified)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns when a zip entry was last modified: the Unix time of its extended
+/// timestamp field, or else its MS-DOS date and time, which carry no time
+/// zone and are read as UTC.
+fn zip_modified(entry: &zip::read::ZipFile<'_>) -> Option<SystemTime> {
+    let extended = entry.extra_data_fields().find_map(|field| match field {
+        ExtraField::ExtendedTimestamp(timestamp) => timestamp.mod_time(),
+        _ => None,
+    });
+    let seconds = match extended {
+        Some(seconds) => u64::from(seconds),
+        None => dos_seconds(entry.last_modified()?)?,
+    };
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// Converts an MS-DOS date and time to seconds since 1970-01-01.
+fn dos_seconds(time: zip::DateTime) -> Option<u64> {
+    let month = i64::from(time.month());
+    let year = i64::from(time.year()) - i64::from(month <= 2);
+
+    // Converts a proleptic Gregorian date to days since 1970-01-01.
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + i64::from(time.day()) - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    u64::try_from(
+        days * 86_400
+            + i64::from(time.hour()) * 3600
+            + i64::from(time.minute()) * 60
+            + i64::from(time.second()),
+    )
+    .ok()
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/archive.rs"]
+mod tests;
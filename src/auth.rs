@@ -0,0 +1,251 @@
+use git2::{Config, Cred, CredentialType, ErrorClass, ErrorCode};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// The private keys tried in `~/.ssh` after any `--ssh-key` and the agent,
+/// in the order `ssh` itself tries them.
+const DEFAULT_SSH_KEYS: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+
+/// The only host the configuration's `token`, `GITHUB_TOKEN` and `GH_TOKEN`
+/// are sent to.
+const GITHUB_HOST: &str = "github.com";
+
+/// The environment variables a token is read from, in order, and whether
+/// each only holds a token for github.com.
+const TOKEN_VARIABLES: [(&str, bool); 3] = [
+    ("BUNDLEREPO_TOKEN", false),
+    ("GITHUB_TOKEN", true),
+    ("GH_TOKEN", true),
+];
+
+/// A way of authenticating with a remote repository.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum AuthMethod {
+    Token,
+    CredentialHelper,
+    SshKey(PathBuf),
+    SshAgent,
+}
+
+impl fmt::Display for AuthMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Token => f.write_str("the token"),
+            Self::CredentialHelper => f.write_str("git's credential helpers"),
+            Self::SshKey(path) => {
+                write!(f, "the SSH key '{}'", path.display())
+            }
+            Self::SshAgent => f.write_str("the SSH agent"),
+        }
+    }
+}
+
+/// Answers libgit2's credential requests while fetching, offering each
+/// method once: the token and then git's credential helpers over HTTPS, and
+/// `--ssh-key`, the SSH agent and then the default keys over SSH.
+pub(crate) struct Authenticator {
+    https: VecDeque<AuthMethod>,
+    ssh: VecDeque<AuthMethod>,
+    token: Option<String>,
+    git_config: Option<Config>,
+    tried: Vec<AuthMethod>,
+}
+
+impl Authenticator {
+    /// Prepares the methods to offer. The default keys are looked for in
+    /// `ssh_dir`, usually `~/.ssh`, and the credential helpers are read from
+    /// `git_config`.
+    pub(crate) fn new(
+        token: Option<&str>,
+        ssh_key: Option<PathBuf>,
+        ssh_dir: Option<&Path>,
+        git_config: Option<Config>,
+    ) -> Self {
+        let mut https = VecDeque::new();
+        if token.is_some() {
+            https.push_back(AuthMethod::Token);
+        }
+        https.push_back(AuthMethod::CredentialHelper);
+
+        let mut ssh = ssh_key
+            .map(AuthMethod::SshKey)
+            .into_iter()
+            .collect::<VecDeque<_>>();
+        ssh.push_back(AuthMethod::SshAgent);
+        ssh.extend(
+            ssh_dir
+                .into_iter()
+                .flat_map(|directory| {
+                    DEFAULT_SSH_KEYS.iter().map(|name| directory.join(name))
+                })
+                .filter(|path| path.is_file())
+                .map(AuthMethod::SshKey),
+        );
+
+        Self {
+            https,
+            ssh,
+            token: token.map(str::to_string),
+            git_config,
+            tried: Vec::new(),
+        }
+    }
+
+    /// Returns the next credentials to offer for `url`, or an error naming
+    /// every method tried once none is left.
+    pub(crate) fn credentials(
+        &mut self,
+        url: &str,
+        username_from_url: Option<&str>,
+        allowed: CredentialType,
+    ) -> Result<Cred, git2::Error> {
+        let username = username_from_url.unwrap_or("git");
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username);
+        }
+
+        let ssh = allowed.contains(CredentialType::SSH_KEY);
+        if !ssh && !allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            return Err(git2::Error::new(
+                ErrorCode::Auth,
+                ErrorClass::Net,
+                format!(
+                    "Authentication failed for '{url}': the server asked for an unsupported authentication method."
+                ),
+            ));
+        }
+        loop {
+            let queue = if ssh { &mut self.ssh } else { &mut self.https };
+            let Some(method) = queue.pop_front() else {
+                return Err(self.failure(url, ssh));
+            };
+            let credentials = match &method {
+                AuthMethod::Token => Cred::userpass_plaintext(
+                    "oauth2",
+                    self.token.as_deref().unwrap_or_default(),
+                ),
+                AuthMethod::CredentialHelper => match &self.git_config {
+                    Some(config) => {
+                        Cred::credential_helper(config, url, username_from_url)
+                    }
+                    None => Err(git2::Error::from_str("no git configuration")),
+                },
+                AuthMethod::SshKey(path) => {
+                    Cred::ssh_key(username, None, path, None)
+                }
+                AuthMethod::SshAgent => Cred::ssh_key_from_agent(username),
+            };
+            self.tried.push(method);
+            if let Ok(credentials) = credentials {
+                return Ok(credentials);
+            }
+        }
+    }
+
+    fn failure(&self, url: &str, ssh: bool) -> git2::Error {
+        let tried = self
+            .tried
+            .iter()
+            .filter(|method| {
+                ssh == matches!(
+                    method,
+                    AuthMethod::SshKey(_) | AuthMethod::SshAgent
+                )
+            })
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let (class, hint) = if ssh {
+            (
+                ErrorClass::Ssh,
+                "Add a key to the SSH agent or pass one with --ssh-key.",
+            )
+        } else {
+            (
+                ErrorClass::Http,
+                "Pass a token with --token or configure a git credential helper.",
+            )
+        };
+        git2::Error::new(
+            ErrorCode::Auth,
+            class,
+            format!(
+                "Authentication failed for '{url}' after trying {}. {hint}",
+                join_methods(&tried)
+            ),
+        )
+    }
+}
+
+/// Joins method descriptions as "a", "a and b" or "a, b and c".
+fn join_methods(methods: &[String]) -> String {
+    match methods {
+        [] => "no method".to_string(),
+        [method] => method.clone(),
+        [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+    }
+}
+
+/// Picks the token for a repository on `host`: `--token`, then the host's
+/// entry in the configuration's `[tokens]` table, then the first of
+/// `BUNDLEREPO_TOKEN`, `GITHUB_TOKEN` and `GH_TOKEN` set in the environment
+/// (the latter two only for github.com) and finally the configuration's
+/// `token`, which has always been a GitHub token and so is only sent to
+/// github.com. Without a host, as for a local repository, only `--token` is
+/// used.
+pub(crate) fn resolve_token(
+    cli_token: Option<&str>,
+    host: Option<&str>,
+    tokens: &BTreeMap<String, String>,
+    config_token: Option<&str>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    if let Some(token) = cli_token {
+        return Some(token.to_string());
+    }
+    let host = host?;
+    let github = host == GITHUB_HOST;
+    tokens
+        .get(host)
+        .cloned()
+        .or_else(|| {
+            TOKEN_VARIABLES
+                .iter()
+                .filter(|(_, github_only)| github || !github_only)
+                .find_map(|(name, _)| {
+                    env(name).filter(|token| !token.is_empty())
+                })
+        })
+        .or_else(|| config_token.filter(|_| github).map(str::to_string))
+}
+
+/// Reads a `--token-file` token from a file, or from standard input for `-`,
+/// without its surrounding whitespace.
+pub(crate) fn read_token_file(source: &str) -> io::Result<String> {
+    let content = if source == "-" {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        content
+    } else {
+        fs::read_to_string(source).map_err(|error| {
+            io::Error::new(
+                error.kind(),
+                format!("cannot read token file '{source}': {error}"),
+            )
+        })?
+    };
+    let token = content.trim();
+    if token.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("token file '{source}' is empty"),
+        ));
+    }
+    Ok(token.to_string())
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/auth.rs"]
+mod tests;
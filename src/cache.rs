@@ -0,0 +1,305 @@
+use dirs_next::home_dir;
+use git2::{AutotagOption, FetchOptions, FetchPrune, Remote, Repository};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use url::Url;
+
+use crate::structs::Params;
+
+/// Touched in a cached clone's git directory each time it is used, so that
+/// `cache prune --older-than` keeps the clones still in use.
+const LAST_USED_FILE: &str = "bundlerepo-last-used";
+
+/// Fetched when every branch and tag of a remote is needed.
+const MIRROR_REFSPECS: [&str; 2] =
+    ["+refs/heads/*:refs/heads/*", "+refs/tags/*:refs/tags/*"];
+
+/// Where the remote's `HEAD` is fetched to before it is stored under the name
+/// of its default branch.
+const FETCHED_HEAD: &str = "refs/bundlerepo/fetched-head";
+
+/// The depth libgit2 takes to fetch the history a shallow clone is missing.
+const UNSHALLOW_DEPTH: i32 = i32::MAX;
+
+/// libgit2's error code for a transport that cannot fetch shallow, such as
+/// the local one.
+const GIT_ENOTSUPPORTED: i32 = -39;
+
+/// What a fetch brings into a clone. Unless `full_history` is asked for, only
+/// the last commit of a single ref is fetched when the remote supports it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum FetchTarget<'a> {
+    /// The remote's default branch, which the clone's `HEAD` points at.
+    DefaultBranch,
+    Branch(&'a str),
+    /// A branch, tag or full ref name. Anything else, such as a commit id,
+    /// fetches every branch and tag in full to be found.
+    Ref(&'a str),
+    /// Every branch and tag in full, for comparisons between refs.
+    Everything,
+}
+
+/// A cached clone of a remote repository.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct CachedRepository {
+    /// The clone's path below the cache root, as `<host>/<owner>/<repo>`.
+    pub(crate) name: String,
+    pub(crate) path: PathBuf,
+    pub(crate) size: u64,
+    /// When the clone was last fetched or bundled, as a Unix time.
+    pub(crate) last_used: Option<i64>,
+}
+
+/// Returns the directory the clones are cached in: `cache_dir` from the
+/// configuration, with a leading `~` expanded, or
+/// `~/.cache/bundlerepo/repos`.
+pub(crate) fn cache_root(flags: &Params) -> Option<PathBuf> {
+    let Some(cache_dir) = flags.cache_dir.as_deref().map(Path::new) else {
+        return home_dir().map(|home| home.join(".cache/bundlerepo/repos"));
+    };
+    match cache_dir.strip_prefix("~") {
+        Ok(relative_path) => home_dir().map(|home| home.join(relative_path)),
+        Err(_) => Some(cache_dir.to_path_buf()),
+    }
+}
+
+/// Returns where the clone of `url` is cached below `root`: the URL's host
+/// followed by its path, without the `.git` suffix.
+pub(crate) fn cache_path(root: &Path, url: &str) -> Option<PathBuf> {
+    let url = Url::parse(url).ok()?;
+    let host = url
+        .host_str()
+        .filter(|host| !host.is_empty())
+        .unwrap_or("localhost");
+    let segments = url
+        .path_segments()?
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let (name, owners) = segments.split_last()?;
+    let mut path = root.join(host);
+    path.extend(owners);
+    path.push(name.strip_suffix(".git").unwrap_or(name));
+    Some(path)
+}
+
+/// Opens the cached clone at `path` without touching the network.
+pub(crate) fn open_cached_clone(
+    path: &Path,
+) -> Result<Repository, git2::Error> {
+    let repository = Repository::open_bare(path)?;
+    record_use(path);
+    Ok(repository)
+}
+
+/// Fetches `target` from `url` into the cached clone at `path`, creating the
+/// clone on first use. Only the objects missing from the cache are
+/// downloaded, and only the last commit unless `full_history` is set. A
+/// clone whose first fetch fails is removed again.
+pub(crate) fn fetch_into_cache(
+    path: &Path,
+    url: &str,
+    target: FetchTarget<'_>,
+    full_history: bool,
+    fetch_options: &mut FetchOptions<'_>,
+) -> Result<Repository, git2::Error> {
+    let existed = path.join("HEAD").is_file();
+    let repository = if existed {
+        Repository::open_bare(path)?
+    } else {
+        fs::create_dir_all(path)
+            .map_err(|error| git2::Error::from_str(&error.to_string()))?;
+        Repository::init_bare(path)?
+    };
+
+    let fetched =
+        fetch_target(&repository, url, target, full_history, fetch_options);
+    if let Err(error) = fetched {
+        if !existed {
+            let _ = fs::remove_dir_all(path);
+        }
+        return Err(error);
+    }
+    record_use(path);
+    Ok(repository)
+}
+
+fn fetch_target(
+    repository: &Repository,
+    url: &str,
+    target: FetchTarget<'_>,
+    full_history: bool,
+    fetch_options: &mut FetchOptions<'_>,
+) -> Result<(), git2::Error> {
+    let refspecs = match target {
+        FetchTarget::DefaultBranch => vec![format!("+HEAD:{FETCHED_HEAD}")],
+        FetchTarget::Branch(name) => {
+            vec![format!("+refs/heads/{name}:refs/heads/{name}")]
+        }
+        FetchTarget::Ref(name) if name.starts_with("refs/") => {
+            vec![format!("+{name}:{name}")]
+        }
+        FetchTarget::Ref(name) => vec![
+            format!("+refs/heads/{name}:refs/heads/{name}"),
+            format!("+refs/tags/{name}:refs/tags/{name}"),
+        ],
+        FetchTarget::Everything => {
+            MIRROR_REFSPECS.map(str::to_string).to_vec()
+        }
+    };
+    let everything = target == FetchTarget::Everything;
+    let mut remote = repository.remote_anonymous(url)?;
+    fetch(
+        repository,
+        &mut remote,
+        &refspecs,
+        full_history || everything,
+        fetch_options,
+    )?;
+
+    let default_branch = remote.default_branch()?;
+    let default_branch = default_branch.as_str()?;
+    if let FetchTarget::Ref(name) = target
+        && !name.starts_with("refs/")
+        && repository.revparse_single(name).is_err()
+    {
+        let refspecs = MIRROR_REFSPECS.map(str::to_string);
+        fetch(repository, &mut remote, &refspecs, true, fetch_options)?;
+    } else if target == FetchTarget::DefaultBranch {
+        let mut fetched_head = repository.find_reference(FETCHED_HEAD)?;
+        let commit = fetched_head.peel_to_commit()?;
+        repository.reference(default_branch, commit.id(), true, "fetch")?;
+        fetched_head.delete()?;
+    }
+    if target == FetchTarget::DefaultBranch || everything {
+        repository.set_head(default_branch)?;
+    }
+    Ok(())
+}
+
+/// Fetches `refspecs`, shallow unless `full_history` is set or the remote
+/// cannot fetch shallow. Tags are only fetched when asked for.
+fn fetch(
+    repository: &Repository,
+    remote: &mut Remote<'_>,
+    refspecs: &[String],
+    full_history: bool,
+    fetch_options: &mut FetchOptions<'_>,
+) -> Result<(), git2::Error> {
+    let depth = match (full_history, repository.is_shallow()) {
+        (false, _) => 1,
+        (true, true) => UNSHALLOW_DEPTH,
+        (true, false) => 0,
+    };
+    fetch_options
+        .prune(FetchPrune::On)
+        .download_tags(AutotagOption::None)
+        .depth(depth);
+    match remote.fetch(refspecs, Some(fetch_options), None) {
+        Err(error) if depth == 1 && error.raw_code() == GIT_ENOTSUPPORTED => {
+            fetch_options.depth(0);
+            remote.fetch(refspecs, Some(fetch_options), None)
+        }
+        result => result,
+    }
+}
+
+fn record_use(path: &Path) {
+    // A cache that cannot record its use is still usable.
+    let _ = fs::write(path.join(LAST_USED_FILE), "");
+}
+
+/// Lists the cached clones below `root`, sorted by name.
+pub(crate) fn cached_repositories(
+    root: &Path,
+) -> io::Result<Vec<CachedRepository>> {
+    let mut repositories = Vec::new();
+    if root.is_dir() {
+        find_repositories(root, root, &mut repositories)?;
+    }
+    repositories.sort_by(|left, right| left.name.cmp(&right.name));
+    Ok(repositories)
+}
+
+fn find_repositories(
+    root: &Path,
+    directory: &Path,
+    repositories: &mut Vec<CachedRepository>,
+) -> io::Result<()> {
+    if directory.join("HEAD").is_file() && directory.join("objects").is_dir() {
+        let name = directory
+            .strip_prefix(root)
+            .unwrap_or(directory)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let last_used = fs::metadata(directory.join(LAST_USED_FILE))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|elapsed| elapsed.as_secs() as i64);
+        repositories.push(CachedRepository {
+            name,
+            path: directory.to_path_buf(),
+            size: directory_size(directory)?,
+            last_used,
+        });
+        return Ok(());
+    }
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            find_repositories(root, &entry.path(), repositories)?;
+        }
+    }
+    Ok(())
+}
+
+fn directory_size(directory: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += directory_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Removes the cached clones below `root` last used before `cutoff`, a Unix
+/// time, or every clone without a cutoff, and returns the removed clones.
+/// Directories left empty are removed too.
+pub(crate) fn prune_cache(
+    root: &Path,
+    cutoff: Option<i64>,
+) -> io::Result<Vec<CachedRepository>> {
+    let mut removed = Vec::new();
+    for repository in cached_repositories(root)? {
+        let stale = match (cutoff, repository.last_used) {
+            (Some(cutoff), Some(last_used)) => last_used < cutoff,
+            _ => true,
+        };
+        if !stale {
+            continue;
+        }
+        fs::remove_dir_all(&repository.path)?;
+        let mut parent = repository.path.parent();
+        while let Some(directory) = parent
+            && directory != root
+            && fs::remove_dir(directory).is_ok()
+        {
+            parent = directory.parent();
+        }
+        removed.push(repository);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/cache.rs"]
+mod tests;
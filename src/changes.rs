@@ -0,0 +1,367 @@
+use crate::dry_run::{Candidate, Decision, Exclusion};
+use crate::filelist::{
+    ExclusionMatcher, FileList, FileTree, add_deleted_file, is_within_scope,
+};
+use crate::follow::DependencyGraph;
+use crate::language::FileSelection;
+use crate::source::FileSource;
+use crate::structs::Params;
+use git2::{Delta, Diff, DiffFindOptions, DiffOptions, Patch, Repository};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io;
+
+/// How a file changed between the two sides of a diff.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+impl ChangeKind {
+    pub(crate) const fn as_str(self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Modified => "modified",
+            Self::Deleted => "deleted",
+            Self::Renamed => "renamed",
+        }
+    }
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A changed file, with the path it was renamed from and its unified patch
+/// when requested.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct FileChange {
+    pub(crate) kind: ChangeKind,
+    pub(crate) previous_path: Option<String>,
+    pub(crate) patch: Option<String>,
+}
+
+/// The changed files, keyed by their path on the new side, or on the old
+/// side for deleted files.
+pub(crate) type ChangeSet = BTreeMap<String, FileChange>;
+
+/// The two sides of a `--diff-base` or `--diff` comparison.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct DiffRange {
+    pub(crate) base: String,
+    pub(crate) head: String,
+    /// Compare from the merge base of the two sides, as `A...B` does.
+    pub(crate) merge_base: bool,
+}
+
+/// Splits `A..B` or `A...B` into its two sides.
+pub(crate) fn parse_diff_range(value: &str) -> Result<DiffRange, String> {
+    let (base, head, merge_base) =
+        if let Some((base, head)) = value.split_once("...") {
+            (base, head, true)
+        } else if let Some((base, head)) = value.split_once("..") {
+            (base, head, false)
+        } else {
+            return Err(format!("expected 'A..B' or 'A...B', not '{value}'"));
+        };
+    if base.is_empty() || head.is_empty() {
+        return Err(format!("both sides of '{value}' must name a ref"));
+    }
+    Ok(DiffRange {
+        base: base.to_string(),
+        head: head.to_string(),
+        merge_base,
+    })
+}
+
+/// Checks that a `--diff` value names two refs.
+pub(crate) fn diff_range_value(value: &str) -> Result<String, String> {
+    parse_diff_range(value).map(|_| value.to_string())
+}
+
+/// What the changed files are compared against.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum Comparison {
+    /// `--diff-base` or `--diff`: one commit against another.
+    Refs(DiffRange),
+    /// `--uncommitted` and `--staged`: local edits against `HEAD`.
+    Local { unstaged: bool, staged: bool },
+}
+
+/// Returns the comparison asked for by `--diff-base`, `--diff`,
+/// `--uncommitted` or `--staged`, if any. `--diff-base BASE` compares `HEAD`
+/// with its merge base with `BASE`.
+pub(crate) fn comparison(flags: &Params) -> Option<Comparison> {
+    if let Some(base) = &flags.diff_base {
+        return Some(Comparison::Refs(DiffRange {
+            base: base.clone(),
+            head: "HEAD".to_string(),
+            merge_base: true,
+        }));
+    }
+    if let Some(range) = flags
+        .diff
+        .as_deref()
+        .and_then(|value| parse_diff_range(value).ok())
+    {
+        return Some(Comparison::Refs(range));
+    }
+    (flags.uncommitted || flags.staged).then_some(Comparison::Local {
+        unstaged: flags.uncommitted,
+        staged: flags.staged,
+    })
+}
+
+/// Computes the files changed by `comparison`, detecting renames, with
+/// unified patches when `patches` is set.
+pub(crate) fn changed_files(
+    repository: &Repository,
+    comparison: &Comparison,
+    patches: bool,
+) -> Result<ChangeSet, git2::Error> {
+    match comparison {
+        Comparison::Refs(range) => range_changes(repository, range, patches),
+        Comparison::Local { unstaged, staged } => {
+            local_changes(repository, *unstaged, *staged, patches)
+        }
+    }
+}
+
+fn range_changes(
+    repository: &Repository,
+    range: &DiffRange,
+    patches: bool,
+) -> Result<ChangeSet, git2::Error> {
+    let resolve = |name: &str| {
+        repository
+            .revparse_single(name)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|_| {
+                git2::Error::from_str(&format!(
+                    "The ref '{name}' does not exist in the repository."
+                ))
+            })
+    };
+    let head = resolve(&range.head)?;
+    let mut base = resolve(&range.base)?;
+    if range.merge_base {
+        let merge_base = repository.merge_base(base.id(), head.id())?;
+        base = repository.find_commit(merge_base)?;
+    }
+
+    let mut diff = repository.diff_tree_to_tree(
+        Some(&base.tree()?),
+        Some(&head.tree()?),
+        None,
+    )?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    collect_changes(&diff, patches)
+}
+
+/// Compares `HEAD` with the index for staged changes, or with the working
+/// tree otherwise, so that every patch is against `HEAD`. With only
+/// `unstaged`, files whose changes are all staged are left out.
+fn local_changes(
+    repository: &Repository,
+    unstaged: bool,
+    staged: bool,
+    patches: bool,
+) -> Result<ChangeSet, git2::Error> {
+    // A repository without commits compares against an empty tree.
+    let head = repository
+        .head()
+        .ok()
+        .and_then(|head| head.peel_to_tree().ok());
+    let mut options = DiffOptions::new();
+    options
+        .include_untracked(unstaged)
+        .recurse_untracked_dirs(unstaged)
+        .show_untracked_content(unstaged);
+    let mut diff = if unstaged {
+        repository.diff_tree_to_workdir_with_index(
+            head.as_ref(),
+            Some(&mut options),
+        )?
+    } else {
+        repository.diff_tree_to_index(
+            head.as_ref(),
+            None,
+            Some(&mut options),
+        )?
+    };
+    diff.find_similar(Some(
+        DiffFindOptions::new().renames(true).for_untracked(true),
+    ))?;
+    let mut changes = collect_changes(&diff, patches)?;
+
+    if unstaged && !staged {
+        let mut options = DiffOptions::new();
+        options.include_untracked(true).recurse_untracked_dirs(true);
+        let unstaged_diff =
+            repository.diff_index_to_workdir(None, Some(&mut options))?;
+        let touched = unstaged_diff
+            .deltas()
+            .flat_map(|delta| {
+                [delta.old_file().path(), delta.new_file().path()]
+            })
+            .flatten()
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .collect::<BTreeSet<_>>();
+        changes.retain(|path, change| {
+            touched.contains(path)
+                || change
+                    .previous_path
+                    .as_ref()
+                    .is_some_and(|previous| touched.contains(previous))
+        });
+    }
+    Ok(changes)
+}
+
+fn collect_changes(
+    diff: &Diff<'_>,
+    patches: bool,
+) -> Result<ChangeSet, git2::Error> {
+    let mut changes = ChangeSet::new();
+    for (index, delta) in diff.deltas().enumerate() {
+        let kind = match delta.status() {
+            Delta::Added | Delta::Copied | Delta::Untracked => {
+                ChangeKind::Added
+            }
+            Delta::Modified | Delta::Typechange => ChangeKind::Modified,
+            Delta::Deleted => ChangeKind::Deleted,
+            Delta::Renamed => ChangeKind::Renamed,
+            _ => continue,
+        };
+        let file = if kind == ChangeKind::Deleted {
+            delta.old_file()
+        } else {
+            delta.new_file()
+        };
+        let Some(path) = file.path().map(|path| path.to_string_lossy()) else {
+            continue;
+        };
+        let previous_path = (kind == ChangeKind::Renamed)
+            .then(|| delta.old_file().path())
+            .flatten()
+            .map(|path| path.to_string_lossy().into_owned());
+        let patch = if patches {
+            Patch::from_diff(diff, index)?
+                .map(|mut patch| patch.to_buf())
+                .transpose()?
+                .map(|buf| String::from_utf8_lossy(&buf).into_owned())
+        } else {
+            None
+        };
+        changes.insert(
+            path.replace('\\', "/"),
+            FileChange {
+                kind,
+                previous_path,
+                patch,
+            },
+        );
+    }
+    Ok(changes)
+}
+
+/// The files a comparison selects: the changed files and, with
+/// `--with-imports`, the local files they import.
+#[derive(Debug, Default)]
+pub(crate) struct ChangedFiles {
+    pub(crate) changes: ChangeSet,
+    pub(crate) imports: BTreeSet<String>,
+}
+
+impl ChangedFiles {
+    /// Follows the imports of the changed files that exist in `source` when
+    /// `--with-imports` is set.
+    pub(crate) fn new(
+        source: &(impl FileSource + ?Sized),
+        flags: &Params,
+        changes: ChangeSet,
+    ) -> io::Result<Self> {
+        let mut imports = BTreeSet::new();
+        if flags.with_imports {
+            let seeds = changes
+                .iter()
+                .filter(|(path, change)| {
+                    change.kind != ChangeKind::Deleted && source.has_file(path)
+                })
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<_>>();
+            imports = DependencyGraph::build(source, &seeds)?
+                .imports
+                .into_keys()
+                .filter(|path| !changes.contains_key(path))
+                .collect();
+        }
+        Ok(Self { changes, imports })
+    }
+
+    pub(crate) fn contains(&self, path: &str) -> bool {
+        self.changes.contains_key(path) || self.imports.contains(path)
+    }
+
+    /// Keeps only the listed files that changed or are imported.
+    pub(crate) fn retain(&self, mut file_list: FileList) -> FileList {
+        file_list.files.retain(|path| self.contains(path));
+        file_list
+            .skipped
+            .retain(|skipped| self.contains(&skipped.path));
+        file_list
+    }
+
+    /// Excludes a dry-run candidate file that is neither changed nor
+    /// imported.
+    pub(crate) fn exclude_unselected(&self, candidate: &mut Candidate) {
+        if !candidate.is_dir
+            && !matches!(candidate.decision, Decision::Exclude(_))
+            && !self.contains(&candidate.path)
+        {
+            candidate.decision = Decision::Exclude(Exclusion::Unchanged);
+        }
+    }
+}
+
+/// Adds the deleted files that the file listing would have kept to
+/// `file_tree` and records the changes for the output. A deleted file is
+/// only kept when it passes the exclusions, the `--path` scopes and the
+/// language and category filters, as an existing file would have to.
+pub(crate) fn add_changes(
+    mut file_tree: FileTree,
+    flags: &Params,
+    scopes: &[String],
+    mut changes: ChangeSet,
+) -> io::Result<FileTree> {
+    let exclusions = ExclusionMatcher::new(
+        flags.extend_exclude.as_deref(),
+        flags.exclude.as_deref(),
+    );
+    let selection = FileSelection::new(flags)?;
+    changes.retain(|path, change| {
+        change.kind != ChangeKind::Deleted
+            || (!exclusions.matches(path)
+                && selection.deselection(path).is_none()
+                && (scopes.is_empty()
+                    || scopes
+                        .iter()
+                        .any(|scope| is_within_scope(path, scope))))
+    });
+    for (path, change) in &changes {
+        if change.kind == ChangeKind::Deleted {
+            add_deleted_file(&mut file_tree, path);
+        }
+    }
+    file_tree.changes = changes;
+    Ok(file_tree)
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/changes.rs"]
+mod tests;
@@ -1,7 +1,12 @@
-use clap::{ArgAction, Parser};
+use clap::{ArgAction, ArgGroup, Parser, Subcommand};
 
-use crate::structs::{DEFAULT_MODEL, DEFAULT_OUTPUT_FILE};
+use crate::changes::diff_range_value;
+use crate::grep::parse_grep_pattern;
+use crate::language::parse_language;
+use crate::limits::{parse_age, parse_file_size, parse_line_count};
+use crate::structs::{DEFAULT_MODEL, DEFAULT_OUTPUT_FILE, ORDER_VALUES};
 use crate::tokenizer::MODEL_VALUES;
+use crate::truncation::parse_token_count;
 
 fn parse_gzip_level(value: &str) -> Result<u32, String> {
     match value.parse::<u32>() {
@@ -16,13 +21,15 @@ fn parse_gzip_level(value: &str) -> Result<u32, String> {
     author = env!("CARGO_PKG_AUTHORS"),
     about = env!("CARGO_PKG_DESCRIPTION"),
     long_about = None,
+    group(ArgGroup::new("changes").multiple(true)),
 )]
 pub struct Flags {
-    #[arg(
-        help = "GitHub repository to clone (e.g. 'user/repo' or full GitHub \
-                URL). If not provided, the current directory will be searched \
-                for a Git repository."
-    )]
+    #[arg(help = "Repository to clone: 'user/repo' on GitHub, a \
+                'gitlab:group/repo', 'bitbucket:team/repo' or \
+                'codeberg:user/repo' shorthand, a configured 'host:owner/repo' \
+                or a URL; or a local directory, file:// URL or .tar, .tar.gz \
+                or .zip archive to bundle. If not provided, the current directory \
+                will be searched for a Git repository.")]
     pub repo: Option<String>,
 
     #[arg(
@@ -32,6 +39,116 @@ pub struct Flags {
     )]
     pub branch: Option<String>,
 
+    #[arg(
+        long = "ref",
+        value_name = "REF",
+        help = "Bundle a commit, tag or other ref, read from git's objects rather than the working tree",
+        conflicts_with_all = ["branch", "no_git", "tracked_only", "include_ignored"]
+    )]
+    pub git_ref: Option<String>,
+
+    #[arg(
+        long = "diff-base",
+        value_name = "BASE",
+        group = "changes",
+        conflicts_with_all = ["git_ref", "branch", "no_git", "tracked_only", "include_ignored", "diff", "uncommitted", "staged"],
+        help = "Bundle only the files HEAD changed since its merge base with BASE"
+    )]
+    pub diff_base: Option<String>,
+
+    #[arg(
+        long = "diff",
+        value_name = "A..B",
+        group = "changes",
+        conflicts_with_all = ["git_ref", "branch", "no_git", "tracked_only", "include_ignored", "uncommitted", "staged"],
+        value_parser = diff_range_value,
+        help = "Bundle only the files changed between two refs, as A..B or A...B"
+    )]
+    pub diff: Option<String>,
+
+    #[arg(
+        long = "uncommitted",
+        action = ArgAction::SetTrue,
+        group = "changes",
+        conflicts_with_all = ["git_ref", "no_git"],
+        help = "Bundle only the files with unstaged changes in the working tree, including untracked files"
+    )]
+    pub uncommitted: bool,
+
+    #[arg(
+        long = "staged",
+        action = ArgAction::SetTrue,
+        group = "changes",
+        conflicts_with_all = ["git_ref", "no_git"],
+        help = "Bundle only the files with changes staged in the index"
+    )]
+    pub staged: bool,
+
+    #[arg(
+        long = "diff-patch",
+        action = ArgAction::SetTrue,
+        requires = "changes",
+        help = "Include the unified patch of each changed file in a <diff> element"
+    )]
+    pub diff_patch: bool,
+
+    #[arg(
+        long = "patch-only",
+        action = ArgAction::SetTrue,
+        requires = "changes",
+        help = "Leave out the content of changed files, keeping only their patches"
+    )]
+    pub patch_only: bool,
+
+    #[arg(
+        long = "with-imports",
+        action = ArgAction::SetTrue,
+        requires = "changes",
+        conflicts_with = "follow",
+        help = "Also bundle the local files the changed files import, as --follow does"
+    )]
+    pub with_imports: bool,
+
+    #[arg(
+        long = "history",
+        value_name = "N",
+        conflicts_with = "no_git",
+        help = "Append the last N commits that touched the bundled files in a <git_history> section"
+    )]
+    pub history: Option<usize>,
+
+    #[arg(
+        long = "last-commit",
+        action = ArgAction::SetTrue,
+        conflicts_with = "no_git",
+        help = "Record the last commit to touch each file in a last_commit attribute"
+    )]
+    pub last_commit: bool,
+
+    #[arg(
+        long = "hotspots",
+        action = ArgAction::SetTrue,
+        conflicts_with = "no_git",
+        help = "Rank the bundled files by commits, distinct authors and recent churn, and show the top files in the summary"
+    )]
+    pub hotspots: bool,
+
+    #[arg(
+        long = "hotspots-section",
+        action = ArgAction::SetTrue,
+        conflicts_with = "no_git",
+        help = "Add the hotspot ranking to the bundle in a <hotspots> section"
+    )]
+    pub hotspots_section: bool,
+
+    #[arg(
+        long = "order",
+        value_name = "ORDER",
+        value_parser = ORDER_VALUES,
+        help = "Order the files in the bundle by path, or with the riskiest hotspots first"
+    )]
+    pub order: Option<String>,
+
     #[arg(
         long = "file",
         short = 'f',
@@ -96,11 +213,42 @@ pub struct Flags {
     #[arg(
         short,
         long,
-        help = "GitHub personal access token (required for private repos and \
-                to pass rate limits)"
+        help = "Access token for private repositories and higher rate limits. \
+                Prefer BUNDLEREPO_TOKEN or --token-file, which keep it out of \
+                the shell history and process list"
     )]
     pub token: Option<String>,
 
+    #[arg(
+        long = "token-file",
+        value_name = "PATH",
+        help = "Read the access token from PATH, or from standard input for '-'",
+        conflicts_with = "token"
+    )]
+    pub token_file: Option<String>,
+
+    #[arg(
+        long = "ssh-key",
+        value_name = "PATH",
+        help = "Private SSH key to authenticate with, tried before the SSH agent and the default keys in ~/.ssh"
+    )]
+    pub ssh_key: Option<String>,
+
+    #[arg(
+        long = "offline",
+        action = ArgAction::SetTrue,
+        help = "Bundle a remote repository from its cached clone without fetching"
+    )]
+    pub offline: bool,
+
+    #[arg(
+        long = "no-cache",
+        action = ArgAction::SetTrue,
+        help = "Clone a remote repository into a temporary directory instead of the cache",
+        conflicts_with = "offline"
+    )]
+    pub no_cache: bool,
+
     #[arg(
         long = "version",
         short = 'V',
@@ -128,6 +276,133 @@ pub struct Flags {
     )]
     pub exclude: Option<Vec<String>>,
 
+    #[arg(
+        long = "path",
+        value_name = "PATH",
+        help = "Bundle only this path within the repository, relative to its root. Add :START-END to a file for just those lines, or ::SYMBOL for the definition of a Rust, Python, JavaScript or TypeScript symbol. Can be specified multiple times.",
+        action = ArgAction::Append
+    )]
+    pub paths: Option<Vec<String>>,
+
+    #[arg(
+        long = "files-from",
+        value_name = "PATH",
+        help = "Bundle only the files listed in PATH, or on standard input for '-'. Entries are separated by newlines or NUL characters.",
+        conflicts_with_all = ["paths", "packages"]
+    )]
+    pub files_from: Option<String>,
+
+    #[arg(
+        long = "package",
+        value_name = "NAME",
+        help = "Bundle only this Cargo or npm/pnpm workspace package, with the root manifest. Can be specified multiple times.",
+        action = ArgAction::Append
+    )]
+    pub packages: Option<Vec<String>>,
+
+    #[arg(
+        long = "with-path-deps",
+        action = ArgAction::SetTrue,
+        requires = "packages",
+        help = "Also bundle the local path dependencies of each --package"
+    )]
+    pub with_path_deps: bool,
+
+    #[arg(
+        long = "grep",
+        value_name = "PATTERN",
+        value_parser = parse_grep_pattern,
+        help = "Bundle only files whose text matches the regular expression PATTERN"
+    )]
+    pub grep: Option<String>,
+
+    #[arg(
+        long = "grep-context",
+        value_name = "LINES",
+        requires = "grep",
+        help = "Include only the lines matching --grep, with LINES lines either side"
+    )]
+    pub grep_context: Option<usize>,
+
+    #[arg(
+        long = "follow",
+        value_name = "SEED",
+        help = "Bundle only SEED and the local files it imports, following Rust modules, Python imports and relative JavaScript/TypeScript imports. Can be specified multiple times.",
+        action = ArgAction::Append
+    )]
+    pub follow: Option<Vec<String>>,
+
+    #[arg(
+        long = "lang",
+        value_name = "LANGUAGES",
+        value_delimiter = ',',
+        value_parser = parse_language,
+        help = "Bundle only files in these comma-separated languages (e.g. 'rust,toml')",
+        action = ArgAction::Append
+    )]
+    pub languages: Option<Vec<String>>,
+
+    #[arg(
+        long = "no-tests",
+        action = ArgAction::SetTrue,
+        help = "Leave out test files and directories"
+    )]
+    pub no_tests: bool,
+
+    #[arg(
+        long = "no-docs",
+        action = ArgAction::SetTrue,
+        help = "Leave out documentation directories"
+    )]
+    pub no_docs: bool,
+
+    #[arg(
+        long = "no-examples",
+        action = ArgAction::SetTrue,
+        help = "Leave out example directories"
+    )]
+    pub no_examples: bool,
+
+    #[arg(
+        long = "max-file-size",
+        value_name = "SIZE",
+        value_parser = parse_file_size,
+        help = "Skip files larger than SIZE (e.g. '500K' or '10MB')"
+    )]
+    pub max_file_size: Option<u64>,
+
+    #[arg(
+        long = "max-file-lines",
+        value_name = "LINES",
+        value_parser = parse_line_count,
+        help = "Skip files with more than LINES lines"
+    )]
+    pub max_file_lines: Option<usize>,
+
+    #[arg(
+        long = "modified-within",
+        value_name = "AGE",
+        value_parser = parse_age,
+        help = "Skip files not modified within AGE (e.g. '12h', '30d' or '2w')"
+    )]
+    pub modified_within: Option<u64>,
+
+    #[arg(
+        long = "truncate-lines",
+        value_name = "LINES",
+        value_parser = parse_line_count,
+        help = "Keep only the first and last LINES lines of longer files"
+    )]
+    pub truncate_lines: Option<usize>,
+
+    #[arg(
+        long = "truncate-tokens",
+        value_name = "TOKENS",
+        value_parser = parse_token_count,
+        help = "Keep only the first and last lines of files over TOKENS tokens"
+    )]
+    pub truncate_tokens: Option<usize>,
+
     #[arg(
         long = "utf8",
         short = 'u',
@@ -144,6 +419,92 @@ pub struct Flags {
         conflicts_with = "utf8",
     )]
     pub no_utf8: bool,
+
+    #[arg(
+        long = "no-bundlerepoignore",
+        action = ArgAction::SetTrue,
+        help = "Do not honour '.bundlerepoignore' files"
+    )]
+    pub no_bundlerepoignore: bool,
+
+    #[arg(
+        long = "tracked-only",
+        action = ArgAction::SetTrue,
+        help = "Bundle only files tracked by git, read from the index"
+    )]
+    pub tracked_only: bool,
+
+    #[arg(
+        long = "include-untracked",
+        action = ArgAction::SetTrue,
+        help = "Bundle untracked files as well as tracked ones, overriding configuration",
+        conflicts_with = "tracked_only"
+    )]
+    pub include_untracked: bool,
+
+    #[arg(
+        long = "include-ignored",
+        action = ArgAction::SetTrue,
+        help = "Also bundle files that git ignores"
+    )]
+    pub include_ignored: bool,
+
+    #[arg(
+        long = "no-git",
+        action = ArgAction::SetTrue,
+        help = "Bundle a local directory as plain files, without requiring a git repository",
+        conflicts_with_all = ["tracked_only", "include_ignored", "from_root"]
+    )]
+    pub no_git: bool,
+
+    #[arg(
+        long = "from-root",
+        action = ArgAction::SetTrue,
+        help = "Bundle the whole repository from its root, even when started in a subdirectory"
+    )]
+    pub from_root: bool,
+
+    #[arg(
+        long = "dry-run",
+        action = ArgAction::SetTrue,
+        help = "List every candidate file and whether it would be bundled, without writing output"
+    )]
+    pub dry_run: bool,
+
+    #[arg(
+        long = "explain",
+        value_name = "PATH",
+        help = "Explain whether a single repository path would be bundled, and why"
+    )]
+    pub explain: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    #[command(about = "Manage the cached clones of remote repositories")]
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    #[command(about = "List the cached clones with their size and last use")]
+    List,
+    #[command(about = "Remove cached clones")]
+    Prune {
+        #[arg(
+            long = "older-than",
+            value_name = "AGE",
+            value_parser = parse_age,
+            help = "Only remove clones not used within AGE, e.g. '30d'"
+        )]
+        older_than: Option<u64>,
+    },
 }
 
 pub fn version_info() -> String {
@@ -0,0 +1,463 @@
+use crate::filelist::{
+    ExclusionMatcher, custom_ignore_filenames, list_files_in_repo,
+    lists_git_states, path_scopes, repository_relative_path,
+    walk_ignore_rules,
+};
+use crate::follow::{DependencyGraph, dependency_graph};
+use crate::git_files::GitFiles;
+use crate::grep::grep_pattern;
+use crate::ignore_rules::{IgnoreMatch, IgnoreRules};
+use crate::language::{Deselection, FileSelection};
+use crate::limits::{FileLimits, SkipReason};
+use crate::progress::ProgressReporter;
+use crate::source::{EntryKind, FileSource};
+use crate::structs::Params;
+use crate::text_processing::{
+    BinaryReason, ProcessedFile, read_classify_and_decode,
+};
+use crate::timings::ProcessingTimings;
+use regex::Regex;
+use std::fmt;
+use std::io::{self, Write};
+
+/// The rule that keeps a path out of the bundle entirely.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum Exclusion {
+    IgnoreFile(IgnoreMatch),
+    Pattern(String),
+    SymbolicLink,
+    GitIgnored,
+    Untracked,
+    Deselected(Deselection),
+    Unmatched,
+    Unreachable,
+    Unchanged,
+}
+
+impl fmt::Display for Exclusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IgnoreFile(found) => write!(f, "ignored by {found}"),
+            Self::Pattern(pattern) => {
+                write!(f, "matches exclude pattern '{pattern}'")
+            }
+            Self::SymbolicLink => {
+                f.write_str("symbolic links are not followed")
+            }
+            Self::GitIgnored => f.write_str("ignored by git"),
+            Self::Untracked => {
+                f.write_str("not tracked by git, excluded by --tracked-only")
+            }
+            Self::Deselected(deselection) => write!(f, "{deselection}"),
+            Self::Unmatched => f.write_str("text does not match --grep"),
+            Self::Unreachable => {
+                f.write_str("not imported from a --follow seed")
+            }
+            Self::Unchanged => f.write_str("not among the changed files"),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum Decision {
+    Include,
+    Omit(BinaryReason),
+    Skip(SkipReason),
+    Exclude(Exclusion),
+    Unreadable(String),
+}
+
+impl Decision {
+    const fn status(&self) -> &'static str {
+        match self {
+            Self::Include => "include",
+            Self::Omit(_) => "omit",
+            Self::Skip(_) => "skip",
+            Self::Exclude(_) => "exclude",
+            Self::Unreadable(_) => "error",
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct Candidate {
+    pub(crate) path: String,
+    pub(crate) is_dir: bool,
+    pub(crate) decision: Decision,
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let suffix = if self.is_dir { "/" } else { "" };
+        write!(f, "{:<7}  {}{suffix}", self.decision.status(), self.path)?;
+        match &self.decision {
+            Decision::Include => Ok(()),
+            Decision::Omit(reason) => {
+                write!(f, "  (content omitted: {reason})")
+            }
+            Decision::Skip(reason) => {
+                write!(f, "  (skipped: {}, {reason})", reason.as_str())
+            }
+            Decision::Exclude(exclusion) => write!(f, "  ({exclusion})"),
+            Decision::Unreadable(error) => write!(f, "  ({error})"),
+        }
+    }
+}
+
+/// The git file states consulted when the listing is built from git rather
+/// than by walking the working tree.
+struct GitStates {
+    files: GitFiles,
+    rules: IgnoreRules<'static>,
+    tracked_only: bool,
+    include_ignored: bool,
+}
+
+impl GitStates {
+    fn exclusion(
+        &mut self,
+        relative_path: &str,
+        is_dir: bool,
+    ) -> Option<Exclusion> {
+        let tracked = if is_dir {
+            self.files.contains_tracked(relative_path)
+        } else {
+            self.files.is_tracked(relative_path)
+        };
+        if tracked {
+            return None;
+        }
+        if self.files.is_ignored(relative_path) {
+            return (!self.include_ignored).then(|| {
+                self.rules
+                    .ignored(relative_path, is_dir)
+                    .map_or(Exclusion::GitIgnored, Exclusion::IgnoreFile)
+            });
+        }
+        // An untracked directory may still hold ignored files to bundle.
+        (self.tracked_only && !(is_dir && self.include_ignored))
+            .then_some(Exclusion::Untracked)
+    }
+}
+
+struct Explainer<'a, S: FileSource + ?Sized> {
+    source: &'a S,
+    rules: IgnoreRules<'a>,
+    git: Option<GitStates>,
+    exclusions: ExclusionMatcher,
+    selection: FileSelection,
+    limits: FileLimits,
+    grep: Option<Regex>,
+    graph: Option<DependencyGraph>,
+    utf8: bool,
+    timings: ProcessingTimings,
+}
+
+impl<'a, S: FileSource + ?Sized> Explainer<'a, S> {
+    fn new(source: &'a S, flags: &Params) -> io::Result<Self> {
+        let custom_filenames = custom_ignore_filenames(flags);
+        // A commit's tree has no file states, so it is walked like a plain
+        // directory.
+        let git_root = source.directory().filter(|_| lists_git_states(flags));
+        let (rules, git) = if let Some(repo_path) = git_root {
+            let git = GitStates {
+                files: GitFiles::open(repo_path).map_err(io::Error::other)?,
+                rules: IgnoreRules::new(repo_path, &[]),
+                tracked_only: flags.tracked_only,
+                include_ignored: flags.include_ignored,
+            };
+            (
+                IgnoreRules::without_git_ignores(repo_path, &custom_filenames),
+                Some(git),
+            )
+        } else {
+            (walk_ignore_rules(source, flags), None)
+        };
+        Ok(Self {
+            source,
+            rules,
+            git,
+            exclusions: ExclusionMatcher::new(
+                flags.extend_exclude.as_deref(),
+                flags.exclude.as_deref(),
+            ),
+            selection: FileSelection::new(flags)?,
+            limits: FileLimits::new(flags),
+            grep: grep_pattern(flags)?,
+            graph: dependency_graph(source, flags)?,
+            utf8: flags.utf8,
+            timings: ProcessingTimings::default(),
+        })
+    }
+
+    fn selection_exclusion(
+        &self,
+        relative_path: &str,
+        is_dir: bool,
+    ) -> Option<Exclusion> {
+        let deselection = if is_dir {
+            relative_path
+                .split('/')
+                .find_map(|name| self.selection.excluded_directory(name))
+                .map(Deselection::Category)
+        } else {
+            self.selection.deselection(relative_path)
+        };
+        deselection.map(Exclusion::Deselected)
+    }
+
+    fn git_exclusion(
+        &mut self,
+        relative_path: &str,
+        is_dir: bool,
+    ) -> Option<Exclusion> {
+        self.git
+            .as_mut()
+            .and_then(|git| git.exclusion(relative_path, is_dir))
+    }
+
+    fn pattern_exclusion(
+        &self,
+        relative_path: &str,
+        is_dir: bool,
+    ) -> Option<Exclusion> {
+        // Exclude patterns are unanchored, so a pattern matching a directory
+        // with its trailing slash matches every path beneath it as well.
+        let candidate = if is_dir {
+            format!("{relative_path}/")
+        } else {
+            relative_path.to_string()
+        };
+        self.exclusions
+            .matching_pattern(&candidate)
+            .map(|pattern| Exclusion::Pattern(pattern.to_string()))
+    }
+
+    fn classify(&mut self, relative_path: &str) -> Decision {
+        if self
+            .graph
+            .as_ref()
+            .is_some_and(|graph| !graph.contains(relative_path))
+        {
+            return Decision::Exclude(Exclusion::Unreachable);
+        }
+        match self.limits.check(self.source, relative_path) {
+            Ok(Some(reason)) => return Decision::Skip(reason),
+            Ok(None) => {}
+            Err(error) => return Decision::Unreadable(error.to_string()),
+        }
+        match read_classify_and_decode(
+            self.source,
+            relative_path,
+            self.utf8,
+            &mut self.timings,
+        ) {
+            Ok(ProcessedFile::Text(decoded))
+                if self.grep.as_ref().is_some_and(|pattern| {
+                    !pattern.is_match(&decoded.text)
+                }) =>
+            {
+                Decision::Exclude(Exclusion::Unmatched)
+            }
+            Ok(ProcessedFile::Text(_)) => Decision::Include,
+            Ok(ProcessedFile::Binary(reason)) => Decision::Omit(reason),
+            Err(error) => Decision::Unreadable(error.to_string()),
+        }
+    }
+
+    /// Decides a `--path` scope named directly. As in the directory walker,
+    /// ignore files do not apply to the scope itself, only to its contents.
+    fn scope(
+        &mut self,
+        scope: &str,
+        candidates: &mut Vec<Candidate>,
+    ) -> io::Result<()> {
+        let kind = self.source.kind(scope)?;
+        let is_dir = kind == EntryKind::Directory;
+        let exclusion = if kind == EntryKind::Symlink {
+            Some(Exclusion::SymbolicLink)
+        } else if is_dir {
+            None
+        } else {
+            self.pattern_exclusion(scope, false)
+                .or_else(|| self.selection_exclusion(scope, false))
+                .or_else(|| self.git_exclusion(scope, false))
+        };
+
+        match exclusion {
+            Some(exclusion) => candidates.push(Candidate {
+                path: scope.to_string(),
+                is_dir,
+                decision: Decision::Exclude(exclusion),
+            }),
+            None if is_dir => self.walk(scope, candidates)?,
+            None => {
+                let decision = self.classify(scope);
+                candidates.push(Candidate {
+                    path: scope.to_string(),
+                    is_dir,
+                    decision,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn walk(
+        &mut self,
+        directory: &str,
+        candidates: &mut Vec<Candidate>,
+    ) -> io::Result<()> {
+        let mut entries = self.source.entries(directory)?;
+        entries.sort();
+
+        for (name, kind) in entries {
+            let path = if directory.is_empty() {
+                name
+            } else {
+                format!("{directory}/{name}")
+            };
+            let is_dir = kind == EntryKind::Directory;
+            let exclusion = if kind == EntryKind::Symlink {
+                Some(Exclusion::SymbolicLink)
+            } else {
+                self.rules
+                    .matched(&path, is_dir)
+                    .filter(|found| !found.whitelist)
+                    .map(Exclusion::IgnoreFile)
+                    .or_else(|| self.pattern_exclusion(&path, is_dir))
+                    .or_else(|| self.selection_exclusion(&path, is_dir))
+                    .or_else(|| self.git_exclusion(&path, is_dir))
+            };
+
+            match exclusion {
+                Some(exclusion) => candidates.push(Candidate {
+                    path,
+                    is_dir,
+                    decision: Decision::Exclude(exclusion),
+                }),
+                None if is_dir => self.walk(&path, candidates)?,
+                None => {
+                    let decision = self.classify(&path);
+                    candidates.push(Candidate {
+                        path,
+                        is_dir,
+                        decision,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lists every candidate path under `repo_path`, or under each `--path`
+/// scope, with the decision the bundle would make for it. Excluded
+/// directories are reported once and not entered. A `--files-from` list is
+/// taken as given, like the listing itself.
+pub(crate) fn dry_run_candidates(
+    source: &(impl FileSource + ?Sized),
+    flags: &Params,
+) -> io::Result<Vec<Candidate>> {
+    let mut explainer = Explainer::new(source, flags)?;
+    if flags.files_from.is_some() {
+        let file_list = list_files_in_repo(source, flags)?;
+        let listed = file_list
+            .files
+            .into_iter()
+            .chain(file_list.skipped.into_iter().map(|skipped| skipped.path));
+        let mut candidates = listed
+            .map(|path| Candidate {
+                decision: explainer.classify(&path),
+                path,
+                is_dir: false,
+            })
+            .chain(file_list.missing.into_iter().map(|path| Candidate {
+                path,
+                is_dir: false,
+                decision: Decision::Unreadable(
+                    "does not exist in the repository".to_string(),
+                ),
+            }))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|left, right| left.path.cmp(&right.path));
+        return Ok(candidates);
+    }
+
+    let scopes = path_scopes(source, flags)?;
+    let mut candidates = Vec::new();
+    if scopes.is_empty() {
+        explainer.walk("", &mut candidates)?;
+    }
+    for scope in scopes {
+        explainer.scope(&scope, &mut candidates)?;
+    }
+    Ok(candidates)
+}
+
+/// Explains the decision for a single path, including exclusions inherited
+/// from an ignored parent directory.
+pub(crate) fn explain_path(
+    source: &(impl FileSource + ?Sized),
+    path: &str,
+    flags: &Params,
+) -> io::Result<Candidate> {
+    let path = repository_relative_path(source, path)?;
+    let kind = source.kind(&path).map_err(|error| {
+        io::Error::new(
+            error.kind(),
+            format!("'{path}' does not exist in the repository"),
+        )
+    })?;
+    let is_dir = kind == EntryKind::Directory;
+    let mut explainer = Explainer::new(source, flags)?;
+
+    let exclusion = if kind == EntryKind::Symlink {
+        Some(Exclusion::SymbolicLink)
+    } else {
+        explainer
+            .rules
+            .ignored(&path, is_dir)
+            .map(Exclusion::IgnoreFile)
+            .or_else(|| explainer.pattern_exclusion(&path, is_dir))
+            .or_else(|| explainer.selection_exclusion(&path, is_dir))
+            .or_else(|| explainer.git_exclusion(&path, is_dir))
+    };
+    let decision = match exclusion {
+        Some(exclusion) => Decision::Exclude(exclusion),
+        None if is_dir => Decision::Include,
+        None => explainer.classify(&path),
+    };
+
+    Ok(Candidate {
+        path,
+        is_dir,
+        decision,
+    })
+}
+
+pub(crate) fn write_candidates<N: Write, D: Write>(
+    reporter: &mut ProgressReporter<N, D>,
+    candidates: &[Candidate],
+) -> io::Result<()> {
+    for candidate in candidates {
+        reporter.listing(&candidate.to_string())?;
+    }
+    let count = |status: &str| {
+        candidates
+            .iter()
+            .filter(|candidate| candidate.decision.status() == status)
+            .count()
+    };
+    reporter.listing(&format!(
+        "\n{} included, {} omitted, {} skipped, {} excluded",
+        count("include"),
+        count("omit"),
+        count("skip"),
+        count("exclude"),
+    ))
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/dry_run.rs"]
+mod tests;
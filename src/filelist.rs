@@ -1,10 +1,25 @@
+use crate::changes::ChangeSet;
+use crate::git_files::GitFiles;
+use crate::history::GitHistory;
+use crate::ignore_rules::IgnoreRules;
+use crate::language::FileSelection;
+use crate::limits::{FileLimits, SkipReason};
+use crate::path_spec::path_spec;
+use crate::source::{EntryKind, FileSource};
+use crate::structs::Params;
+use crate::workspace::package_scopes;
 use ignore::WalkBuilder;
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read};
 use std::path::{Component, Path, PathBuf};
 
-const DEFAULT_EXCLUDE_PATTERNS: [&str; 8] = [
+pub(crate) const BUNDLEREPO_IGNORE_FILENAME: &str = ".bundlerepoignore";
+
+const DEFAULT_EXCLUDE_PATTERNS: [&str; 9] = [
     r"(?i)\.gitignore",
+    r"(?i)\.bundlerepoignore$",
     r"(?i)renovate\.json",
     r"(?i)requirement.*\.txt",
     r"(?i)\.lock$",
@@ -17,6 +32,8 @@ const DEFAULT_EXCLUDE_PATTERNS: [&str; 8] = [
 #[derive(Default)]
 pub struct FolderNode {
     pub files: Vec<String>,
+    pub skipped: Vec<(String, SkipReason)>,
+    pub deleted: Vec<String>,
     pub subfolders: HashMap<String, FolderNode>,
 }
 
@@ -24,15 +41,181 @@ pub struct FolderNode {
 pub struct FileTree {
     pub folder_node: FolderNode,
     pub file_paths: Vec<String>, // Add a list to track file paths for <repository_files>
+    pub changes: ChangeSet,
+    pub history: GitHistory,
+    pub bundlerepoignore: bool,
+}
+
+/// A file left out of the bundle by a size, line count or age limit. It is
+/// still listed in the repository structure.
+#[derive(Debug, PartialEq)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: SkipReason,
+}
+
+/// The files to bundle, the files skipped by limits, and any `--files-from`
+/// entries that are not files in the repository.
+#[derive(Debug, Default, PartialEq)]
+pub struct FileList {
+    pub files: Vec<String>,
+    pub skipped: Vec<SkippedFile>,
+    pub missing: Vec<String>,
+    /// Whether a `.bundlerepoignore` file was found and applied.
+    pub bundlerepoignore: bool,
+}
+
+impl FileList {
+    fn push(
+        &mut self,
+        source: &(impl FileSource + ?Sized),
+        limits: &FileLimits,
+        path: String,
+    ) {
+        // Unreadable metadata is reported when the file itself is read.
+        match limits.check(source, &path).ok().flatten() {
+            Some(reason) => self.skipped.push(SkippedFile { path, reason }),
+            None => self.files.push(path),
+        }
+    }
 }
 
-fn repository_path(path: &Path) -> String {
+/// Joins the components of a relative path with `/` separators.
+pub(crate) fn repository_path(path: &Path) -> String {
     path.components()
         .map(|component| component.as_os_str().to_string_lossy())
         .collect::<Vec<_>>()
         .join("/")
 }
 
+/// Converts a user-supplied path into a repository-relative path with `/`
+/// separators, rejecting paths that leave the repository.
+pub(crate) fn repository_relative_path(
+    source: &(impl FileSource + ?Sized),
+    input: &str,
+) -> io::Result<String> {
+    #[cfg(windows)]
+    let input = &input.replace('\\', "/");
+    let outside_repository = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{input}' is outside the repository"),
+        )
+    };
+
+    let path = Path::new(input);
+    let relative = if path.is_absolute() {
+        let root = source
+            .directory()
+            .ok_or_else(outside_repository)?
+            .canonicalize()?;
+        let absolute =
+            path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        absolute
+            .strip_prefix(&root)
+            .map_err(|_| outside_repository())?
+            .to_path_buf()
+    } else {
+        path.to_path_buf()
+    };
+
+    let mut components = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::CurDir => {}
+            Component::Normal(part) => {
+                components.push(part.to_string_lossy().into_owned());
+            }
+            Component::ParentDir => {
+                components.pop().ok_or_else(outside_repository)?;
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(outside_repository());
+            }
+        }
+    }
+    if components.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{input}' does not name a path inside the repository"),
+        ));
+    }
+    Ok(components.join("/"))
+}
+
+/// Joins a relative path onto a repository directory, resolving `.` and
+/// `..`. Returns `None` for paths that leave the repository.
+pub(crate) fn join_repository_path(
+    directory: &str,
+    relative: &str,
+) -> Option<String> {
+    let mut parts = directory
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    for component in Path::new(relative).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::Normal(part) => {
+                parts.push(part.to_string_lossy().into_owned());
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Returns `true` if `path` is `scope` itself or lies below it.
+pub(crate) fn is_within_scope(path: &str, scope: &str) -> bool {
+    path.strip_prefix(scope)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Resolves the `--path` scopes to repository-relative paths, dropping any
+/// scope that lies inside another. An empty list selects the whole repository.
+/// Line ranges are dropped here; see [`crate::path_spec::line_ranges`].
+pub(crate) fn path_scopes(
+    source: &(impl FileSource + ?Sized),
+    flags: &Params,
+) -> io::Result<Vec<String>> {
+    let mut scopes = Vec::new();
+    for value in flags.paths.iter().flatten() {
+        let spec = path_spec(value)?;
+        let path = spec.path.as_str();
+        let scope = repository_relative_path(source, path)?;
+        let Ok(kind) = source.kind(&scope) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("'{path}' does not exist in the repository"),
+            ));
+        };
+        if spec.is_partial() && kind == EntryKind::Directory {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("'{value}' selects lines of a folder, not a file"),
+            ));
+        }
+        scopes.push(scope);
+    }
+    scopes.extend(package_scopes(source, flags)?);
+    scopes.sort();
+    scopes.dedup();
+
+    Ok(scopes
+        .iter()
+        .filter(|scope| {
+            !scopes
+                .iter()
+                .any(|other| other != *scope && is_within_scope(scope, other))
+        })
+        .cloned()
+        .collect())
+}
+
 fn literal_exclude_pattern(pattern: &str) -> String {
     #[cfg(windows)]
     let pattern = pattern.replace('\\', "/");
@@ -42,31 +225,31 @@ fn literal_exclude_pattern(pattern: &str) -> String {
     format!(r"(?i){}", regex::escape(&pattern))
 }
 
-struct ExclusionMatcher {
-    patterns: Vec<Regex>,
+pub(crate) struct ExclusionMatcher {
+    patterns: Vec<(String, Regex)>,
 }
 
 impl ExclusionMatcher {
-    fn new(
+    pub(crate) fn new(
         extend_exclude: Option<&[String]>,
         exclude: Option<&[String]>,
     ) -> Self {
         let patterns = if let Some(patterns) = exclude {
             patterns
                 .iter()
-                .map(|pattern| literal_exclude_pattern(pattern))
+                .map(|pattern| {
+                    (pattern.clone(), literal_exclude_pattern(pattern))
+                })
                 .collect()
         } else {
             let mut patterns = DEFAULT_EXCLUDE_PATTERNS
                 .into_iter()
-                .map(String::from)
+                .map(|pattern| (pattern.to_string(), pattern.to_string()))
                 .collect::<Vec<_>>();
             if let Some(extend_patterns) = extend_exclude {
-                patterns.extend(
-                    extend_patterns
-                        .iter()
-                        .map(|pattern| literal_exclude_pattern(pattern)),
-                );
+                patterns.extend(extend_patterns.iter().map(|pattern| {
+                    (pattern.clone(), literal_exclude_pattern(pattern))
+                }));
             }
             patterns
         };
@@ -74,43 +257,192 @@ impl ExclusionMatcher {
         Self {
             patterns: patterns
                 .into_iter()
-                .map(|pattern| {
-                    Regex::new(&pattern).unwrap_or_else(|error| {
+                .map(|(display, pattern)| {
+                    let regex = Regex::new(&pattern).unwrap_or_else(|error| {
                         eprintln!(
                             "Warning: Invalid regex pattern '{pattern}': {error}"
                         );
                         Regex::new(r"^$").unwrap()
-                    })
+                    });
+                    (display, regex)
                 })
                 .collect(),
         }
     }
 
-    fn matches(&self, repository_path: &str) -> bool {
+    pub(crate) fn matches(&self, repository_path: &str) -> bool {
+        self.matching_pattern(repository_path).is_some()
+    }
+
+    /// Returns the pattern, as it was configured, that excludes the path.
+    pub(crate) fn matching_pattern(
+        &self,
+        repository_path: &str,
+    ) -> Option<&str> {
         self.patterns
             .iter()
-            .any(|pattern| pattern.is_match(repository_path))
+            .find(|(_, pattern)| pattern.is_match(repository_path))
+            .map(|(display, _)| display.as_str())
+    }
+}
+
+/// Returns the ignore filenames honoured in addition to `.ignore` and git's
+/// own ignore files.
+pub(crate) fn custom_ignore_filenames(flags: &Params) -> Vec<&'static str> {
+    if flags.bundlerepoignore {
+        vec![BUNDLEREPO_IGNORE_FILENAME]
+    } else {
+        Vec::new()
     }
 }
 
+/// Splits a `--files-from` list into its entries. Lists separated by NUL
+/// characters, as written by `git ls-files -z` or `find -print0`, are
+/// detected automatically.
+pub(crate) fn parse_file_list(content: &str) -> Vec<String> {
+    let separator = if content.contains('\0') { '\0' } else { '\n' };
+    content
+        .split(separator)
+        .map(|entry| entry.strip_suffix('\r').unwrap_or(entry))
+        .filter(|entry| !entry.trim().is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Reads a `--files-from` list from a file, or from standard input for `-`.
+pub(crate) fn read_files_from(source: &str) -> io::Result<Vec<String>> {
+    let content = if source == "-" {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        content
+    } else {
+        fs::read_to_string(source).map_err(|error| {
+            io::Error::new(
+                error.kind(),
+                format!("cannot read file list '{source}': {error}"),
+            )
+        })?
+    };
+    Ok(parse_file_list(&content))
+}
+
+/// Builds the file list from `--files-from` entries without walking the
+/// repository. Entries outside the repository are rejected, and entries that
+/// are not files in it are returned as missing.
+fn listed_files(
+    source: &(impl FileSource + ?Sized),
+    flags: &Params,
+    entries: &[String],
+) -> io::Result<FileList> {
+    let limits = FileLimits::new(flags);
+    let mut file_list = FileList::default();
+    let mut seen = HashSet::new();
+    for entry in entries {
+        let spec = path_spec(entry)?;
+        let path = repository_relative_path(source, &spec.path)?;
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        if source.has_file(&path) {
+            file_list.push(source, &limits, path);
+        } else {
+            file_list.missing.push(path);
+        }
+    }
+    Ok(file_list)
+}
+
+/// Returns the ignore rules the walk of `source` applies.
+pub(crate) fn walk_ignore_rules<'a, S: FileSource + ?Sized>(
+    source: &'a S,
+    flags: &Params,
+) -> IgnoreRules<'a> {
+    let custom_filenames = custom_ignore_filenames(flags);
+    match source.directory() {
+        Some(repo_path) if flags.no_git => {
+            IgnoreRules::plain_directory(repo_path, &custom_filenames)
+        }
+        Some(repo_path) => IgnoreRules::new(repo_path, &custom_filenames),
+        None => IgnoreRules::in_source(source, &custom_filenames),
+    }
+}
+
+/// Returns `true` when the file list is built from git's file states rather
+/// than by walking the working tree.
+pub(crate) const fn lists_git_states(flags: &Params) -> bool {
+    flags.tracked_only || flags.include_ignored
+}
+
 pub fn list_files_in_repo(
-    repo_path: &Path,
-    extend_exclude: Option<&[String]>,
-    exclude: Option<&[String]>,
-) -> Vec<String> {
-    let mut file_list = Vec::new();
-    let exclusions = ExclusionMatcher::new(extend_exclude, exclude);
+    source: &(impl FileSource + ?Sized),
+    flags: &Params,
+) -> io::Result<FileList> {
+    if let Some(entries) = &flags.files_from {
+        return listed_files(source, flags, entries);
+    }
+    let exclusions = ExclusionMatcher::new(
+        flags.extend_exclude.as_deref(),
+        flags.exclude.as_deref(),
+    );
+    let scopes = path_scopes(source, flags)?;
+    let selection = FileSelection::new(flags)?;
+    let Some(repo_path) = source.directory() else {
+        return list_source_files(
+            source,
+            flags,
+            &scopes,
+            &exclusions,
+            &selection,
+        );
+    };
+    if lists_git_states(flags) {
+        return list_git_files(
+            repo_path,
+            flags,
+            &scopes,
+            &exclusions,
+            &selection,
+        );
+    }
 
-    let walker = WalkBuilder::new(repo_path)
+    let limits = FileLimits::new(flags);
+    let mut file_list = FileList {
+        bundlerepoignore: flags.bundlerepoignore
+            && scopes.iter().any(|scope| {
+                Path::new(scope).ancestors().skip(1).any(|directory| {
+                    has_bundlerepoignore(&repo_path.join(directory))
+                })
+            }),
+        ..FileList::default()
+    };
+    let mut roots = scopes.iter().map(|scope| repo_path.join(scope));
+    let mut builder = WalkBuilder::new(
+        roots.next().unwrap_or_else(|| repo_path.to_path_buf()),
+    );
+    for root in roots {
+        builder.add(root);
+    }
+    // A `--no-git` directory honours its own `.gitignore` files, but not
+    // those of any repository around it or git's exclude files.
+    builder
         .hidden(false)
+        .parents(!flags.no_git)
+        .require_git(!flags.no_git)
         .git_ignore(true)
-        .git_exclude(true)
-        .git_global(true)
-        .build();
+        .git_exclude(!flags.no_git)
+        .git_global(!flags.no_git);
+    for filename in custom_ignore_filenames(flags) {
+        builder.add_custom_ignore_filename(filename);
+    }
+    let walker = builder.build();
 
     for result in walker {
         match result {
             Ok(entry) => {
+                if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+                    file_list.bundlerepoignore |= flags.bundlerepoignore
+                        && has_bundlerepoignore(entry.path());
+                }
                 if !entry.file_type().is_some_and(|ft| ft.is_file()) {
                     continue;
                 }
@@ -121,47 +453,182 @@ pub fn list_files_in_repo(
                     Err(_) => continue,
                 };
 
-                if exclusions.matches(&relative_path) {
+                if exclusions.matches(&relative_path)
+                    || selection.deselection(&relative_path).is_some()
+                {
                     continue;
                 }
 
-                file_list.push(relative_path);
+                file_list.push(repo_path, &limits, relative_path);
             }
             Err(err) => eprintln!("Error: {}", err),
         }
     }
 
-    file_list
+    Ok(file_list)
 }
 
-pub fn group_files_by_directory(file_list: Vec<String>) -> FileTree {
-    let mut root = FolderNode::default();
-    let mut file_paths = Vec::new(); // To store the relative paths of each file
+/// Lists the files of a source without a directory on disk, such as the
+/// tree of a commit. Its ignore files apply as in a `--no-git` walk, and a
+/// scope named directly is not checked against them.
+fn list_source_files(
+    source: &(impl FileSource + ?Sized),
+    flags: &Params,
+    scopes: &[String],
+    exclusions: &ExclusionMatcher,
+    selection: &FileSelection,
+) -> io::Result<FileList> {
+    let mut rules = walk_ignore_rules(source, flags);
+    let mut paths = Vec::new();
+    if scopes.is_empty() {
+        walk_source(source, "", &mut rules, &mut paths)?;
+    }
+    for scope in scopes {
+        match source.kind(scope)? {
+            EntryKind::Directory => {
+                walk_source(source, scope, &mut rules, &mut paths)?;
+            }
+            EntryKind::File => paths.push(scope.clone()),
+            EntryKind::Symlink => {}
+        }
+    }
+
+    let limits = FileLimits::new(flags);
+    let mut file_list = FileList::default();
+    for path in paths {
+        if !exclusions.matches(&path) && selection.deselection(&path).is_none()
+        {
+            file_list.push(source, &limits, path);
+        }
+    }
+    file_list.bundlerepoignore = rules.has_read(BUNDLEREPO_IGNORE_FILENAME);
+    Ok(file_list)
+}
+
+/// Collects the files below `directory` that no ignore file excludes, in
+/// name order. Symbolic links are not followed.
+fn walk_source(
+    source: &(impl FileSource + ?Sized),
+    directory: &str,
+    rules: &mut IgnoreRules,
+    paths: &mut Vec<String>,
+) -> io::Result<()> {
+    let mut entries = source.entries(directory)?;
+    entries.sort();
+    for (name, kind) in entries {
+        let Some(path) = join_repository_path(directory, &name) else {
+            continue;
+        };
+        let is_dir = kind == EntryKind::Directory;
+        if kind == EntryKind::Symlink
+            || rules
+                .matched(&path, is_dir)
+                .is_some_and(|found| !found.whitelist)
+        {
+            continue;
+        }
+        if is_dir {
+            walk_source(source, &path, rules, paths)?;
+        } else {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
 
-    for file_path in file_list {
-        let path = PathBuf::from(&file_path);
-        let path_components: Vec<Component> = path.components().collect();
+fn list_git_files(
+    repo_path: &Path,
+    flags: &Params,
+    scopes: &[String],
+    exclusions: &ExclusionMatcher,
+    selection: &FileSelection,
+) -> io::Result<FileList> {
+    let git_files = GitFiles::open(repo_path).map_err(io::Error::other)?;
+    let mut rules = IgnoreRules::without_git_ignores(
+        repo_path,
+        &custom_ignore_filenames(flags),
+    );
 
-        let mut current_node = &mut root;
-        for component in path_components.iter().take(path_components.len() - 1)
+    let limits = FileLimits::new(flags);
+    let mut file_list = FileList::default();
+    for path in git_files
+        .list(!flags.tracked_only, flags.include_ignored)
+        .map_err(io::Error::other)?
+    {
+        if (scopes.is_empty()
+            || scopes.iter().any(|scope| is_within_scope(&path, scope)))
+            && !exclusions.matches(&path)
+            && selection.deselection(&path).is_none()
+            && rules.ignored(&path, false).is_none()
         {
-            let folder_name =
-                component.as_os_str().to_string_lossy().to_string();
-            current_node =
-                current_node.subfolders.entry(folder_name).or_default();
+            file_list.push(repo_path, &limits, path);
         }
+    }
+    file_list.bundlerepoignore = rules.has_read(BUNDLEREPO_IGNORE_FILENAME);
+    Ok(file_list)
+}
+
+fn has_bundlerepoignore(directory: &Path) -> bool {
+    directory.join(BUNDLEREPO_IGNORE_FILENAME).is_file()
+}
+
+/// Returns the folder node for the parent directory of `path`, creating
+/// folders as needed, together with the file name.
+fn parent_folder<'a>(
+    root: &'a mut FolderNode,
+    path: &str,
+) -> Option<(&'a mut FolderNode, String)> {
+    let path = PathBuf::from(path);
+    let path_components: Vec<Component> = path.components().collect();
+    let file_name = path_components.last()?;
 
-        if let Some(file_name) = path_components.last() {
-            current_node
-                .files
-                .push(file_name.as_os_str().to_string_lossy().to_string());
+    let mut current_node = root;
+    for component in path_components.iter().take(path_components.len() - 1) {
+        let folder_name = component.as_os_str().to_string_lossy().to_string();
+        current_node = current_node.subfolders.entry(folder_name).or_default();
+    }
+    Some((
+        current_node,
+        file_name.as_os_str().to_string_lossy().to_string(),
+    ))
+}
+
+pub fn group_files_by_directory(file_list: FileList) -> FileTree {
+    let mut root = FolderNode::default();
+    let mut file_paths = Vec::new(); // To store the relative paths of each file
+
+    for file_path in file_list.files {
+        if let Some((folder, file_name)) = parent_folder(&mut root, &file_path)
+        {
+            folder.files.push(file_name);
             file_paths.push(file_path); // Store the full relative path
         }
     }
+    for skipped in file_list.skipped {
+        if let Some((folder, file_name)) =
+            parent_folder(&mut root, &skipped.path)
+        {
+            folder.skipped.push((file_name, skipped.reason));
+        }
+    }
 
     FileTree {
         folder_node: root,
         file_paths,
+        changes: ChangeSet::new(),
+        history: GitHistory::default(),
+        bundlerepoignore: file_list.bundlerepoignore,
+    }
+}
+
+/// Lists a file deleted by a `--diff` in the structure and among the files,
+/// where it is written without content.
+pub(crate) fn add_deleted_file(file_tree: &mut FileTree, path: &str) {
+    if let Some((folder, file_name)) =
+        parent_folder(&mut file_tree.folder_node, path)
+    {
+        folder.deleted.push(file_name);
+        file_tree.file_paths.push(path.to_string());
     }
 }
 
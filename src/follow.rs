@@ -0,0 +1,311 @@
+use crate::filelist::{
+    FileList, join_repository_path, repository_relative_path,
+};
+use crate::language::language_for;
+use crate::source::FileSource;
+use crate::structs::Params;
+use regex::Regex;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::io;
+use std::sync::LazyLock;
+
+static RUST_MOD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*(?:#\[[^\]]*\]\s*)*(?:pub(?:\([^)]*\))?\s+)?mod\s+(?:r#)?([A-Za-z_][A-Za-z0-9_]*)\s*;",
+    )
+    .unwrap()
+});
+static RUST_PATH: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"#\[\s*path\s*=\s*"([^"]+)"\s*\]"#).unwrap()
+});
+static PYTHON_IMPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*import\s+([A-Za-z0-9_.,\s]+?)\s*(?:#.*)?$").unwrap()
+});
+static PYTHON_FROM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*from\s+(\.*)([A-Za-z0-9_.]*)\s+import\s+\(?([A-Za-z0-9_,\s*]+)",
+    )
+    .unwrap()
+});
+static JS_IMPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?:\bfrom\s*|\bimport\s*\(?\s*|\brequire\s*\(\s*)["'](\.{1,2}/[^"']*)["']"#,
+    )
+    .unwrap()
+});
+
+/// Source roots tried for absolute Python imports.
+const PYTHON_ROOTS: [&str; 2] = ["", "src"];
+
+/// Extensions tried for JavaScript and TypeScript imports without one.
+const JS_EXTENSIONS: [&str; 8] =
+    ["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"];
+
+/// The local imports reached from the `--follow` seeds. Every reached file is
+/// a key, mapped to the files it imports.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub(crate) struct DependencyGraph {
+    pub(crate) seeds: Vec<String>,
+    pub(crate) imports: BTreeMap<String, Vec<String>>,
+}
+
+impl DependencyGraph {
+    /// Follows local imports from `seeds`, which are paths relative to the
+    /// repository root.
+    pub(crate) fn build(
+        source: &(impl FileSource + ?Sized),
+        seeds: &[String],
+    ) -> io::Result<Self> {
+        let mut graph = Self::default();
+        for seed in seeds {
+            let path = repository_relative_path(source, seed)?;
+            if !source.has_file(&path) {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("'{seed}' does not exist in the repository"),
+                ));
+            }
+            if !graph.seeds.contains(&path) {
+                graph.seeds.push(path);
+            }
+        }
+
+        let mut queue = graph.seeds.iter().cloned().collect::<VecDeque<_>>();
+        while let Some(path) = queue.pop_front() {
+            if graph.imports.contains_key(&path) {
+                continue;
+            }
+            // Files that are not UTF-8 text have no imports to follow.
+            let content = source.read_to_string(&path).unwrap_or_default();
+            let imports = local_imports(source, &path, &content);
+            queue.extend(imports.iter().cloned());
+            graph.imports.insert(path, imports);
+        }
+        Ok(graph)
+    }
+
+    pub(crate) fn contains(&self, path: &str) -> bool {
+        self.imports.contains_key(path)
+    }
+}
+
+impl fmt::Display for DependencyGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Dependency graph from {} ({} files):",
+            self.seeds.join(", "),
+            self.imports.len()
+        )?;
+        for (path, imports) in &self.imports {
+            write!(f, "\n  {path}")?;
+            for import in imports {
+                write!(f, "\n    -> {import}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds the dependency graph for the `--follow` seeds, if any were given.
+pub(crate) fn dependency_graph(
+    source: &(impl FileSource + ?Sized),
+    flags: &Params,
+) -> io::Result<Option<DependencyGraph>> {
+    flags
+        .follow
+        .as_ref()
+        .map(|seeds| DependencyGraph::build(source, seeds))
+        .transpose()
+}
+
+/// Keeps only the listed files reachable from the `--follow` seeds.
+pub(crate) fn retain_reachable_files(
+    source: &(impl FileSource + ?Sized),
+    flags: &Params,
+    mut file_list: FileList,
+) -> io::Result<FileList> {
+    if let Some(graph) = dependency_graph(source, flags)? {
+        file_list.files.retain(|path| graph.contains(path));
+        file_list
+            .skipped
+            .retain(|skipped| graph.contains(&skipped.path));
+    }
+    Ok(file_list)
+}
+
+/// Returns the repository files imported by the file at `path`, in the order
+/// they are first imported.
+fn local_imports(
+    source: &(impl FileSource + ?Sized),
+    path: &str,
+    content: &str,
+) -> Vec<String> {
+    let candidates = match language_for(path) {
+        Some("rust") => rust_imports(path, content),
+        Some("python") => python_imports(path, content),
+        Some("javascript" | "typescript" | "svelte" | "vue") => {
+            js_imports(path, content)
+        }
+        _ => Vec::new(),
+    };
+
+    let mut imports = Vec::new();
+    for alternatives in candidates {
+        let found = alternatives
+            .into_iter()
+            .find(|candidate| candidate != path && source.has_file(candidate));
+        if let Some(found) = found
+            && !imports.contains(&found)
+        {
+            imports.push(found);
+        }
+    }
+    imports
+}
+
+fn parent_directory(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
+/// Resolves `mod` declarations, honouring `#[path]` attributes on the same
+/// or preceding lines. Inline `mod name { ... }` blocks are not followed.
+fn rust_imports(path: &str, source: &str) -> Vec<Vec<String>> {
+    let directory = parent_directory(path);
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    // `mod.rs`, `lib.rs` and `main.rs` own their directory; any other file
+    // owns a directory named after itself.
+    let module_directory = match file_name {
+        "mod.rs" | "lib.rs" | "main.rs" => directory.to_string(),
+        _ => {
+            join_repository_path(directory, file_name.trim_end_matches(".rs"))
+                .unwrap_or_default()
+        }
+    };
+
+    let mut imports = Vec::new();
+    let mut path_attribute = None;
+    for line in source.lines() {
+        let trimmed = line.trim_start();
+        if let Some(captures) = RUST_PATH.captures(line) {
+            path_attribute = Some(captures[1].to_string());
+        }
+        if let Some(captures) = RUST_MOD.captures(line) {
+            let name = &captures[1];
+            let candidates = match path_attribute.take() {
+                Some(attribute) => join_repository_path(directory, &attribute)
+                    .into_iter()
+                    .collect(),
+                None => [format!("{name}.rs"), format!("{name}/mod.rs")]
+                    .iter()
+                    .filter_map(|file| {
+                        join_repository_path(&module_directory, file)
+                    })
+                    .collect(),
+            };
+            imports.push(candidates);
+        } else if !trimmed.starts_with("#[") && !trimmed.starts_with("//") {
+            path_attribute = None;
+        }
+    }
+    imports
+}
+
+/// Returns the files a dotted Python module name can live in below
+/// `directory`.
+fn python_module_files(directory: &str, module: &str) -> Vec<String> {
+    let relative = module.replace('.', "/");
+    [format!("{relative}.py"), format!("{relative}/__init__.py")]
+        .iter()
+        .filter_map(|file| join_repository_path(directory, file))
+        .collect()
+}
+
+/// Resolves `import a.b` and `from .a import b` statements. Absolute imports
+/// are looked up from the repository root and from `src`, and imported names
+/// are tried as submodules.
+fn python_imports(path: &str, source: &str) -> Vec<Vec<String>> {
+    let mut imports = Vec::new();
+    for line in source.lines() {
+        if let Some(captures) = PYTHON_IMPORT.captures(line) {
+            for module in captures[1].split(',') {
+                let module = module.split_whitespace().next().unwrap_or("");
+                if module.is_empty() {
+                    continue;
+                }
+                imports.push(
+                    PYTHON_ROOTS
+                        .iter()
+                        .flat_map(|root| python_module_files(root, module))
+                        .collect(),
+                );
+            }
+        } else if let Some(captures) = PYTHON_FROM.captures(line) {
+            let level = captures[1].len();
+            let module = &captures[2];
+            let roots = if level == 0 {
+                PYTHON_ROOTS
+                    .iter()
+                    .map(|root| (*root).to_string())
+                    .collect()
+            } else {
+                let mut directory = Some(parent_directory(path).to_string());
+                for _ in 1..level {
+                    directory = directory.and_then(|directory| {
+                        join_repository_path(&directory, "..")
+                    });
+                }
+                directory.into_iter().collect::<Vec<_>>()
+            };
+            for root in &roots {
+                let base = if module.is_empty() {
+                    Some(root.clone())
+                } else {
+                    imports.push(python_module_files(root, module));
+                    join_repository_path(root, &module.replace('.', "/"))
+                };
+                let Some(base) = base else { continue };
+                for name in captures[3].split(',') {
+                    let name = name.split_whitespace().next().unwrap_or("");
+                    if !name.is_empty() && name != "*" {
+                        imports.push(python_module_files(&base, name));
+                    }
+                }
+            }
+        }
+    }
+    imports
+}
+
+/// Resolves relative `import`, `export ... from`, `require()` and dynamic
+/// `import()` specifiers, trying the usual extensions and `index` files.
+fn js_imports(path: &str, source: &str) -> Vec<Vec<String>> {
+    let directory = parent_directory(path);
+    JS_IMPORT
+        .captures_iter(source)
+        .filter_map(|captures| join_repository_path(directory, &captures[1]))
+        .map(|target| {
+            let mut candidates = vec![target.clone()];
+            // TypeScript sources import their compiled `.js` names.
+            if let Some(stem) = target.strip_suffix(".js") {
+                candidates.extend(
+                    ["ts", "tsx"].iter().map(|ext| format!("{stem}.{ext}")),
+                );
+            }
+            candidates.extend(
+                JS_EXTENSIONS.iter().map(|ext| format!("{target}.{ext}")),
+            );
+            candidates.extend(
+                JS_EXTENSIONS
+                    .iter()
+                    .map(|ext| format!("{target}/index.{ext}")),
+            );
+            candidates
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/follow.rs"]
+mod tests;
@@ -0,0 +1,139 @@
+use git2::{Repository, StatusOptions};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Mode bits git records for a submodule entry in the index.
+const GITLINK_MODE: u32 = 0o160000;
+
+/// The git view of the files below a listing root, with every path relative
+/// to that root rather than to the repository working directory.
+pub(crate) struct GitFiles {
+    repository: Repository,
+    root: PathBuf,
+    prefix: String,
+    tracked: BTreeSet<String>,
+}
+
+impl GitFiles {
+    /// Opens the repository containing `root` and reads its index.
+    pub(crate) fn open(root: &Path) -> Result<Self, git2::Error> {
+        let repository = Repository::discover(root)?;
+        let workdir = repository
+            .workdir()
+            .ok_or_else(|| {
+                git2::Error::from_str("Repository has no working directory")
+            })?
+            .canonicalize()
+            .map_err(|error| git2::Error::from_str(&error.to_string()))?;
+        let canonical_root = root
+            .canonicalize()
+            .map_err(|error| git2::Error::from_str(&error.to_string()))?;
+        let prefix = canonical_root
+            .strip_prefix(&workdir)
+            .map(|relative| {
+                relative
+                    .components()
+                    .map(|component| {
+                        format!("{}/", component.as_os_str().to_string_lossy())
+                    })
+                    .collect::<String>()
+            })
+            .unwrap_or_default();
+
+        let mut files = Self {
+            repository,
+            root: root.to_path_buf(),
+            prefix,
+            tracked: BTreeSet::new(),
+        };
+        // Conflicted paths appear once per stage, so the set also dedupes.
+        let tracked = files
+            .repository
+            .index()?
+            .iter()
+            .filter(|entry| entry.mode & 0o170000 != GITLINK_MODE)
+            .filter_map(|entry| {
+                files.relative(&String::from_utf8_lossy(&entry.path))
+            })
+            .collect();
+        files.tracked = tracked;
+        Ok(files)
+    }
+
+    fn relative(&self, workdir_path: &str) -> Option<String> {
+        workdir_path
+            .strip_prefix(&self.prefix)
+            .filter(|path| !path.is_empty() && !path.ends_with('/'))
+            .map(str::to_owned)
+    }
+
+    fn exists_as_file(&self, relative_path: &str) -> bool {
+        fs::symlink_metadata(self.root.join(relative_path))
+            .is_ok_and(|metadata| metadata.is_file())
+    }
+
+    pub(crate) fn is_tracked(&self, relative_path: &str) -> bool {
+        self.tracked.contains(relative_path)
+    }
+
+    /// Returns `true` if any tracked file lies below `directory`.
+    pub(crate) fn contains_tracked(&self, directory: &str) -> bool {
+        let directory = format!("{directory}/");
+        self.tracked
+            .range(directory.clone()..)
+            .next()
+            .is_some_and(|path| path.starts_with(&directory))
+    }
+
+    /// Returns `true` if git ignores the path, directly or through one of
+    /// its parent directories.
+    pub(crate) fn is_ignored(&self, relative_path: &str) -> bool {
+        self.repository
+            .is_path_ignored(format!("{}{relative_path}", self.prefix))
+            .unwrap_or(false)
+    }
+
+    /// Lists the tracked files that exist in the working tree, together with
+    /// untracked and ignored files when requested.
+    pub(crate) fn list(
+        &self,
+        include_untracked: bool,
+        include_ignored: bool,
+    ) -> Result<Vec<String>, git2::Error> {
+        let mut files = self
+            .tracked
+            .iter()
+            .filter(|path| self.exists_as_file(path))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        if include_untracked || include_ignored {
+            let mut options = StatusOptions::new();
+            options
+                .include_untracked(include_untracked)
+                .recurse_untracked_dirs(true)
+                .include_ignored(include_ignored)
+                .recurse_ignored_dirs(true)
+                .exclude_submodules(true);
+            for entry in self.repository.statuses(Some(&mut options))?.iter() {
+                let status = entry.status();
+                if !(status.is_wt_new() || status.is_ignored()) {
+                    continue;
+                }
+                if let Some(path) = self
+                    .relative(&String::from_utf8_lossy(entry.path_bytes()))
+                    .filter(|path| self.exists_as_file(path))
+                {
+                    files.insert(path);
+                }
+            }
+        }
+
+        Ok(files.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/git_files.rs"]
+mod tests;
@@ -0,0 +1,75 @@
+use crate::filelist::FileList;
+use crate::path_spec::LineRange;
+use crate::source::FileSource;
+use crate::structs::Params;
+use crate::text_processing::{ProcessedFile, read_classify_and_decode};
+use crate::timings::ProcessingTimings;
+use regex::Regex;
+use std::io;
+
+/// Checks that a `--grep` pattern is a valid regular expression.
+pub(crate) fn parse_grep_pattern(value: &str) -> Result<String, String> {
+    Regex::new(value)
+        .map(|_| value.to_string())
+        .map_err(|error| format!("invalid pattern: {error}"))
+}
+
+/// Compiles the `--grep` pattern, if one was given.
+pub(crate) fn grep_pattern(flags: &Params) -> io::Result<Option<Regex>> {
+    flags
+        .grep
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
+}
+
+/// Keeps only the listed files whose decoded text matches `--grep`. Binary
+/// files never match, and unreadable files are kept so that the error is
+/// reported when the bundle is written.
+pub(crate) fn retain_matching_files(
+    source: &(impl FileSource + ?Sized),
+    flags: &Params,
+    mut file_list: FileList,
+    timings: &mut ProcessingTimings,
+) -> io::Result<FileList> {
+    let Some(pattern) = grep_pattern(flags)? else {
+        return Ok(file_list);
+    };
+    file_list.files.retain(|path| {
+        match read_classify_and_decode(source, path, flags.utf8, timings) {
+            Ok(ProcessedFile::Text(decoded)) => {
+                pattern.is_match(&decoded.text)
+            }
+            Ok(ProcessedFile::Binary(_)) => false,
+            Err(_) => true,
+        }
+    });
+    Ok(file_list)
+}
+
+/// Returns the ranges covering each matching line with `context` lines
+/// either side, merging ranges that touch or overlap.
+pub(crate) fn match_ranges(
+    lines: &[&str],
+    pattern: &Regex,
+    context: usize,
+) -> Vec<LineRange> {
+    let mut ranges: Vec<LineRange> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if !pattern.is_match(line) {
+            continue;
+        }
+        let start = (index + 1).saturating_sub(context).max(1);
+        let end = (index + 1 + context).min(lines.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.end + 1 => last.end = end,
+            _ => ranges.push(LineRange { start, end }),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/grep.rs"]
+mod tests;
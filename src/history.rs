@@ -0,0 +1,285 @@
+use crate::structs::Params;
+use git2::{Patch, Repository, Sort, Time};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How far back changed lines count towards a hotspot's recent churn.
+pub(crate) const RECENT_CHURN_DAYS: i64 = 90;
+
+/// A commit in the `<git_history>` section, with the bundled files it
+/// touched.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct CommitSummary {
+    pub(crate) sha: String,
+    pub(crate) author: String,
+    pub(crate) date: String,
+    pub(crate) subject: String,
+    pub(crate) paths: Vec<String>,
+}
+
+/// A bundled file's change activity, for the `--hotspots` ranking.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Hotspot {
+    pub(crate) path: String,
+    pub(crate) commits: usize,
+    pub(crate) authors: usize,
+    /// Lines added and removed in the last [`RECENT_CHURN_DAYS`] days.
+    pub(crate) recent_churn: usize,
+}
+
+/// The recent commits that touched the bundled files, the last commit to
+/// touch each of them, and the files ranked as hotspots.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub(crate) struct GitHistory {
+    pub(crate) commits: Vec<CommitSummary>,
+    pub(crate) last_commits: BTreeMap<String, String>,
+    pub(crate) hotspots: Vec<Hotspot>,
+}
+
+/// Walks the first-parent history from `start` and collects up to `limit`
+/// commits that touched one of `paths`, and with `last_commits` the last
+/// commit to touch each path. `paths` are relative to `prefix`, the bundled
+/// directory's path in the repository.
+pub(crate) fn git_history(
+    repository: &Repository,
+    start: &str,
+    prefix: &str,
+    paths: &[String],
+    limit: usize,
+    last_commits: bool,
+) -> Result<GitHistory, git2::Error> {
+    let mut history = GitHistory::default();
+    if limit == 0 && !last_commits {
+        return Ok(history);
+    }
+    let start = find_commit(repository, start)?;
+    let wanted = repository_paths(prefix, paths);
+
+    let mut walk = repository.revwalk()?;
+    walk.push(start.id())?;
+    walk.simplify_first_parent()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    for oid in walk {
+        let done = history.commits.len() >= limit
+            && (!last_commits || history.last_commits.len() == wanted.len());
+        if done {
+            break;
+        }
+        let commit = repository.find_commit(oid?)?;
+        let diff = first_parent_diff(repository, &commit)?;
+        let touched = diff
+            .deltas()
+            .flat_map(|delta| {
+                [delta.old_file().path(), delta.new_file().path()]
+            })
+            .flatten()
+            .filter_map(|path| {
+                wanted.get(path.to_string_lossy().replace('\\', "/").as_str())
+            })
+            .map(|path| (*path).to_string())
+            .collect::<BTreeSet<_>>();
+        if touched.is_empty() {
+            continue;
+        }
+
+        let sha = commit.id().to_string();
+        if last_commits {
+            for path in &touched {
+                history
+                    .last_commits
+                    .entry(path.clone())
+                    .or_insert_with(|| sha.clone());
+            }
+        }
+        if history.commits.len() < limit {
+            history.commits.push(CommitSummary {
+                sha,
+                author: String::from_utf8_lossy(commit.author().name_bytes())
+                    .into_owned(),
+                date: format_time(commit.time()),
+                subject: commit
+                    .summary_bytes()
+                    .map(|subject| {
+                        String::from_utf8_lossy(subject).into_owned()
+                    })
+                    .unwrap_or_default(),
+                paths: touched.into_iter().collect(),
+            });
+        }
+    }
+    Ok(history)
+}
+
+/// Whether the hotspot ranking is shown, written or used to order files.
+pub(crate) fn ranks_hotspots(flags: &Params) -> bool {
+    flags.hotspots
+        || flags.hotspots_section
+        || flags.order.as_deref() == Some("hotspots")
+}
+
+/// Ranks `paths` by the number of commits that touched them, then by their
+/// distinct authors, then by the lines changed since `recent_since`, a Unix
+/// time. Every commit reachable from `start` counts except merges, whose
+/// changes are counted in the commits they merge. Files no commit touched
+/// are left out.
+pub(crate) fn hotspots(
+    repository: &Repository,
+    start: &str,
+    prefix: &str,
+    paths: &[String],
+    recent_since: i64,
+) -> Result<Vec<Hotspot>, git2::Error> {
+    let start = find_commit(repository, start)?;
+    let wanted = repository_paths(prefix, paths);
+    let mut activity =
+        BTreeMap::<&str, (usize, BTreeSet<Vec<u8>>, usize)>::new();
+
+    let mut walk = repository.revwalk()?;
+    walk.push(start.id())?;
+    for oid in walk {
+        let commit = repository.find_commit(oid?)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let recent = commit.time().seconds() >= recent_since;
+        let diff = first_parent_diff(repository, &commit)?;
+        for (index, delta) in diff.deltas().enumerate() {
+            let Some(path) = delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .map(|path| path.to_string_lossy().replace('\\', "/"))
+                .and_then(|path| wanted.get(path.as_str()).copied())
+            else {
+                continue;
+            };
+            let (commits, authors, churn) = activity.entry(path).or_default();
+            *commits += 1;
+            authors.insert(commit.author().email_bytes().to_vec());
+            if recent && let Some(patch) = Patch::from_diff(&diff, index)? {
+                let (_, additions, deletions) = patch.line_stats()?;
+                *churn += additions + deletions;
+            }
+        }
+    }
+
+    let mut hotspots = activity
+        .into_iter()
+        .map(|(path, (commits, authors, recent_churn))| Hotspot {
+            path: path.to_string(),
+            commits,
+            authors: authors.len(),
+            recent_churn,
+        })
+        .collect::<Vec<_>>();
+    hotspots.sort_by(|left, right| {
+        (right.commits, right.authors, right.recent_churn)
+            .cmp(&(left.commits, left.authors, left.recent_churn))
+            .then_with(|| left.path.cmp(&right.path))
+    });
+    Ok(hotspots)
+}
+
+/// Puts the ranked hotspots first in `paths`, followed by the rest in path
+/// order.
+pub(crate) fn order_by_hotspots(paths: &mut [String], hotspots: &[Hotspot]) {
+    let ranks = hotspots
+        .iter()
+        .enumerate()
+        .map(|(rank, hotspot)| (hotspot.path.as_str(), rank))
+        .collect::<BTreeMap<_, _>>();
+    paths.sort_by(|left, right| {
+        let rank = |path: &String| {
+            ranks.get(path.as_str()).copied().unwrap_or(usize::MAX)
+        };
+        rank(left).cmp(&rank(right)).then_with(|| left.cmp(right))
+    });
+}
+
+fn find_commit<'r>(
+    repository: &'r Repository,
+    name: &str,
+) -> Result<git2::Commit<'r>, git2::Error> {
+    repository
+        .revparse_single(name)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| {
+            git2::Error::from_str(&format!(
+                "The ref '{name}' does not exist in the repository."
+            ))
+        })
+}
+
+/// Maps each bundled path's location in the repository to the path itself.
+fn repository_paths<'p>(
+    prefix: &str,
+    paths: &'p [String],
+) -> BTreeMap<String, &'p str> {
+    paths
+        .iter()
+        .map(|path| {
+            let repository_path = if prefix.is_empty() {
+                path.clone()
+            } else {
+                format!("{prefix}/{path}")
+            };
+            (repository_path, path.as_str())
+        })
+        .collect()
+}
+
+/// Diffs a commit against its first parent, or against an empty tree for a
+/// root commit.
+fn first_parent_diff<'r>(
+    repository: &'r Repository,
+    commit: &git2::Commit<'r>,
+) -> Result<git2::Diff<'r>, git2::Error> {
+    let parent_tree = commit
+        .parent(0)
+        .ok()
+        .map(|parent| parent.tree())
+        .transpose()?;
+    repository.diff_tree_to_tree(
+        parent_tree.as_ref(),
+        Some(&commit.tree()?),
+        None,
+    )
+}
+
+/// Formats a commit time as an RFC 3339 timestamp in the committer's offset.
+pub(crate) fn format_time(time: Time) -> String {
+    let offset = i64::from(time.offset_minutes());
+    let local = time.seconds() + offset * 60;
+    let (days, seconds) = (local.div_euclid(86_400), local.rem_euclid(86_400));
+
+    // Converts days since 1970-01-01 to a proleptic Gregorian date.
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let sign = if offset < 0 { '-' } else { '+' };
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}{sign}{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/history.rs"]
+mod tests;
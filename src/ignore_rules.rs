@@ -0,0 +1,315 @@
+use crate::source::FileSource;
+use git2::Repository;
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder, Glob};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The ignore-file rule that decided whether a path is ignored.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct IgnoreMatch {
+    pub(crate) source: String,
+    pub(crate) line: usize,
+    pub(crate) pattern: String,
+    pub(crate) whitelist: bool,
+}
+
+impl fmt::Display for IgnoreMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.source, self.line, self.pattern)
+    }
+}
+
+struct RuleFile {
+    matcher: Gitignore,
+    lines: Vec<String>,
+}
+
+impl RuleFile {
+    fn parse(directory: &Path, source: &str, content: &str) -> Option<Self> {
+        let mut builder = GitignoreBuilder::new(directory);
+        for line in content.lines() {
+            // Invalid globs are skipped, matching the directory walker.
+            let _ = builder.add_line(Some(PathBuf::from(source)), line);
+        }
+        Some(Self {
+            matcher: builder.build().ok()?,
+            lines: content.lines().map(str::to_owned).collect(),
+        })
+    }
+
+    fn global() -> Option<Self> {
+        let (matcher, _) = Gitignore::global();
+        (!matcher.is_empty()).then_some(Self {
+            matcher,
+            lines: Vec::new(),
+        })
+    }
+
+    fn matched(&self, path: &Path, is_dir: bool) -> Option<IgnoreMatch> {
+        match self.matcher.matched(path, is_dir) {
+            Match::None => None,
+            Match::Ignore(glob) => Some(self.describe(glob, false)),
+            Match::Whitelist(glob) => Some(self.describe(glob, true)),
+        }
+    }
+
+    fn describe(&self, glob: &Glob, whitelist: bool) -> IgnoreMatch {
+        let source = glob
+            .from()
+            .map(|from| from.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        // The global excludes file is read by the ignore crate itself, so its
+        // lines are only loaded when one of its rules is reported.
+        let global_content = if self.lines.is_empty() {
+            glob.from()
+                .and_then(|from| fs::read_to_string(from).ok())
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+        let line = if self.lines.is_empty() {
+            line_number(global_content.lines(), glob.original())
+        } else {
+            line_number(self.lines.iter().map(String::as_str), glob.original())
+        };
+        IgnoreMatch {
+            source,
+            line,
+            pattern: glob.original().to_string(),
+            whitelist,
+        }
+    }
+}
+
+/// Reads an ignore file by its path relative to the root of the walk.
+type ReadRuleFile<'a> = Box<dyn Fn(&str) -> Option<String> + 'a>;
+
+/// Resolves paths against the ignore files honoured by the directory walker,
+/// reporting the file and line of the rule that applied.
+pub(crate) struct IgnoreRules<'a> {
+    root: PathBuf,
+    read: ReadRuleFile<'a>,
+    filenames: Vec<&'static str>,
+    directories: HashMap<String, Vec<RuleFile>>,
+    /// The ignore files of the directories above the root.
+    parents: Vec<RuleFile>,
+    fallbacks: Vec<RuleFile>,
+    read_filenames: HashSet<&'static str>,
+}
+
+impl<'a> IgnoreRules<'a> {
+    /// Creates rules for a walk rooted at `root`. Git ignore files are only
+    /// honoured inside a git repository, as the walker does, and the ignore
+    /// files of the directories between `root` and the repository root apply
+    /// too.
+    pub(crate) fn new(root: &Path, custom_filenames: &[&'static str]) -> Self {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let repository = Repository::discover(&root).ok();
+        let Some(workdir) = repository
+            .as_ref()
+            .and_then(Repository::workdir)
+            .and_then(|workdir| workdir.canonicalize().ok())
+        else {
+            return Self::build(&root, custom_filenames, false);
+        };
+        let mut rules = Self::build(&root, custom_filenames, true);
+        if let Some(exclude) = repository
+            .as_ref()
+            .map(|repository| repository.path().join("info/exclude"))
+            .and_then(|exclude| fs::read_to_string(exclude).ok())
+            .and_then(|content| {
+                RuleFile::parse(&workdir, ".git/info/exclude", &content)
+            })
+        {
+            rules.fallbacks.push(exclude);
+        }
+        rules.fallbacks.extend(RuleFile::global());
+
+        // The parents are listed from the nearest, whose rules take
+        // precedence, and named relative to the root.
+        let mut up = String::new();
+        for directory in root.ancestors().skip(1) {
+            if !directory.starts_with(&workdir) {
+                break;
+            }
+            up.push_str("../");
+            for filename in &rules.filenames {
+                let Some(rule_file) =
+                    fs::read_to_string(directory.join(filename))
+                        .ok()
+                        .and_then(|content| {
+                            RuleFile::parse(
+                                directory,
+                                &format!("{up}{filename}"),
+                                &content,
+                            )
+                        })
+                else {
+                    continue;
+                };
+                rules.parents.push(rule_file);
+                rules.read_filenames.insert(filename);
+            }
+        }
+        rules
+    }
+
+    /// Creates rules for a `--no-git` walk, which honours `.gitignore` files
+    /// below `root` but not git's exclude files.
+    pub(crate) fn plain_directory(
+        root: &Path,
+        custom_filenames: &[&'static str],
+    ) -> Self {
+        Self::build(root, custom_filenames, true)
+    }
+
+    /// Creates rules for a walk of a source without a directory on disk,
+    /// such as the tree of a commit, which honours its `.gitignore` files
+    /// like a `--no-git` walk.
+    pub(crate) fn in_source<S: FileSource + ?Sized>(
+        source: &'a S,
+        custom_filenames: &[&'static str],
+    ) -> Self {
+        Self {
+            read: Box::new(|path: &str| source.read_to_string(path).ok()),
+            ..Self::build(Path::new(""), custom_filenames, true)
+        }
+    }
+
+    /// Creates rules that leave `.gitignore` and git's exclude files to git
+    /// itself, for listings built from the repository's file states.
+    pub(crate) fn without_git_ignores(
+        root: &Path,
+        custom_filenames: &[&'static str],
+    ) -> Self {
+        Self::build(root, custom_filenames, false)
+    }
+
+    fn build(
+        root: &Path,
+        custom_filenames: &[&'static str],
+        git_ignores: bool,
+    ) -> Self {
+        let mut filenames = custom_filenames.to_vec();
+        filenames.push(".ignore");
+        if git_ignores {
+            filenames.push(".gitignore");
+        }
+
+        let directory = root.to_path_buf();
+        Self {
+            root: root.to_path_buf(),
+            read: Box::new(move |path: &str| {
+                fs::read_to_string(directory.join(path)).ok()
+            }),
+            filenames,
+            directories: HashMap::new(),
+            parents: Vec::new(),
+            fallbacks: Vec::new(),
+            read_filenames: HashSet::new(),
+        }
+    }
+
+    /// Returns the deciding rule for a repository-relative path, if any.
+    /// Deeper ignore files take precedence over shallower ones, and the
+    /// parent directories of `relative_path` are not consulted.
+    pub(crate) fn matched(
+        &mut self,
+        relative_path: &str,
+        is_dir: bool,
+    ) -> Option<IgnoreMatch> {
+        let full_path = self.root.join(relative_path);
+        let mut directory = parent_directory(relative_path);
+        loop {
+            self.load_directory(&directory);
+            if let Some(found) = self.directories[&directory]
+                .iter()
+                .find_map(|rules| rules.matched(&full_path, is_dir))
+            {
+                return Some(found);
+            }
+            if directory.is_empty() {
+                break;
+            }
+            directory = parent_directory(&directory);
+        }
+
+        self.parents
+            .iter()
+            .chain(&self.fallbacks)
+            .find_map(|rules| rules.matched(&full_path, is_dir))
+    }
+
+    /// Returns the rule that ignores `relative_path` or one of its parent
+    /// directories, checking the outermost directory first.
+    pub(crate) fn ignored(
+        &mut self,
+        relative_path: &str,
+        is_dir: bool,
+    ) -> Option<IgnoreMatch> {
+        let components = relative_path.split('/').collect::<Vec<_>>();
+        for depth in 1..components.len() {
+            let parent = components[..depth].join("/");
+            if let Some(found) = self.matched(&parent, true)
+                && !found.whitelist
+            {
+                return Some(found);
+            }
+        }
+        self.matched(relative_path, is_dir)
+            .filter(|found| !found.whitelist)
+    }
+
+    /// Returns `true` once an ignore file named `filename` has been read for
+    /// any of the paths resolved so far.
+    pub(crate) fn has_read(&self, filename: &str) -> bool {
+        self.read_filenames.contains(filename)
+    }
+
+    fn load_directory(&mut self, directory: &str) {
+        if self.directories.contains_key(directory) {
+            return;
+        }
+        let absolute = self.root.join(directory);
+        let mut rules = Vec::new();
+        for filename in &self.filenames {
+            let source = if directory.is_empty() {
+                (*filename).to_string()
+            } else {
+                format!("{directory}/{filename}")
+            };
+            let Some(content) = (self.read)(&source) else {
+                continue;
+            };
+            if let Some(rule_file) =
+                RuleFile::parse(&absolute, &source, &content)
+            {
+                rules.push(rule_file);
+                self.read_filenames.insert(filename);
+            }
+        }
+        self.directories.insert(directory.to_string(), rules);
+    }
+}
+
+fn line_number<'a>(
+    mut lines: impl Iterator<Item = &'a str>,
+    original: &str,
+) -> usize {
+    lines
+        .position(|line| line.trim_end() == original)
+        .map_or(0, |index| index + 1)
+}
+
+fn parent_directory(relative_path: &str) -> String {
+    relative_path
+        .rsplit_once('/')
+        .map_or_else(String::new, |(parent, _)| parent.to_string())
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/ignore_rules.rs"]
+mod tests;
@@ -0,0 +1,440 @@
+use crate::structs::Params;
+use std::fmt;
+use std::io;
+
+/// A language name with the file extensions and exact filenames that
+/// identify it. Extensions are matched case-insensitively.
+struct Language {
+    name: &'static str,
+    extensions: &'static [&'static str],
+    filenames: &'static [&'static str],
+}
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        name: "c",
+        extensions: &["c", "h"],
+        filenames: &[],
+    },
+    Language {
+        name: "cmake",
+        extensions: &["cmake"],
+        filenames: &["CMakeLists.txt"],
+    },
+    Language {
+        name: "cpp",
+        extensions: &["cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+        filenames: &[],
+    },
+    Language {
+        name: "csharp",
+        extensions: &["cs", "csx"],
+        filenames: &[],
+    },
+    Language {
+        name: "css",
+        extensions: &["css"],
+        filenames: &[],
+    },
+    Language {
+        name: "dart",
+        extensions: &["dart"],
+        filenames: &[],
+    },
+    Language {
+        name: "dockerfile",
+        extensions: &["dockerfile"],
+        filenames: &["Dockerfile", "Containerfile"],
+    },
+    Language {
+        name: "elixir",
+        extensions: &["ex", "exs"],
+        filenames: &[],
+    },
+    Language {
+        name: "erlang",
+        extensions: &["erl", "hrl"],
+        filenames: &[],
+    },
+    Language {
+        name: "go",
+        extensions: &["go"],
+        filenames: &["go.mod", "go.sum"],
+    },
+    Language {
+        name: "graphql",
+        extensions: &["gql", "graphql"],
+        filenames: &[],
+    },
+    Language {
+        name: "haskell",
+        extensions: &["hs", "lhs"],
+        filenames: &[],
+    },
+    Language {
+        name: "html",
+        extensions: &["htm", "html"],
+        filenames: &[],
+    },
+    Language {
+        name: "java",
+        extensions: &["java"],
+        filenames: &[],
+    },
+    Language {
+        name: "javascript",
+        extensions: &["cjs", "js", "jsx", "mjs"],
+        filenames: &[],
+    },
+    Language {
+        name: "json",
+        extensions: &["json", "jsonc"],
+        filenames: &[],
+    },
+    Language {
+        name: "kotlin",
+        extensions: &["kt", "kts"],
+        filenames: &[],
+    },
+    Language {
+        name: "lua",
+        extensions: &["lua"],
+        filenames: &[],
+    },
+    Language {
+        name: "makefile",
+        extensions: &["mk"],
+        filenames: &["GNUmakefile", "Makefile", "makefile"],
+    },
+    Language {
+        name: "markdown",
+        extensions: &["markdown", "md", "mdx"],
+        filenames: &[],
+    },
+    Language {
+        name: "nix",
+        extensions: &["nix"],
+        filenames: &[],
+    },
+    Language {
+        name: "php",
+        extensions: &["php"],
+        filenames: &[],
+    },
+    Language {
+        name: "powershell",
+        extensions: &["ps1", "psd1", "psm1"],
+        filenames: &[],
+    },
+    Language {
+        name: "protobuf",
+        extensions: &["proto"],
+        filenames: &[],
+    },
+    Language {
+        name: "python",
+        extensions: &["py", "pyi", "pyw"],
+        filenames: &[],
+    },
+    Language {
+        name: "r",
+        extensions: &["r"],
+        filenames: &[],
+    },
+    Language {
+        name: "restructuredtext",
+        extensions: &["rst"],
+        filenames: &[],
+    },
+    Language {
+        name: "ruby",
+        extensions: &["gemspec", "rake", "rb"],
+        filenames: &["Gemfile", "Rakefile"],
+    },
+    Language {
+        name: "rust",
+        extensions: &["rs"],
+        filenames: &[],
+    },
+    Language {
+        name: "scala",
+        extensions: &["sc", "scala"],
+        filenames: &[],
+    },
+    Language {
+        name: "scss",
+        extensions: &["sass", "scss"],
+        filenames: &[],
+    },
+    Language {
+        name: "shell",
+        extensions: &["bash", "sh", "zsh"],
+        filenames: &[],
+    },
+    Language {
+        name: "sql",
+        extensions: &["sql"],
+        filenames: &[],
+    },
+    Language {
+        name: "svelte",
+        extensions: &["svelte"],
+        filenames: &[],
+    },
+    Language {
+        name: "swift",
+        extensions: &["swift"],
+        filenames: &[],
+    },
+    Language {
+        name: "terraform",
+        extensions: &["tf", "tfvars"],
+        filenames: &[],
+    },
+    Language {
+        name: "text",
+        extensions: &["txt"],
+        filenames: &[],
+    },
+    Language {
+        name: "toml",
+        extensions: &["toml"],
+        filenames: &[],
+    },
+    Language {
+        name: "typescript",
+        extensions: &["cts", "mts", "ts", "tsx"],
+        filenames: &[],
+    },
+    Language {
+        name: "vue",
+        extensions: &["vue"],
+        filenames: &[],
+    },
+    Language {
+        name: "xml",
+        extensions: &["xml", "xsd", "xsl"],
+        filenames: &[],
+    },
+    Language {
+        name: "yaml",
+        extensions: &["yaml", "yml"],
+        filenames: &[],
+    },
+    Language {
+        name: "zig",
+        extensions: &["zig"],
+        filenames: &[],
+    },
+];
+
+/// Directory names that hold tests, documentation and examples by common
+/// convention.
+const TEST_DIRECTORIES: &[&str] = &["__tests__", "spec", "test", "tests"];
+const DOC_DIRECTORIES: &[&str] = &["doc", "docs", "documentation"];
+const EXAMPLE_DIRECTORIES: &[&str] = &["example", "examples"];
+
+/// A category of files that can be left out of the bundle as a whole.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Category {
+    Tests,
+    Docs,
+    Examples,
+}
+
+impl Category {
+    pub(crate) const fn flag(self) -> &'static str {
+        match self {
+            Self::Tests => "--no-tests",
+            Self::Docs => "--no-docs",
+            Self::Examples => "--no-examples",
+        }
+    }
+
+    pub(crate) const fn description(self) -> &'static str {
+        match self {
+            Self::Tests => "test",
+            Self::Docs => "documentation",
+            Self::Examples => "example",
+        }
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Returns the language of a repository path, recognised by its filename or
+/// extension.
+pub(crate) fn language_for(path: &str) -> Option<&'static str> {
+    let name = file_name(path);
+    let extension = name
+        .rsplit_once('.')
+        .filter(|(stem, _)| !stem.is_empty())
+        .map(|(_, extension)| extension.to_ascii_lowercase());
+
+    LANGUAGES
+        .iter()
+        .find(|language| language.filenames.contains(&name))
+        .or_else(|| {
+            let extension = extension.as_deref()?;
+            LANGUAGES
+                .iter()
+                .find(|language| language.extensions.contains(&extension))
+        })
+        .map(|language| language.name)
+}
+
+/// Returns the names accepted by `--lang`, in alphabetical order.
+pub(crate) fn language_names() -> impl Iterator<Item = &'static str> {
+    LANGUAGES.iter().map(|language| language.name)
+}
+
+/// Resolves a user-supplied language name to its canonical spelling.
+pub(crate) fn parse_language(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    language_names()
+        .find(|name| name.eq_ignore_ascii_case(value))
+        .map(str::to_owned)
+        .ok_or_else(|| {
+            format!(
+                "Unknown language '{value}'. Supported languages: {}",
+                language_names().collect::<Vec<_>>().join(", ")
+            )
+        })
+}
+
+/// Returns the category a directory name marks its contents with.
+pub(crate) fn directory_category(name: &str) -> Option<Category> {
+    let name = name.to_ascii_lowercase();
+    if TEST_DIRECTORIES.contains(&name.as_str()) {
+        Some(Category::Tests)
+    } else if DOC_DIRECTORIES.contains(&name.as_str()) {
+        Some(Category::Docs)
+    } else if EXAMPLE_DIRECTORIES.contains(&name.as_str()) {
+        Some(Category::Examples)
+    } else {
+        None
+    }
+}
+
+fn is_test_file_name(name: &str) -> bool {
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+    match extension {
+        "go" => stem.ends_with("_test"),
+        "py" => stem.starts_with("test_") || stem.ends_with("_test"),
+        "rb" => stem.ends_with("_spec") || stem.ends_with("_test"),
+        "java" | "kt" | "cs" | "swift" => {
+            stem.ends_with("Test") || stem.ends_with("Tests")
+        }
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => {
+            stem.ends_with(".test") || stem.ends_with(".spec")
+        }
+        _ => false,
+    }
+}
+
+/// Returns the categories a repository path belongs to, from its parent
+/// directories and, for tests, its filename.
+pub(crate) fn categories_for(path: &str) -> Vec<Category> {
+    let mut components = path.split('/').collect::<Vec<_>>();
+    let name = components.pop().unwrap_or_default();
+    let mut categories = components
+        .into_iter()
+        .filter_map(directory_category)
+        .collect::<Vec<_>>();
+    if is_test_file_name(name) {
+        categories.push(Category::Tests);
+    }
+    categories
+}
+
+/// Why a file was left out by the language and category selections.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Deselection {
+    Language(Option<&'static str>),
+    Category(Category),
+}
+
+impl fmt::Display for Deselection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Language(Some(language)) => {
+                write!(f, "language '{language}' is not selected by --lang")
+            }
+            Self::Language(None) => {
+                f.write_str("language is not recognised by --lang")
+            }
+            Self::Category(category) => write!(
+                f,
+                "{} files are excluded by {}",
+                category.description(),
+                category.flag()
+            ),
+        }
+    }
+}
+
+/// The `--lang` and category selections applied to candidate files.
+pub(crate) struct FileSelection {
+    languages: Option<Vec<String>>,
+    excluded_categories: Vec<Category>,
+}
+
+impl FileSelection {
+    pub(crate) fn new(flags: &Params) -> io::Result<Self> {
+        let languages = flags
+            .languages
+            .as_ref()
+            .map(|languages| {
+                languages
+                    .iter()
+                    .map(|language| parse_language(language))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()
+            .map_err(|error| {
+                io::Error::new(io::ErrorKind::InvalidInput, error)
+            })?;
+        let excluded_categories = [
+            (Category::Tests, flags.include_tests),
+            (Category::Docs, flags.include_docs),
+            (Category::Examples, flags.include_examples),
+        ]
+        .into_iter()
+        .filter(|(_, included)| !included)
+        .map(|(category, _)| category)
+        .collect();
+
+        Ok(Self {
+            languages,
+            excluded_categories,
+        })
+    }
+
+    /// Returns the excluded category that a directory name marks.
+    pub(crate) fn excluded_directory(&self, name: &str) -> Option<Category> {
+        directory_category(name)
+            .filter(|category| self.excluded_categories.contains(category))
+    }
+
+    /// Returns why a repository file is left out, if it is.
+    pub(crate) fn deselection(&self, path: &str) -> Option<Deselection> {
+        if let Some(category) = categories_for(path)
+            .into_iter()
+            .find(|category| self.excluded_categories.contains(category))
+        {
+            return Some(Deselection::Category(category));
+        }
+        let languages = self.languages.as_ref()?;
+        let language = language_for(path);
+        (!language.is_some_and(|language| {
+            languages.iter().any(|selected| selected == language)
+        }))
+        .then_some(Deselection::Language(language))
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/language.rs"]
+mod tests;
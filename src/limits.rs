@@ -0,0 +1,188 @@
+use crate::source::FileSource;
+use crate::structs::Params;
+use std::fmt;
+use std::io::{self, BufRead, BufReader};
+use std::time::{Duration, SystemTime};
+
+const SIZE_UNITS: [(&str, u64); 10] = [
+    ("b", 1),
+    ("k", 1 << 10),
+    ("kb", 1 << 10),
+    ("kib", 1 << 10),
+    ("m", 1 << 20),
+    ("mb", 1 << 20),
+    ("mib", 1 << 20),
+    ("g", 1 << 30),
+    ("gb", 1 << 30),
+    ("gib", 1 << 30),
+];
+
+const AGE_UNITS: [(&str, u64); 5] = [
+    ("s", 1),
+    ("m", 60),
+    ("h", 60 * 60),
+    ("d", 24 * 60 * 60),
+    ("w", 7 * 24 * 60 * 60),
+];
+
+fn split_number(value: &str) -> Option<(u64, String)> {
+    let value = value.trim();
+    let digits = value
+        .find(|character: char| !character.is_ascii_digit())
+        .unwrap_or(value.len());
+    let number = value[..digits].parse().ok()?;
+    Some((number, value[digits..].trim().to_ascii_lowercase()))
+}
+
+/// Parses a size such as `512000`, `500K` or `10MB`. Units are powers of
+/// 1024.
+pub(crate) fn parse_file_size(value: &str) -> Result<u64, String> {
+    split_number(value)
+        .and_then(|(number, unit)| {
+            let multiplier = if unit.is_empty() {
+                1
+            } else {
+                SIZE_UNITS.iter().find(|(name, _)| *name == unit)?.1
+            };
+            number.checked_mul(multiplier)
+        })
+        .ok_or_else(|| {
+            "file size must be a number of bytes, optionally followed by K, M \
+             or G (e.g. '10MB')"
+                .to_string()
+        })
+}
+
+/// Parses a positive line count.
+pub(crate) fn parse_line_count(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err("line count must be a positive integer".to_string()),
+    }
+}
+
+/// Parses an age such as `90m`, `12h`, `30d` or `2w` into seconds.
+pub(crate) fn parse_age(value: &str) -> Result<u64, String> {
+    split_number(value)
+        .and_then(|(number, unit)| {
+            let multiplier =
+                AGE_UNITS.iter().find(|(name, _)| *name == unit)?.1;
+            number.checked_mul(multiplier)
+        })
+        .ok_or_else(|| {
+            "age must be a number followed by s, m, h, d or w (e.g. '30d')"
+                .to_string()
+        })
+}
+
+/// Why a listed file was skipped rather than bundled.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SkipReason {
+    Size,
+    Lines,
+    Age,
+}
+
+impl SkipReason {
+    /// The value written to the `skipped` attribute in the structure.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Size => "too-large",
+            Self::Lines => "too-many-lines",
+            Self::Age => "too-old",
+        }
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Size => "larger than --max-file-size",
+            Self::Lines => "more lines than --max-file-lines",
+            Self::Age => "not modified within --modified-within",
+        })
+    }
+}
+
+/// The size, line count and age limits a file must meet to be bundled.
+pub(crate) struct FileLimits {
+    max_size: Option<u64>,
+    max_lines: Option<usize>,
+    modified_since: Option<SystemTime>,
+}
+
+impl FileLimits {
+    pub(crate) fn new(flags: &Params) -> Self {
+        Self {
+            max_size: flags.max_file_size,
+            max_lines: flags.max_file_lines,
+            modified_since: flags.modified_within.and_then(|seconds| {
+                SystemTime::now().checked_sub(Duration::from_secs(seconds))
+            }),
+        }
+    }
+
+    /// Returns why the file at `path` breaks a limit, checking the cheap
+    /// metadata limits before counting lines.
+    pub(crate) fn check(
+        &self,
+        source: &(impl FileSource + ?Sized),
+        path: &str,
+    ) -> io::Result<Option<SkipReason>> {
+        if self.max_size.is_none()
+            && self.max_lines.is_none()
+            && self.modified_since.is_none()
+        {
+            return Ok(None);
+        }
+        if let Some(max) = self.max_size
+            && source.file_size(path)? > max
+        {
+            return Ok(Some(SkipReason::Size));
+        }
+        if let Some(since) = self.modified_since
+            && source.modified_time(path)? < since
+        {
+            return Ok(Some(SkipReason::Age));
+        }
+        if let Some(max) = self.max_lines
+            && exceeds_line_count(source, path, max)?
+        {
+            return Ok(Some(SkipReason::Lines));
+        }
+        Ok(None)
+    }
+}
+
+/// Counts lines until `max` is exceeded, so large files are not read in full.
+fn exceeds_line_count(
+    source: &(impl FileSource + ?Sized),
+    path: &str,
+    max: usize,
+) -> io::Result<bool> {
+    let mut reader = BufReader::new(source.open_file(path)?);
+    let mut lines = 0;
+    let mut last_byte = None;
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            break;
+        }
+        lines += buffer.iter().filter(|byte| **byte == b'\n').count();
+        last_byte = buffer.last().copied();
+        let length = buffer.len();
+        reader.consume(length);
+        if lines > max {
+            return Ok(true);
+        }
+    }
+    // A final line without a terminator still counts.
+    if last_byte.is_some_and(|byte| byte != b'\n') {
+        lines += 1;
+    }
+    Ok(lines > max)
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/limits.rs"]
+mod tests;
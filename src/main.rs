@@ -1,11 +1,12 @@
 use std::fmt;
 use std::path::Path;
 use std::process::exit;
-use std::time::Instant;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use clap::Parser;
 use config::{Config, File, FileFormat};
 use dirs_next::home_dir;
+use source::{FileSource, Source};
 use structs::Params;
 use tabled::{
     Table, Tabled,
@@ -17,18 +18,35 @@ use tabled::{
 use tempfile::tempdir;
 use tokenizer::{Model, TokenizerType};
 
+mod archive;
+mod auth;
+mod cache;
+mod changes;
 mod cli;
+mod dry_run;
 mod embedded;
 mod filelist;
+mod follow;
+mod git_files;
+mod grep;
+mod history;
+mod ignore_rules;
+mod language;
+mod limits;
+mod path_spec;
 mod progress;
 mod repo;
+mod source;
 mod structs;
+mod symbol;
 #[cfg(test)]
 #[path = "../tests/crate/test_fixtures.rs"]
 mod test_fixtures;
 mod text_processing;
 mod timings;
 mod tokenizer;
+mod truncation;
+mod workspace;
 mod xml_output;
 
 #[derive(Tabled)]
@@ -63,6 +81,9 @@ fn load_config_from_paths(
     }
 
     if local_config_path.exists() {
+        if let Some(warning) = local_token_warning(local_config_path) {
+            eprintln!("{warning}");
+        }
         config_builder = config_builder.add_source(File::new(
             local_config_path.to_str().unwrap(),
             FileFormat::Toml,
@@ -78,6 +99,34 @@ fn load_config_from_paths(
     }
 }
 
+/// Warns about a token in the repository-local configuration file when git
+/// does not ignore that file, since it would then be committed with the
+/// repository.
+fn local_token_warning(local_config_path: &Path) -> Option<String> {
+    let config = Config::builder()
+        .add_source(File::new(local_config_path.to_str()?, FileFormat::Toml))
+        .build()
+        .ok()?;
+    let has_token = config.get_string("token").is_ok()
+        || config
+            .get_table("tokens")
+            .is_ok_and(|tokens| !tokens.is_empty());
+    if !has_token {
+        return None;
+    }
+    let path = local_config_path.canonicalize().ok()?;
+    let repository = git2::Repository::discover(path.parent()?).ok()?;
+    let workdir = repository.workdir()?.canonicalize().ok()?;
+    let relative_path = path.strip_prefix(workdir).ok()?;
+    if repository.is_path_ignored(relative_path).unwrap_or(false) {
+        return None;
+    }
+    Some(format!(
+        "warning: '{}' holds a token and is not ignored by git, so it may be committed. Move the token to ~/.config/bundlerepo/config.toml, the BUNDLEREPO_TOKEN environment variable or a --token-file.",
+        local_config_path.display()
+    ))
+}
+
 fn report_success<N: std::io::Write, D: std::io::Write>(
     params: &Params,
     model: Model,
@@ -122,6 +171,139 @@ fn report_success<N: std::io::Write, D: std::io::Write>(
     reporter.normal_text(&format!("\nSummary:\n{table}\n\n"))
 }
 
+#[derive(Tabled)]
+struct HotspotRow {
+    #[tabled(rename = "Rank")]
+    rank: usize,
+    #[tabled(rename = "File")]
+    path: String,
+    #[tabled(rename = "Commits")]
+    commits: usize,
+    #[tabled(rename = "Authors")]
+    authors: usize,
+    #[tabled(rename = "Recent churn")]
+    recent_churn: usize,
+}
+
+/// The number of hotspots shown in the terminal summary.
+const HOTSPOT_ROWS: usize = 10;
+
+fn report_hotspots<N: std::io::Write, D: std::io::Write>(
+    hotspots: &[history::Hotspot],
+    reporter: &mut progress::ProgressReporter<N, D>,
+) -> std::io::Result<()> {
+    if hotspots.is_empty() {
+        return reporter
+            .normal_line("Hotspots: no bundled file has any commits\n");
+    }
+    let rows = hotspots
+        .iter()
+        .take(HOTSPOT_ROWS)
+        .enumerate()
+        .map(|(index, hotspot)| HotspotRow {
+            rank: index + 1,
+            path: hotspot.path.clone(),
+            commits: hotspot.commits,
+            authors: hotspot.authors,
+            recent_churn: hotspot.recent_churn,
+        })
+        .collect::<Vec<_>>();
+    let table = Table::new(rows)
+        .with(Style::empty())
+        .with(Modify::list(Columns::first(), Alignment::right()))
+        .to_string();
+
+    reporter.normal_text(&format!(
+        "Hotspots (recent churn is lines changed in the last {} days):\n{table}\n\n",
+        history::RECENT_CHURN_DAYS
+    ))
+}
+
+#[derive(Tabled)]
+struct CachedRow {
+    #[tabled(rename = "Repository")]
+    name: String,
+    #[tabled(rename = "Size (bytes)")]
+    size: u64,
+    #[tabled(rename = "Last used")]
+    last_used: String,
+}
+
+/// Lists or prunes the cached clones of remote repositories below `root`.
+fn run_cache_command<W: std::io::Write>(
+    action: &cli::CacheAction,
+    root: Option<&Path>,
+    out: &mut W,
+) -> Result<(), ApplicationError> {
+    let root = root.ok_or_else(|| {
+        ApplicationError::Cache(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "cannot find a home directory for the clone cache",
+        ))
+    })?;
+    match action {
+        cli::CacheAction::List => {
+            let repositories = cache::cached_repositories(root)
+                .map_err(ApplicationError::Cache)?;
+            if repositories.is_empty() {
+                writeln!(out, "No cached repositories in '{}'", root.display())
+            } else {
+                let size = repositories
+                    .iter()
+                    .map(|repository| repository.size)
+                    .sum::<u64>();
+                let count = repositories.len();
+                let rows = repositories
+                    .into_iter()
+                    .map(|repository| CachedRow {
+                        name: repository.name,
+                        size: repository.size,
+                        last_used: repository.last_used.map_or_else(
+                            || "never".to_string(),
+                            |seconds| {
+                                history::format_time(git2::Time::new(
+                                    seconds, 0,
+                                ))
+                            },
+                        ),
+                    })
+                    .collect::<Vec<_>>();
+                let table = Table::new(rows).with(Style::empty()).to_string();
+                writeln!(
+                    out,
+                    "{table}\n\n{count} cached repositories, {size} bytes in '{}'",
+                    root.display()
+                )
+            }
+        }
+        cli::CacheAction::Prune { older_than } => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs() as i64);
+            let cutoff = older_than.map(|age| now - age as i64);
+            let removed = cache::prune_cache(root, cutoff)
+                .map_err(ApplicationError::Cache)?;
+            let size = removed
+                .iter()
+                .map(|repository| repository.size)
+                .sum::<u64>();
+            removed
+                .iter()
+                .try_for_each(|repository| {
+                    writeln!(out, "-> Removed '{}'", repository.name)
+                })
+                .and_then(|()| {
+                    writeln!(
+                        out,
+                        "Removed {} cached repositories ({size} bytes)",
+                        removed.len()
+                    )
+                })
+        }
+    }
+    .map_err(ApplicationError::Cache)
+}
+
 fn prepare_tokenizer<N: std::io::Write, D: std::io::Write>(
     params: &Params,
     reporter: &mut progress::ProgressReporter<N, D>,
@@ -147,6 +329,10 @@ enum ApplicationError {
     Clone(git2::Error),
     CurrentDirectory(git2::Error),
     Output(std::io::Error),
+    Listing(std::io::Error),
+    Archive(std::io::Error),
+    Cache(std::io::Error),
+    Config(std::io::Error),
 }
 
 impl ApplicationError {
@@ -156,6 +342,10 @@ impl ApplicationError {
             Self::Clone(_) => 2,
             Self::CurrentDirectory(_) => 3,
             Self::Output(_) => 4,
+            Self::Listing(_) => 5,
+            Self::Archive(_) => 6,
+            Self::Cache(_) => 7,
+            Self::Config(_) => 8,
         }
     }
 }
@@ -170,7 +360,235 @@ impl fmt::Display for ApplicationError {
             Self::Output(error) => {
                 write!(formatter, "X  Failed to write XML: {error}")
             }
+            Self::Listing(error)
+            | Self::Archive(error)
+            | Self::Cache(error)
+            | Self::Config(error) => {
+                write!(formatter, "Error: {error}")
+            }
+        }
+    }
+}
+
+fn resolve_repository(
+    args: &cli::Flags,
+    params: &Params,
+    repository_path: &Path,
+    temp_dir: &Path,
+) -> Result<Source, ApplicationError> {
+    if let Some(ref repo_input) = args.repo
+        && let Some(format) = archive::archive_input(repo_input)
+    {
+        if params.git_ref.is_some() || changes::comparison(params).is_some() {
+            return Err(ApplicationError::Archive(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "--ref, --diff, --uncommitted and --staged cannot be used with an archive",
+            )));
+        }
+        if !params.stdout {
+            println!("-> Unpacking archive '{repo_input}'");
+        }
+        return archive::EntryFilter::new(params)
+            .and_then(|filter| {
+                archive::extract_archive(
+                    Path::new(repo_input),
+                    format,
+                    &temp_dir.join("archive"),
+                    &filter,
+                )
+            })
+            .map(Source::Directory)
+            .map_err(ApplicationError::Archive);
+    }
+
+    let local_path =
+        args.repo.as_deref().and_then(repo::local_repository_path);
+    if let Some(ref repo_input) = args.repo
+        && local_path.is_none()
+    {
+        if args.no_git {
+            return Err(ApplicationError::Clone(git2::Error::from_str(
+                "--no-git only applies to local directories",
+            )));
+        }
+        if params.uncommitted || params.staged {
+            return Err(ApplicationError::Clone(git2::Error::from_str(
+                "--uncommitted and --staged only apply to local repositories",
+            )));
+        }
+        return repo::clone_repo(
+            params,
+            repo_input,
+            params.token.as_deref(),
+            temp_dir,
+        )
+        .map(Source::Tree)
+        .map_err(ApplicationError::Clone);
+    }
+
+    let repository_path = local_path.as_deref().unwrap_or(repository_path);
+    // Path scopes are relative to the repository root, not to the
+    // directory the tool was started from. A plain directory is its own
+    // root.
+    let from_root = params.from_root
+        || params.paths.is_some()
+        || params.packages.is_some()
+        || params.files_from.is_some()
+        || params.follow.is_some()
+        || changes::comparison(params).is_some();
+    // A bare repository, such as a mirror, has no working tree, so its HEAD
+    // is bundled unless a ref is given.
+    let bare = !params.no_git && repo::is_bare_repository(repository_path);
+    if params.git_ref.is_some() || bare {
+        let git_ref = params.git_ref.as_deref().unwrap_or("HEAD");
+        return repo::read_ref_at(repository_path, git_ref, from_root, params)
+            .map(Source::Tree)
+            .map_err(ApplicationError::CurrentDirectory);
+    }
+
+    repo::check_repository_at(repository_path, params)
+        .map_err(ApplicationError::CurrentDirectory)?;
+    if params.no_git {
+        Ok(Source::Directory(repository_path.to_path_buf()))
+    } else if from_root {
+        repo::repository_root(repository_path)
+            .map(Source::Directory)
+            .map_err(ApplicationError::CurrentDirectory)
+    } else {
+        Ok(Source::Directory(repository_path.to_path_buf()))
+    }
+}
+
+/// Opens the local repository containing the bundled directory. A tree is
+/// read from its own repository instead.
+fn source_repository(
+    args: &cli::Flags,
+    repository_path: &Path,
+) -> Result<git2::Repository, ApplicationError> {
+    let local_path =
+        args.repo.as_deref().and_then(repo::local_repository_path);
+    git2::Repository::discover(
+        local_path.as_deref().unwrap_or(repository_path),
+    )
+    .map_err(|_| {
+        ApplicationError::CurrentDirectory(git2::Error::from_str(
+            "Not a git repository",
+        ))
+    })
+}
+
+/// Reads the `--history`, `--last-commit` and `--hotspots` details for the
+/// bundled files
+/// from the commit being bundled. The files are relative to the bundled
+/// source, which may be a subdirectory of the repository or of a ref's tree.
+fn repository_history(
+    args: &cli::Flags,
+    params: &Params,
+    repository_path: &Path,
+    source: &Source,
+    paths: &[String],
+) -> Result<history::GitHistory, ApplicationError> {
+    // A tree is read from its own repository, at the commit it was read
+    // from; a working tree from the repository around it, at HEAD.
+    let opened;
+    let (repository, start, prefix) = match source {
+        Source::Tree(tree) => {
+            (tree.repository(), tree.git_ref(), tree.prefix().to_string())
+        }
+        Source::Directory(folder) => {
+            opened = source_repository(args, repository_path)?;
+            let prefix = opened
+                .workdir()
+                .and_then(|workdir| workdir.canonicalize().ok())
+                .zip(folder.canonicalize().ok())
+                .and_then(|(workdir, folder)| {
+                    folder
+                        .strip_prefix(workdir)
+                        .ok()
+                        .map(filelist::repository_path)
+                })
+                .unwrap_or_default();
+            (&opened, "HEAD", prefix)
+        }
+    };
+    let mut git_history = history::git_history(
+        repository,
+        start,
+        &prefix,
+        paths,
+        params.history.unwrap_or(0),
+        params.last_commit,
+    )
+    .map_err(ApplicationError::CurrentDirectory)?;
+    if history::ranks_hotspots(params) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        git_history.hotspots = history::hotspots(
+            repository,
+            start,
+            &prefix,
+            paths,
+            now - history::RECENT_CHURN_DAYS * 24 * 60 * 60,
+        )
+        .map_err(ApplicationError::CurrentDirectory)?;
+    }
+    Ok(git_history)
+}
+
+/// Computes the files changed by `--diff-base`, `--diff`, `--uncommitted` or
+/// `--staged`, reading them from the repository a tree is read from, such as
+/// the clone of a remote repository, or from the local repository.
+fn diff_changes(
+    args: &cli::Flags,
+    params: &Params,
+    repository_path: &Path,
+    source: &Source,
+) -> Result<Option<changes::ChangedFiles>, ApplicationError> {
+    let Some(comparison) = changes::comparison(params) else {
+        return Ok(None);
+    };
+    let opened;
+    let repository = match source {
+        Source::Tree(tree) => tree.repository(),
+        Source::Directory(_) => {
+            opened = source_repository(args, repository_path)?;
+            &opened
+        }
+    };
+    let changes =
+        changes::changed_files(repository, &comparison, params.diff_patch)
+            .map_err(ApplicationError::CurrentDirectory)?;
+    changes::ChangedFiles::new(source.files(), params, changes)
+        .map(Some)
+        .map_err(ApplicationError::Listing)
+}
+
+fn run_dry_run<N: std::io::Write, D: std::io::Write>(
+    args: &cli::Flags,
+    params: &Params,
+    files: &dyn FileSource,
+    changes: Option<&changes::ChangedFiles>,
+    reporter: &mut progress::ProgressReporter<N, D>,
+) -> std::io::Result<()> {
+    if let Some(ref path) = args.explain {
+        let mut candidate = dry_run::explain_path(files, path, params)?;
+        if let Some(changes) = changes {
+            changes.exclude_unselected(&mut candidate);
+        }
+        reporter.listing(&candidate.to_string())
+    } else {
+        let mut candidates = dry_run::dry_run_candidates(files, params)?;
+        if let Some(changes) = changes {
+            for candidate in &mut candidates {
+                changes.exclude_unselected(candidate);
+            }
         }
+        dry_run::write_candidates(reporter, &candidates)?;
+        if let Some(graph) = follow::dependency_graph(files, params)? {
+            reporter.listing(&format!("\n{graph}"))?;
+        }
+        Ok(())
     }
 }
 
@@ -181,36 +599,86 @@ fn run_application<N: std::io::Write, D: std::io::Write>(
     reporter: &mut progress::ProgressReporter<N, D>,
     timings: &mut timings::ProcessingTimings,
 ) -> Result<(), ApplicationError> {
-    let (model, tokenizer) = prepare_tokenizer(params, reporter, timings)
-        .map_err(ApplicationError::Tokenizer)?;
     let temp_dir = tempdir().unwrap();
 
-    let repo_folder = if let Some(ref repo_input) = args.repo {
-        repo::clone_repo(
+    if args.dry_run || args.explain.is_some() {
+        let source = resolve_repository(
+            args,
             params,
-            repo_input,
-            params.token.as_deref(),
+            repository_path,
             temp_dir.path(),
-        )
-        .map_err(ApplicationError::Clone)?
+        )?;
+        let files = source.files();
+        let changes = diff_changes(args, params, repository_path, &source)?;
+        return run_dry_run(args, params, files, changes.as_ref(), reporter)
+            .map_err(ApplicationError::Listing);
+    }
+
+    let (model, tokenizer) = prepare_tokenizer(params, reporter, timings)
+        .map_err(ApplicationError::Tokenizer)?;
+    let source =
+        resolve_repository(args, params, repository_path, temp_dir.path())?;
+    let files = source.files();
+    let changes = diff_changes(args, params, repository_path, &source)?;
+
+    let file_list = filelist::list_files_in_repo(files, params)
+        .and_then(|file_list| {
+            follow::retain_reachable_files(files, params, file_list)
+        })
+        .and_then(|file_list| {
+            grep::retain_matching_files(files, params, file_list, timings)
+        })
+        .map(|file_list| match changes {
+            Some(ref changes) => changes.retain(file_list),
+            None => file_list,
+        })
+        .map_err(ApplicationError::Listing)?;
+    for path in &file_list.missing {
+        reporter
+            .warning(&format!(
+                "warning: '{path}' from the file list does not exist in the repository"
+            ))
+            .unwrap();
+    }
+    let mut file_tree = filelist::group_files_by_directory(file_list);
+    if let Some(changes) = changes {
+        let scopes = filelist::path_scopes(files, params)
+            .map_err(ApplicationError::Listing)?;
+        file_tree =
+            changes::add_changes(file_tree, params, &scopes, changes.changes)
+                .map_err(ApplicationError::Listing)?;
+    }
+    if params.history.is_some()
+        || params.last_commit
+        || history::ranks_hotspots(params)
+    {
+        file_tree.history = repository_history(
+            args,
+            params,
+            repository_path,
+            &source,
+            &file_tree.file_paths,
+        )?;
+    }
+    if params.order.as_deref() == Some("hotspots") {
+        history::order_by_hotspots(
+            &mut file_tree.file_paths,
+            &file_tree.history.hotspots,
+        );
     } else {
-        repo::check_repository_at(repository_path, params)
-            .map_err(ApplicationError::CurrentDirectory)?;
-        repository_path.to_path_buf()
+        file_tree.file_paths.sort();
+    }
+    let hotspots = if params.hotspots {
+        file_tree.history.hotspots.clone()
+    } else {
+        Vec::new()
     };
 
-    let file_list = filelist::list_files_in_repo(
-        &repo_folder,
-        params.extend_exclude.as_deref(),
-        params.exclude.as_deref(),
-    );
-    let file_tree = filelist::group_files_by_directory(file_list);
-
     reporter.phase("Reading files and generating XML").unwrap();
     let metrics = xml_output::output_repo_as_xml_with_timings(
         params,
         file_tree,
-        &repo_folder,
+        files,
         &tokenizer,
         model.display_name(),
         reporter,
@@ -218,6 +686,9 @@ fn run_application<N: std::io::Write, D: std::io::Write>(
     )
     .map_err(ApplicationError::Output)?;
     report_success(params, model, metrics, reporter).unwrap();
+    if params.hotspots && !params.stdout {
+        report_hotspots(&hotspots, reporter).unwrap();
+    }
 
     Ok(())
 }
@@ -234,7 +705,45 @@ fn main() {
 
     // Load config values
     let config = load_config();
-    let params = Params::from_args_and_config(&args, config);
+    if let Some(cli::Command::Cache { ref action }) = args.command {
+        let root = cache::cache_root(&config);
+        if let Err(error) =
+            run_cache_command(action, root.as_deref(), &mut std::io::stdout())
+        {
+            eprintln!("{error}");
+            exit(error.exit_code());
+        }
+        exit(0);
+    }
+    let mut params = Params::from_args_and_config(&args, config);
+    if let Some(ref source) = args.files_from {
+        match filelist::read_files_from(source) {
+            Ok(entries) => params.files_from = Some(entries),
+            Err(error) => {
+                let error = ApplicationError::Listing(error);
+                eprintln!("{error}");
+                exit(error.exit_code());
+            }
+        }
+    }
+
+    if let Some(ref repo_input) = args.repo
+        && repo::local_repository_path(repo_input).is_some()
+        && let Some(warning) =
+            repo::shadowed_shorthand_warning(repo_input, &params.hosts)
+    {
+        eprintln!("{warning}");
+    }
+    if let Some(ref source) = args.token_file {
+        match auth::read_token_file(source) {
+            Ok(token) => params.token = Some(token),
+            Err(error) => {
+                let error = ApplicationError::Config(error);
+                eprintln!("{error}");
+                exit(error.exit_code());
+            }
+        }
+    }
 
     if let Err(error) = xml_output::validate_output_options(&params) {
         eprintln!("Error: {error}");
@@ -0,0 +1,170 @@
+use crate::filelist::{is_within_scope, repository_relative_path};
+use crate::source::FileSource;
+use crate::structs::Params;
+use crate::symbol::symbol_lines;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::LazyLock;
+
+static SYMBOL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[A-Za-z_$][A-Za-z0-9_$]*(?:::[A-Za-z_$][A-Za-z0-9_$]*)*$")
+        .unwrap()
+});
+
+/// An inclusive range of line numbers, counted from 1.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct LineRange {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+/// A path to bundle, optionally narrowed to a range of lines or to the
+/// definition of a symbol.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct PathSpec {
+    pub(crate) path: String,
+    pub(crate) lines: Option<LineRange>,
+    pub(crate) symbol: Option<String>,
+}
+
+impl PathSpec {
+    /// Returns `true` if only part of the file is selected.
+    pub(crate) const fn is_partial(&self) -> bool {
+        self.lines.is_some() || self.symbol.is_some()
+    }
+}
+
+/// Parses `path`, `path:LINE`, `path:START-END` or `path::SYMBOL`, where the
+/// symbol may be nested as in `Type::method`. A suffix that is neither a line
+/// range nor a symbol is taken as part of the path.
+pub(crate) fn parse_path_spec(value: &str) -> Result<PathSpec, String> {
+    let whole_path = || PathSpec {
+        path: value.to_string(),
+        lines: None,
+        symbol: None,
+    };
+    if let Some((path, symbol)) = value.split_once("::")
+        && !path.is_empty()
+        && SYMBOL.is_match(symbol)
+    {
+        return Ok(PathSpec {
+            path: path.to_string(),
+            lines: None,
+            symbol: Some(symbol.to_string()),
+        });
+    }
+    let Some((path, range)) = value.rsplit_once(':') else {
+        return Ok(whole_path());
+    };
+    let (start, end) = range.split_once('-').unwrap_or((range, range));
+    let is_number = |part: &str| {
+        !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit())
+    };
+    if path.is_empty() || !is_number(start) || !is_number(end) {
+        return Ok(whole_path());
+    }
+
+    let invalid = || {
+        format!(
+            "invalid line range '{range}' in '{value}': lines are counted from 1 \
+             and the range must not end before it starts"
+        )
+    };
+    let start = start.parse::<usize>().map_err(|_| invalid())?;
+    let end = end.parse::<usize>().map_err(|_| invalid())?;
+    if start == 0 || end < start {
+        return Err(invalid());
+    }
+    Ok(PathSpec {
+        path: path.to_string(),
+        lines: Some(LineRange { start, end }),
+        symbol: None,
+    })
+}
+
+/// Parses a path spec for use while listing files.
+pub(crate) fn path_spec(value: &str) -> io::Result<PathSpec> {
+    parse_path_spec(value)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
+}
+
+/// Returns the path specs selecting what to bundle: the `--files-from`
+/// entries when given, otherwise the `--path` scopes.
+pub(crate) fn selected_path_specs(
+    flags: &Params,
+) -> impl Iterator<Item = &String> {
+    flags
+        .files_from
+        .as_ref()
+        .or(flags.paths.as_ref())
+        .into_iter()
+        .flatten()
+}
+
+/// Returns the line ranges selected for each repository file, with symbols
+/// resolved to the lines of their definitions. A file that is also selected
+/// whole, directly or through its folder, has no ranges.
+pub(crate) fn line_ranges(
+    source: &(impl FileSource + ?Sized),
+    flags: &Params,
+) -> io::Result<BTreeMap<String, Vec<LineRange>>> {
+    let mut whole = Vec::new();
+    let mut ranges = BTreeMap::<String, Vec<LineRange>>::new();
+    for value in selected_path_specs(flags) {
+        let spec = path_spec(value)?;
+        let path = repository_relative_path(source, &spec.path)?;
+        let range = match (spec.lines, spec.symbol) {
+            (Some(range), _) => range,
+            // Listed files that do not exist are reported as missing.
+            (None, Some(_)) if !source.has_file(&path) => continue,
+            (None, Some(symbol)) => {
+                let content = source.read_to_string(&path)?;
+                symbol_lines(&path, &content, &symbol)?
+            }
+            (None, None) => {
+                whole.push(path);
+                continue;
+            }
+        };
+        ranges.entry(path).or_default().push(range);
+    }
+    ranges.retain(|path, ranges| {
+        ranges.sort_by_key(|range| (range.start, range.end));
+        ranges.dedup();
+        !whole.iter().any(|scope| is_within_scope(path, scope))
+    });
+    Ok(ranges)
+}
+
+/// Renders the lines of `range` that exist in the file, numbered as in the
+/// original file when requested. Returns the last line rendered.
+pub(crate) fn render_line_range(
+    lines: &[&str],
+    range: LineRange,
+    line_numbers: bool,
+) -> Option<(String, usize)> {
+    let end = range.end.min(lines.len());
+    if range.start > end {
+        return None;
+    }
+    let width = lines.len().to_string().len();
+    let mut rendered = lines[range.start - 1..end]
+        .iter()
+        .zip(range.start..)
+        .map(|(line, number)| {
+            if line_numbers {
+                format!("{number:>width$}  {line}")
+            } else {
+                (*line).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    rendered.push('\n');
+    Some((rendered, end))
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/path_spec.rs"]
+mod tests;
@@ -61,6 +61,12 @@ impl<N: Write, D: Write> ProgressReporter<N, D> {
         Ok(())
     }
 
+    /// Writes requested listing output, such as a dry run, even when ordinary
+    /// progress output is quiet.
+    pub(crate) fn listing(&mut self, message: &str) -> io::Result<()> {
+        writeln!(self.normal, "{message}")
+    }
+
     pub(crate) fn warning(&mut self, message: &str) -> io::Result<()> {
         if !self.quiet {
             writeln!(self.diagnostic, "{message}")?;
@@ -1,74 +1,246 @@
-use git2::{
-    Cred, ErrorClass, ErrorCode, FetchOptions, RemoteCallbacks, Repository,
-};
+use dirs_next::home_dir;
+use git2::{ErrorClass, ErrorCode, FetchOptions, RemoteCallbacks, Repository};
 use regex::Regex;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use url::Url;
 
+use crate::auth::Authenticator;
+use crate::cache::{self, FetchTarget};
+use crate::changes;
+use crate::filelist::repository_path;
+use crate::history;
+use crate::source::TreeSource;
 use crate::structs::Params;
 
-pub fn clone_repo(
+/// The forges with a built-in `prefix:owner/repo` shorthand, and whether
+/// they nest repositories in groups.
+const FORGES: [(&str, &str, bool); 4] = [
+    ("github", "https://github.com", false),
+    ("gitlab", "https://gitlab.com", true),
+    ("bitbucket", "https://bitbucket.org", false),
+    ("codeberg", "https://codeberg.org", false),
+];
+
+/// The URL schemes a repository can be given with instead of a shorthand.
+const URL_SCHEMES: [&str; 5] = ["http", "https", "ssh", "git", "file"];
+
+/// Bundles a remote repository from its cached clone, which is created or
+/// brought up to date first unless `--offline` is set. With `--no-cache`, a
+/// single-use clone is made in `temp_dir` instead. The ref, branch or default
+/// branch is read from the clone's objects.
+pub(crate) fn clone_repo(
     flags: &Params,
     repo_input: &str,
     token: Option<&str>,
-    temp_dir_path: &Path,
-) -> Result<PathBuf, git2::Error> {
-    if !flags.stdout {
-        println!("-> Cloning repository...");
+    temp_dir: &Path,
+) -> Result<TreeSource, git2::Error> {
+    let repo_url = remote_url(repo_input, &flags.hosts)
+        .map_err(|error| git2::Error::from_str(&error))?;
+    if flags.no_cache && flags.offline {
+        return Err(git2::Error::from_str(
+            "--offline reads the clone cache, which --no-cache turns off",
+        ));
     }
-
-    let repo_url = if is_valid_url(repo_input) {
-        repo_input.to_string()
-    } else if is_valid_shorthand(repo_input) {
-        format!("https://github.com/{}.git", repo_input)
+    let cache_path = if flags.no_cache {
+        temp_dir.join("repo_clone")
     } else {
-        return Err(git2::Error::from_str("Invalid repository shorthand"));
+        cached_clone_path(flags, repo_input).ok_or_else(|| {
+            git2::Error::from_str(
+                "Cannot find a directory for the clone cache",
+            )
+        })?
     };
+    let display_url = repo_url.trim_end_matches(".git");
 
-    let repo_folder = temp_dir_path.join("repo_clone");
-
-    let mut callbacks = RemoteCallbacks::new();
-    callbacks.credentials(move |_url, _username_from_url, _allowed_types| {
-        if let Some(token) = token {
-            Cred::userpass_plaintext("oauth2", token)
-        } else {
-            Cred::userpass_plaintext("", "")
-        }
+    // The branch, which may come from the configuration file, is bundled
+    // unless a ref is given in its place.
+    let branch = flags.branch.as_deref().filter(|branch| {
+        flags
+            .git_ref
+            .as_deref()
+            .is_none_or(|git_ref| git_ref == *branch)
     });
 
-    let mut fetch_options = FetchOptions::new();
-    fetch_options.remote_callbacks(callbacks).depth(1);
-
-    let mut builder = git2::build::RepoBuilder::new();
-    builder.fetch_options(fetch_options);
+    let repository = if flags.offline {
+        if !flags.stdout {
+            println!("-> Using the cached clone of '{display_url}' (offline)");
+        }
+        cache::open_cached_clone(&cache_path).map_err(|_| {
+            git2::Error::from_str(&format!(
+                "The repository '{repo_input}' is not in the clone cache. Run once without --offline to fetch it."
+            ))
+        })?
+    } else {
+        if !flags.stdout {
+            if cache_path.join("HEAD").is_file() {
+                println!("-> Fetching updates into the cached clone...");
+            } else {
+                println!("-> Cloning repository...");
+            }
+        }
+        let ssh_key =
+            flags.ssh_key.as_deref().map(Path::new).map(|path| {
+                match (path.strip_prefix("~"), home_dir()) {
+                    (Ok(relative_path), Some(home)) => {
+                        home.join(relative_path)
+                    }
+                    _ => path.to_path_buf(),
+                }
+            });
+        let mut authenticator = Authenticator::new(
+            token,
+            ssh_key,
+            home_dir().map(|home| home.join(".ssh")).as_deref(),
+            git2::Config::open_default().ok(),
+        );
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(move |url, username_from_url, allowed| {
+            authenticator.credentials(url, username_from_url, allowed)
+        });
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(callbacks);
+        // Comparisons may name any ref, and history needs every commit.
+        let target = match (flags.git_ref.as_deref(), branch) {
+            _ if changes::comparison(flags).is_some() => {
+                FetchTarget::Everything
+            }
+            (_, Some(branch)) => FetchTarget::Branch(branch),
+            (Some(git_ref), None) if git_ref != "HEAD" => {
+                FetchTarget::Ref(git_ref)
+            }
+            _ => FetchTarget::DefaultBranch,
+        };
+        let full_history = flags.history.is_some()
+            || flags.last_commit
+            || history::ranks_hotspots(flags);
+        let repository = cache::fetch_into_cache(
+            &cache_path,
+            &repo_url,
+            target,
+            full_history,
+            &mut fetch_options,
+        )
+        .map_err(|error| {
+            git2::Error::from_str(&clone_error_message(
+                repo_input, None, &error,
+            ))
+        })?;
+        if !flags.stdout {
+            println!("-> Successfully fetched repository '{display_url}'");
+        }
+        repository
+    };
 
-    if let Some(branch_name) = &flags.branch {
-        builder.branch(branch_name);
+    if let Some(branch_name) = branch {
+        if let Err(error) =
+            repository.find_reference(&format!("refs/heads/{branch_name}"))
+        {
+            return Err(git2::Error::from_str(&clone_error_message(
+                repo_input,
+                Some(branch_name),
+                &error,
+            )));
+        }
         if !flags.stdout {
-            println!("-> Checking out branch: {}", branch_name);
+            println!("-> Checking out branch: {branch_name}");
         }
     }
 
-    match builder.clone(&repo_url, &repo_folder) {
-        Ok(_) => {
-            if !flags.stdout {
-                println!(
-                    "-> Successfully cloned repository '{}'{}",
-                    repo_url.trim_end_matches(".git"),
-                    flags.branch.as_ref().map_or(String::new(), |b| format!(
-                        " (branch: {})",
-                        b
-                    ))
-                );
-            }
-            Ok(repo_folder)
-        }
-        Err(error) => Err(git2::Error::from_str(&clone_error_message(
-            repo_input,
-            flags.branch.as_deref(),
-            &error,
-        ))),
+    let git_ref = flags.git_ref.as_deref().or(branch).unwrap_or("HEAD");
+    read_ref(repository, git_ref, "", flags)
+}
+
+/// Returns the URL a remote repository is fetched from: the URL itself, the
+/// GitHub URL of an `owner/repo` shorthand, or the URL of a
+/// `prefix:owner/repo` shorthand. The prefix names a built-in forge or a
+/// host from the `[hosts]` configuration table, which takes precedence over
+/// the forges and over git's scp-like SSH syntax.
+pub(crate) fn remote_url(
+    repo_input: &str,
+    hosts: &BTreeMap<String, String>,
+) -> Result<String, String> {
+    let configured_prefix = repo_input
+        .split_once(':')
+        .is_some_and(|(prefix, _)| hosts.contains_key(prefix));
+    if !configured_prefix && scp_url(repo_input).is_some() {
+        return Ok(repo_input.to_string());
+    }
+    let Some((prefix, path)) =
+        repo_input.split_once(':').filter(|(prefix, _)| {
+            !URL_SCHEMES.contains(&prefix.to_ascii_lowercase().as_str())
+        })
+    else {
+        return if is_valid_url(repo_input) {
+            Ok(repo_input.to_string())
+        } else if is_valid_shorthand(repo_input) {
+            Ok(format!("https://github.com/{repo_input}.git"))
+        } else {
+            Err("Invalid repository shorthand".to_string())
+        };
+    };
+
+    let (base_url, nested) = hosts
+        .get(prefix)
+        .map(|base_url| (base_url.as_str(), true))
+        .or_else(|| {
+            FORGES
+                .iter()
+                .find(|(name, _, _)| *name == prefix)
+                .map(|(_, base_url, nested)| (*base_url, *nested))
+        })
+        .ok_or_else(|| {
+            format!(
+                "Unknown forge '{prefix}' in '{repo_input}'. Use github, gitlab, bitbucket or codeberg, or add the host under [hosts] in the configuration file."
+            )
+        })?;
+    if !is_repository_path(path, nested) {
+        let form = if nested {
+            "group/.../repo"
+        } else {
+            "owner/repo"
+        };
+        return Err(format!(
+            "Invalid repository shorthand '{repo_input}', expected '{prefix}:{form}'"
+        ));
     }
+    Ok(format!(
+        "{}/{}.git",
+        base_url.trim_end_matches('/'),
+        path.trim_end_matches(".git")
+    ))
+}
+
+/// Returns where the clone of a remote repository is cached.
+pub(crate) fn cached_clone_path(
+    flags: &Params,
+    repo_input: &str,
+) -> Option<PathBuf> {
+    let root = cache::cache_root(flags)?;
+    let repo_url = remote_url(repo_input, &flags.hosts).ok()?;
+    cache::cache_path(&root, &scp_url(&repo_url).unwrap_or(repo_url))
+}
+
+/// Returns the host a remote repository is fetched from, which picks its
+/// token.
+pub(crate) fn remote_host(
+    repo_input: &str,
+    hosts: &BTreeMap<String, String>,
+) -> Option<String> {
+    let repo_url = remote_url(repo_input, hosts).ok()?;
+    let url = Url::parse(&scp_url(&repo_url).unwrap_or(repo_url)).ok()?;
+    url.host_str().map(str::to_ascii_lowercase)
+}
+
+/// Rewrites git's scp-like SSH syntax, `user@host:path`, as an `ssh://` URL.
+/// The host tells it apart from a forge shorthand: it has a user or a dot.
+fn scp_url(repo_input: &str) -> Option<String> {
+    let (host, path) = repo_input.split_once(':')?;
+    let scp = !repo_input.contains("://")
+        && !path.is_empty()
+        && (host.contains('@') || host.contains('.'))
+        && !host.contains('/');
+    scp.then(|| format!("ssh://{host}/{}", path.trim_start_matches('/')))
 }
 
 fn clone_error_message(
@@ -98,6 +270,8 @@ fn clone_error_message(
                 "The repository '{repo_input}' does not exist or requires authentication.\nIf it's a private repository, please provide a valid token using the --token option."
             )
         }
+        // The credentials callback names the methods it tried.
+        (_, ErrorCode::Auth) => error.message().to_string(),
         _ => format!("Failed to clone: {error}"),
     }
 }
@@ -107,31 +281,154 @@ pub fn is_valid_url(input: &str) -> bool {
 }
 
 pub fn is_valid_shorthand(input: &str) -> bool {
-    let re = Regex::new(r"^[\w\-]+/[\w\-]+$").unwrap();
-    re.is_match(input)
+    is_repository_path(input, false)
+}
+
+/// Checks an `owner/repo` path, or with `nested` a `group/subgroup/repo`
+/// path of any depth. No segment may be `.` or `..`.
+fn is_repository_path(path: &str, nested: bool) -> bool {
+    let pattern = if nested {
+        r"^[\w\-][\w.\-]*(/[\w\-][\w.\-]*)+$"
+    } else {
+        r"^[\w\-][\w.\-]*/[\w\-][\w.\-]*$"
+    };
+    Regex::new(pattern).unwrap().is_match(path)
+}
+
+/// Returns the local directory named by the positional repository argument:
+/// any `file://` URL, or a path to an existing directory. Anything else is
+/// cloned.
+pub(crate) fn local_repository_path(repo_input: &str) -> Option<PathBuf> {
+    if repo_input.starts_with("file://") {
+        return Url::parse(repo_input).ok()?.to_file_path().ok();
+    }
+    let path = Path::new(repo_input);
+    path.is_dir().then(|| path.to_path_buf())
+}
+
+/// Warns when a relative path that names a local directory could also be
+/// read as a repository shorthand, since the directory is bundled in its
+/// place. Paths starting with `./`, `../` or `/` are plainly local.
+pub(crate) fn shadowed_shorthand_warning(
+    repo_input: &str,
+    hosts: &BTreeMap<String, String>,
+) -> Option<String> {
+    let explicit_path = Path::new(repo_input).is_absolute()
+        || ["./", "../", ".\\", "..\\"]
+            .iter()
+            .any(|prefix| repo_input.starts_with(prefix))
+        || repo_input.contains("://");
+    if explicit_path {
+        return None;
+    }
+    let repo_url = remote_url(repo_input, hosts).ok()?;
+    let remote = if repo_input.contains(':') {
+        scp_url(&repo_url).unwrap_or(repo_url)
+    } else {
+        format!("github:{repo_input}")
+    };
+    Some(format!(
+        "warning: '{repo_input}' is a local directory and is bundled as one, not as the remote repository. Use '{remote}' for the remote repository, or './{repo_input}' to silence this warning."
+    ))
+}
+
+/// Returns whether `path` is a bare repository, such as a mirror, which has
+/// no working tree to bundle.
+pub(crate) fn is_bare_repository(path: &Path) -> bool {
+    Repository::open(path).is_ok_and(|repository| repository.is_bare())
 }
 
 pub(crate) fn check_repository_at(
     path: &Path,
     flags: &Params,
 ) -> Result<(), git2::Error> {
+    if flags.no_git {
+        if !flags.stdout {
+            println!(
+                "-> Bundling a directory without git: '{}'",
+                path.display()
+            );
+        }
+        return Ok(());
+    }
+
     match Repository::discover(path) {
         Ok(repo) => {
             if !flags.stdout {
                 let repo_path = repo.path().parent().unwrap().display();
                 let branch_name = get_current_branch_name(&repo)?;
                 println!(
-                    "-> Found a git repository in the current directory: '{}' (branch: {})",
+                    "-> Found a git repository: '{}' (branch: {})",
                     repo_path, branch_name
                 );
             }
             Ok(())
         }
-        Err(_) => {
-            eprintln!("X  No git repository found in the current directory.");
-            Err(git2::Error::from_str("Not a git repository"))
-        }
+        Err(_) => Err(git2::Error::from_str(&format!(
+            "No git repository found at '{}'. Use --no-git to bundle it as a plain directory.",
+            path.display()
+        ))),
+    }
+}
+
+/// Reads the tree of `git_ref` below `prefix` from the object database, so
+/// that neither the working tree nor the index is consulted and no file is
+/// written to disk.
+pub(crate) fn read_ref(
+    repository: Repository,
+    git_ref: &str,
+    prefix: &str,
+    flags: &Params,
+) -> Result<TreeSource, git2::Error> {
+    let commit = repository
+        .revparse_single(git_ref)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| {
+            git2::Error::from_str(&format!(
+                "The ref '{git_ref}' does not exist in the repository."
+            ))
+        })?
+        .id();
+    if !flags.stdout {
+        println!(
+            "-> Reading ref '{git_ref}' (commit {})",
+            &commit.to_string()[..12]
+        );
     }
+    TreeSource::new(repository, git_ref, prefix)
+}
+
+/// Reads `git_ref` from the repository containing `path`: the tree of
+/// `path` itself, or of the repository root when `from_root` is set.
+pub(crate) fn read_ref_at(
+    path: &Path,
+    git_ref: &str,
+    from_root: bool,
+    flags: &Params,
+) -> Result<TreeSource, git2::Error> {
+    let repository = Repository::discover(path)
+        .map_err(|_| git2::Error::from_str("Not a git repository"))?;
+    let workdir = repository
+        .workdir()
+        .and_then(|workdir| workdir.canonicalize().ok());
+    let prefix = path
+        .canonicalize()
+        .ok()
+        .zip(workdir)
+        .filter(|_| !from_root)
+        .and_then(|(path, workdir)| {
+            path.strip_prefix(workdir).ok().map(repository_path)
+        })
+        .unwrap_or_default();
+    read_ref(repository, git_ref, &prefix, flags)
+}
+
+/// Returns the working directory of the repository containing `path`.
+pub(crate) fn repository_root(path: &Path) -> Result<PathBuf, git2::Error> {
+    let repo = Repository::discover(path)?;
+    repo.workdir().map(Path::to_path_buf).ok_or_else(|| {
+        git2::Error::from_str("Repository has no working directory")
+    })
 }
 
 fn get_current_branch_name(repo: &Repository) -> Result<String, git2::Error> {
@@ -0,0 +1,301 @@
+use git2::{ObjectType, Oid, Repository, TreeWalkMode, TreeWalkResult};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Mode bits git records for a symbolic link in a tree.
+const SYMLINK_MODE: i32 = 0o120000;
+
+/// The kind of an entry in a [`FileSource`]. Symbolic links are reported as
+/// links, not as the entry they point to.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+}
+
+/// The files to bundle, addressed by repository-relative paths with `/`
+/// separators. The empty path names the root.
+pub(crate) trait FileSource {
+    /// Returns the directory on disk the files are read from, if any.
+    fn directory(&self) -> Option<&Path>;
+
+    /// Returns the kind of the entry at `path` without following links.
+    fn kind(&self, path: &str) -> io::Result<EntryKind>;
+
+    /// Returns `true` if `path` is a file, following links on disk.
+    fn has_file(&self, path: &str) -> bool;
+
+    /// Returns `true` if `path` is a directory, following links on disk.
+    fn has_dir(&self, path: &str) -> bool;
+
+    /// Lists the names and kinds of the entries in the directory at `path`,
+    /// in no particular order.
+    fn entries(&self, path: &str) -> io::Result<Vec<(String, EntryKind)>>;
+
+    fn open_file(&self, path: &str) -> io::Result<Box<dyn Read + '_>>;
+
+    /// Returns the size of the file at `path` in bytes.
+    fn file_size(&self, path: &str) -> io::Result<u64>;
+
+    /// Returns when the file at `path` was last modified.
+    fn modified_time(&self, path: &str) -> io::Result<SystemTime>;
+
+    /// Describes `path` for error messages.
+    fn describe(&self, path: &str) -> String;
+
+    fn read_to_string(&self, path: &str) -> io::Result<String> {
+        let mut content = String::new();
+        self.open_file(path)?.read_to_string(&mut content)?;
+        Ok(content)
+    }
+}
+
+fn entry_kind(file_type: fs::FileType) -> EntryKind {
+    if file_type.is_symlink() {
+        EntryKind::Symlink
+    } else if file_type.is_dir() {
+        EntryKind::Directory
+    } else {
+        EntryKind::File
+    }
+}
+
+impl<P: AsRef<Path> + ?Sized> FileSource for P {
+    fn directory(&self) -> Option<&Path> {
+        Some(self.as_ref())
+    }
+
+    fn kind(&self, path: &str) -> io::Result<EntryKind> {
+        fs::symlink_metadata(self.as_ref().join(path))
+            .map(|metadata| entry_kind(metadata.file_type()))
+    }
+
+    fn has_file(&self, path: &str) -> bool {
+        self.as_ref().join(path).is_file()
+    }
+
+    fn has_dir(&self, path: &str) -> bool {
+        self.as_ref().join(path).is_dir()
+    }
+
+    fn entries(&self, path: &str) -> io::Result<Vec<(String, EntryKind)>> {
+        fs::read_dir(self.as_ref().join(path))?
+            .map(|entry| {
+                let entry = entry?;
+                Ok((
+                    entry.file_name().to_string_lossy().into_owned(),
+                    entry_kind(entry.file_type()?),
+                ))
+            })
+            .collect()
+    }
+
+    fn open_file(&self, path: &str) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(File::open(self.as_ref().join(path))?))
+    }
+
+    fn file_size(&self, path: &str) -> io::Result<u64> {
+        fs::metadata(self.as_ref().join(path)).map(|metadata| metadata.len())
+    }
+
+    fn modified_time(&self, path: &str) -> io::Result<SystemTime> {
+        fs::metadata(self.as_ref().join(path))?.modified()
+    }
+
+    fn describe(&self, path: &str) -> String {
+        self.as_ref().join(path).display().to_string()
+    }
+}
+
+/// The tree of a commit, read from the object database without writing any
+/// file to disk. Paths are relative to `prefix`, the bundled directory's
+/// path in the repository. Submodules are left out.
+pub(crate) struct TreeSource {
+    repository: Repository,
+    git_ref: String,
+    prefix: String,
+    /// The commit time, reported as the modification time of every file.
+    modified: SystemTime,
+    entries: HashMap<String, (EntryKind, Oid)>,
+    directories: HashMap<String, Vec<(String, EntryKind)>>,
+}
+
+impl TreeSource {
+    /// Reads the tree of `git_ref` below `prefix`. A directory missing from
+    /// the commit yields an empty tree rather than the whole commit.
+    pub(crate) fn new(
+        repository: Repository,
+        git_ref: &str,
+        prefix: &str,
+    ) -> Result<Self, git2::Error> {
+        let mut entries = HashMap::new();
+        let mut directories = HashMap::from([(String::new(), Vec::new())]);
+        let modified = {
+            let commit = repository
+                .revparse_single(git_ref)
+                .and_then(|object| object.peel_to_commit())?;
+            let mut tree = Some(commit.tree()?);
+            if !prefix.is_empty() {
+                tree = tree
+                    .and_then(|tree| tree.get_path(Path::new(prefix)).ok())
+                    .and_then(|entry| entry.to_object(&repository).ok())
+                    .and_then(|object| object.into_tree().ok());
+            }
+            if let Some(tree) = tree {
+                tree.walk(TreeWalkMode::PreOrder, |directory, entry| {
+                    let kind = match entry.kind() {
+                        Some(ObjectType::Tree) => EntryKind::Directory,
+                        Some(ObjectType::Blob)
+                            if entry.filemode() == SYMLINK_MODE =>
+                        {
+                            EntryKind::Symlink
+                        }
+                        Some(ObjectType::Blob) => EntryKind::File,
+                        _ => return TreeWalkResult::Skip,
+                    };
+                    let name = String::from_utf8_lossy(entry.name_bytes());
+                    let parent = directory.trim_end_matches('/');
+                    let path = format!("{directory}{name}");
+                    if kind == EntryKind::Directory {
+                        directories.insert(path.clone(), Vec::new());
+                    }
+                    directories
+                        .entry(parent.to_string())
+                        .or_default()
+                        .push((name.into_owned(), kind));
+                    entries.insert(path, (kind, entry.id()));
+                    TreeWalkResult::Ok
+                })?;
+            }
+            let seconds = u64::try_from(commit.time().seconds()).unwrap_or(0);
+            UNIX_EPOCH + Duration::from_secs(seconds)
+        };
+        Ok(Self {
+            repository,
+            git_ref: git_ref.to_string(),
+            prefix: prefix.to_string(),
+            modified,
+            entries,
+            directories,
+        })
+    }
+
+    /// The repository the tree is read from.
+    pub(crate) fn repository(&self) -> &Repository {
+        &self.repository
+    }
+
+    /// The ref the tree was read from.
+    pub(crate) fn git_ref(&self) -> &str {
+        &self.git_ref
+    }
+
+    /// The bundled directory's path in the repository, empty for its root.
+    pub(crate) fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn entry(&self, path: &str) -> io::Result<(EntryKind, Oid)> {
+        self.entries.get(path).copied().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("'{}' does not exist", self.describe(path)),
+            )
+        })
+    }
+
+    fn file(&self, path: &str) -> io::Result<Oid> {
+        match self.entry(path)? {
+            (EntryKind::File, id) => Ok(id),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("'{}' is not a file", self.describe(path)),
+            )),
+        }
+    }
+}
+
+impl FileSource for TreeSource {
+    fn directory(&self) -> Option<&Path> {
+        None
+    }
+
+    fn kind(&self, path: &str) -> io::Result<EntryKind> {
+        if path.is_empty() {
+            return Ok(EntryKind::Directory);
+        }
+        self.entry(path).map(|(kind, _)| kind)
+    }
+
+    fn has_file(&self, path: &str) -> bool {
+        self.kind(path).is_ok_and(|kind| kind == EntryKind::File)
+    }
+
+    fn has_dir(&self, path: &str) -> bool {
+        self.kind(path)
+            .is_ok_and(|kind| kind == EntryKind::Directory)
+    }
+
+    fn entries(&self, path: &str) -> io::Result<Vec<(String, EntryKind)>> {
+        self.directories.get(path).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("'{}' is not a directory", self.describe(path)),
+            )
+        })
+    }
+
+    fn open_file(&self, path: &str) -> io::Result<Box<dyn Read + '_>> {
+        let blob = self
+            .repository
+            .find_blob(self.file(path)?)
+            .map_err(io::Error::other)?;
+        Ok(Box::new(Cursor::new(blob.content().to_vec())))
+    }
+
+    fn file_size(&self, path: &str) -> io::Result<u64> {
+        let id = self.file(path)?;
+        self.repository
+            .odb()
+            .and_then(|odb| odb.read_header(id))
+            .map(|(size, _)| size as u64)
+            .map_err(io::Error::other)
+    }
+
+    fn modified_time(&self, path: &str) -> io::Result<SystemTime> {
+        self.file(path).map(|_| self.modified)
+    }
+
+    fn describe(&self, path: &str) -> String {
+        let path = [self.prefix.as_str(), path]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("/");
+        format!("{}:{path}", self.git_ref)
+    }
+}
+
+/// What a run bundles: a directory on disk, such as a working tree or an
+/// unpacked archive, or the tree of a commit.
+pub(crate) enum Source {
+    Directory(PathBuf),
+    Tree(TreeSource),
+}
+
+impl Source {
+    pub(crate) fn files(&self) -> &dyn FileSource {
+        match self {
+            Self::Directory(path) => path,
+            Self::Tree(tree) => tree,
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/source.rs"]
+mod tests;
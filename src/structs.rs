@@ -1,6 +1,14 @@
+use crate::archive::archive_input;
+use crate::auth::resolve_token;
+use crate::changes::parse_diff_range;
 use crate::cli;
+use crate::limits::{parse_age, parse_file_size, parse_line_count};
+use crate::repo::{local_repository_path, remote_host};
+use crate::truncation::parse_token_count;
 use config::Config;
 use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
 use std::fmt;
 
 #[derive(Debug)]
@@ -191,6 +199,37 @@ impl<T: TomlValue> TomlValue for Vec<T> {
     }
 }
 
+// The values are read from the table itself rather than by key, since a
+// key such as a host name may contain dots.
+impl TomlValue for BTreeMap<String, String> {
+    const TYPE_NAME: &'static str = "table";
+
+    fn load_from_config(
+        config: &Config,
+        key: &str,
+    ) -> Result<Self, ConfigError> {
+        let type_error = || ConfigError::TypeError {
+            key: key.to_string(),
+            message: format!("Expected {}, got invalid type", Self::TYPE_NAME),
+        };
+        config
+            .get_table(key)
+            .map_err(|e| {
+                if matches!(e, config::ConfigError::NotFound(_)) {
+                    ConfigError::Missing(key.to_string())
+                } else {
+                    type_error()
+                }
+            })?
+            .into_iter()
+            .map(|(name, value)| {
+                let value = value.into_string().map_err(|_| type_error())?;
+                Ok((name, value))
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct Params {
     pub output_file: Option<String>,
@@ -200,15 +239,57 @@ pub struct Params {
     pub line_numbers: bool,
     pub token: Option<String>,
     pub branch: Option<String>,
+    pub git_ref: Option<String>,
+    pub offline: bool,
+    pub no_cache: bool,
+    pub cache_dir: Option<String>,
+    pub hosts: BTreeMap<String, String>,
+    pub tokens: BTreeMap<String, String>,
+    pub ssh_key: Option<String>,
+    pub diff_base: Option<String>,
+    pub diff: Option<String>,
+    pub uncommitted: bool,
+    pub staged: bool,
+    pub diff_patch: bool,
+    pub patch_only: bool,
+    pub with_imports: bool,
+    pub history: Option<usize>,
+    pub last_commit: bool,
+    pub hotspots: bool,
+    pub hotspots_section: bool,
+    pub order: Option<String>,
     pub extend_exclude: Option<Vec<String>>,
     pub exclude: Option<Vec<String>>,
+    pub paths: Option<Vec<String>>,
+    pub files_from: Option<Vec<String>>,
+    pub packages: Option<Vec<String>>,
+    pub package_path_dependencies: bool,
+    pub grep: Option<String>,
+    pub follow: Option<Vec<String>>,
+    pub grep_context: Option<usize>,
+    pub languages: Option<Vec<String>>,
+    pub include_tests: bool,
+    pub include_docs: bool,
+    pub include_examples: bool,
+    pub max_file_size: Option<u64>,
+    pub max_file_lines: Option<usize>,
+    pub modified_within: Option<u64>,
+    pub truncate_lines: Option<usize>,
+    pub truncate_tokens: Option<usize>,
     pub utf8: bool,
     pub gzip: bool,
     pub gzip_level: u32,
+    pub bundlerepoignore: bool,
+    pub tracked_only: bool,
+    pub include_ignored: bool,
+    pub no_git: bool,
+    pub from_root: bool,
 }
 
 pub const DEFAULT_OUTPUT_FILE: &str = "packed-repo.xml";
 pub const DEFAULT_MODEL: &str = "gpt5";
+/// The values `--order` and the `order` setting accept.
+pub const ORDER_VALUES: [&str; 2] = ["path", "hotspots"];
 
 impl Default for Params {
     fn default() -> Self {
@@ -220,11 +301,51 @@ impl Default for Params {
             line_numbers: false,
             token: None,
             branch: None,
+            git_ref: None,
+            offline: false,
+            no_cache: false,
+            cache_dir: None,
+            hosts: BTreeMap::new(),
+            tokens: BTreeMap::new(),
+            ssh_key: None,
+            diff_base: None,
+            diff: None,
+            uncommitted: false,
+            staged: false,
+            diff_patch: false,
+            patch_only: false,
+            with_imports: false,
+            history: None,
+            last_commit: false,
+            hotspots: false,
+            hotspots_section: false,
+            order: None,
             extend_exclude: None,
             exclude: None,
+            paths: None,
+            files_from: None,
+            packages: None,
+            package_path_dependencies: false,
+            grep: None,
+            follow: None,
+            grep_context: None,
+            languages: None,
+            include_tests: true,
+            include_docs: true,
+            include_examples: true,
+            max_file_size: None,
+            max_file_lines: None,
+            modified_within: None,
+            truncate_lines: None,
+            truncate_tokens: None,
             utf8: false,
             gzip: false,
             gzip_level: 6,
+            bundlerepoignore: true,
+            tracked_only: false,
+            include_ignored: false,
+            no_git: false,
+            from_root: false,
         }
     }
 }
@@ -261,6 +382,24 @@ impl From<Config> for Params {
         if let Some(val) = update_if_present("branch") {
             params.branch = Some(val);
         }
+        if let Ok(val) = TomlValue::load_from_config(&settings, "offline") {
+            params.offline = val;
+        }
+        if let Ok(val) = TomlValue::load_from_config(&settings, "no_cache") {
+            params.no_cache = val;
+        }
+        if let Some(val) = update_if_present("cache_dir") {
+            params.cache_dir = Some(val);
+        }
+        if let Ok(val) = TomlValue::load_from_config(&settings, "hosts") {
+            params.hosts = val;
+        }
+        if let Ok(val) = TomlValue::load_from_config(&settings, "tokens") {
+            params.tokens = val;
+        }
+        if let Some(val) = update_if_present("ssh_key") {
+            params.ssh_key = Some(val);
+        }
         if let Ok(val) =
             TomlValue::load_from_config(&settings, "extend_exclude")
         {
@@ -269,6 +408,59 @@ impl From<Config> for Params {
         if let Ok(val) = TomlValue::load_from_config(&settings, "exclude") {
             params.exclude = val;
         }
+        if let Ok(val) = TomlValue::load_from_config(&settings, "paths") {
+            params.paths = val;
+        }
+        if let Ok(val) = TomlValue::load_from_config(&settings, "packages") {
+            params.packages = val;
+        }
+        if let Ok(val) =
+            TomlValue::load_from_config(&settings, "package_path_dependencies")
+        {
+            params.package_path_dependencies = val;
+        }
+        if let Ok(val) = TomlValue::load_from_config(&settings, "languages") {
+            params.languages = val;
+        }
+        if let Ok(val) =
+            TomlValue::load_from_config(&settings, "include_tests")
+        {
+            params.include_tests = val;
+        }
+        if let Ok(val) = TomlValue::load_from_config(&settings, "include_docs")
+        {
+            params.include_docs = val;
+        }
+        if let Ok(val) =
+            TomlValue::load_from_config(&settings, "include_examples")
+        {
+            params.include_examples = val;
+        }
+        if let Some(val) = update_if_present("max_file_size")
+            .and_then(|val| parse_file_size(&val).ok())
+        {
+            params.max_file_size = Some(val);
+        }
+        if let Some(val) = update_if_present("max_file_lines")
+            .and_then(|val| parse_line_count(&val).ok())
+        {
+            params.max_file_lines = Some(val);
+        }
+        if let Some(val) = update_if_present("modified_within")
+            .and_then(|val| parse_age(&val).ok())
+        {
+            params.modified_within = Some(val);
+        }
+        if let Some(val) = update_if_present("truncate_lines")
+            .and_then(|val| parse_line_count(&val).ok())
+        {
+            params.truncate_lines = Some(val);
+        }
+        if let Some(val) = update_if_present("truncate_tokens")
+            .and_then(|val| parse_token_count(&val).ok())
+        {
+            params.truncate_tokens = Some(val);
+        }
         if let Ok(val) = TomlValue::load_from_config(&settings, "utf8") {
             params.utf8 = val;
         }
@@ -280,6 +472,37 @@ impl From<Config> for Params {
         {
             params.gzip_level = level as u32;
         }
+        if let Ok(val) =
+            TomlValue::load_from_config(&settings, "bundlerepoignore")
+        {
+            params.bundlerepoignore = val;
+        }
+        if let Ok(val) = TomlValue::load_from_config(&settings, "tracked_only")
+        {
+            params.tracked_only = val;
+        }
+        if let Ok(val) =
+            TomlValue::load_from_config(&settings, "include_ignored")
+        {
+            params.include_ignored = val;
+        }
+        if let Ok(val) = TomlValue::load_from_config(&settings, "from_root") {
+            params.from_root = val;
+        }
+        if let Ok(count @ 0..) =
+            TomlValue::load_from_config(&settings, "history")
+        {
+            params.history = Some(count as usize);
+        }
+        if let Ok(val) = TomlValue::load_from_config(&settings, "last_commit")
+        {
+            params.last_commit = val;
+        }
+        if let Some(val) = update_if_present("order")
+            .filter(|val| ORDER_VALUES.contains(&val.as_str()))
+        {
+            params.order = Some(val);
+        }
         params
     }
 }
@@ -295,6 +518,46 @@ impl Params {
                 None => (config.gzip, config.gzip_level),
             }
         };
+        // A remote repository is read from its cached clone, which is bare,
+        // so its branch or default branch is bundled like a ref.
+        let branch = args.branch.clone().or(config.branch);
+        let remote = args.repo.as_deref().is_some_and(|repo| {
+            local_repository_path(repo).is_none()
+                && archive_input(repo).is_none()
+        });
+        let host = args
+            .repo
+            .as_deref()
+            .filter(|_| remote)
+            .and_then(|repo| remote_host(repo, &config.hosts));
+        let token = resolve_token(
+            args.token.as_deref(),
+            host.as_deref(),
+            &config.tokens,
+            config.token.as_deref(),
+            |name| env::var(name).ok(),
+        );
+        // A diff bundles its new side, read like any other ref.
+        let git_ref = args.git_ref.clone().or_else(|| {
+            args.diff
+                .as_deref()
+                .and_then(|value| parse_diff_range(value).ok())
+                .map(|range| range.head)
+                .or_else(|| {
+                    args.diff_base.as_ref().map(|_| "HEAD".to_string())
+                })
+                .or_else(|| {
+                    remote.then(|| {
+                        branch.clone().unwrap_or_else(|| "HEAD".to_string())
+                    })
+                })
+        });
+        // An archive or a ref snapshot is unpacked without any git metadata.
+        let no_git = args.no_git
+            || git_ref.is_some()
+            || args.repo.as_deref().and_then(archive_input).is_some();
+        // A plain directory or an archive has no history to read.
+        let has_history = !no_git || git_ref.is_some();
 
         Params {
             output_file: args
@@ -310,8 +573,32 @@ impl Params {
                 .or(Params::default().model),
             clipboard: args.clipboard || config.clipboard,
             line_numbers: args.lnumbers || config.line_numbers,
-            token: args.token.clone().or(config.token),
-            branch: args.branch.clone().or(config.branch),
+            token,
+            branch,
+            git_ref,
+            offline: args.offline || config.offline,
+            no_cache: args.no_cache || config.no_cache,
+            cache_dir: config.cache_dir,
+            hosts: config.hosts,
+            tokens: config.tokens,
+            ssh_key: args.ssh_key.clone().or(config.ssh_key),
+            diff_base: args.diff_base.clone(),
+            diff: args.diff.clone(),
+            uncommitted: args.uncommitted,
+            staged: args.staged,
+            diff_patch: args.diff_patch || args.patch_only,
+            patch_only: args.patch_only,
+            with_imports: args.with_imports,
+            history: args.history.or(config.history).filter(|_| has_history),
+            last_commit: has_history
+                && (args.last_commit || config.last_commit),
+            hotspots: has_history && args.hotspots,
+            hotspots_section: has_history && args.hotspots_section,
+            order: args
+                .order
+                .clone()
+                .or(config.order)
+                .filter(|order| has_history || order != "hotspots"),
             extend_exclude: if args.exclude.is_some()
                 || config.exclude.is_some()
             {
@@ -334,6 +621,24 @@ impl Params {
                 (None, Some(config_excludes)) => Some(config_excludes),
                 (None, None) => None,
             },
+            paths: args.paths.clone().or(config.paths),
+            // Read from `--files-from` once the arguments are resolved.
+            files_from: None,
+            packages: args.packages.clone().or(config.packages),
+            package_path_dependencies: args.with_path_deps
+                || config.package_path_dependencies,
+            grep: args.grep.clone(),
+            follow: args.follow.clone(),
+            grep_context: args.grep_context,
+            languages: args.languages.clone().or(config.languages),
+            include_tests: !args.no_tests && config.include_tests,
+            include_docs: !args.no_docs && config.include_docs,
+            include_examples: !args.no_examples && config.include_examples,
+            max_file_size: args.max_file_size.or(config.max_file_size),
+            max_file_lines: args.max_file_lines.or(config.max_file_lines),
+            modified_within: args.modified_within.or(config.modified_within),
+            truncate_lines: args.truncate_lines.or(config.truncate_lines),
+            truncate_tokens: args.truncate_tokens.or(config.truncate_tokens),
             utf8: if args.no_utf8 {
                 false
             } else if args.utf8 {
@@ -343,6 +648,21 @@ impl Params {
             },
             gzip,
             gzip_level,
+            bundlerepoignore: !args.no_bundlerepoignore
+                && config.bundlerepoignore,
+            // Without git there are no file states to list from, so
+            // `--no-git` overrides the git-state settings in configuration.
+            tracked_only: if args.include_untracked || no_git {
+                false
+            } else {
+                args.tracked_only || config.tracked_only
+            },
+            include_ignored: !no_git
+                && (args.include_ignored || config.include_ignored),
+            no_git,
+            // A ref is read from the repository, so only a plain directory
+            // has no root to start from.
+            from_root: !args.no_git && (args.from_root || config.from_root),
         }
     }
 }
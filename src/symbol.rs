@@ -0,0 +1,344 @@
+use crate::language::language_for;
+use crate::path_spec::LineRange;
+use regex::Regex;
+use std::io;
+use std::sync::LazyLock;
+
+static RUST_ITEM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"^\s*(?:pub(?:\s*\([^)]*\))?\s+)?(?:(?:default|const|async|unsafe|extern\s+"[^"]*")\s+)*(?:(?:fn|struct|enum|union|trait|type|const|static(?:\s+mut)?|mod)\s+|macro_rules!\s*)(?:r#)?([A-Za-z_][A-Za-z0-9_]*)"#,
+    )
+    .unwrap()
+});
+static RUST_IMPL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*(?:unsafe\s+)?impl\b(?:\s*<.*?>)?\s+(?:.*?\s+for\s+)?&?(?:[A-Za-z_][A-Za-z0-9_]*::)*([A-Za-z_][A-Za-z0-9_]*)",
+    )
+    .unwrap()
+});
+static PYTHON_ITEM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:async\s+)?(?:def|class)\s+([A-Za-z_][A-Za-z0-9_]*)")
+        .unwrap()
+});
+static JS_ITEM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:abstract\s+)?(?:async\s+)?(?:function\s*\*?\s*|(?:class|interface|enum|type|namespace|module|const|let|var)\s+)([A-Za-z_$][A-Za-z0-9_$]*)",
+    )
+    .unwrap()
+});
+static JS_MEMBER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*(?:(?:public|private|protected|static|async|readonly|override|abstract|declare|get|set)\s+)*\*?#?([A-Za-z_$][A-Za-z0-9_$]*)\s*\??\s*[(<=:]",
+    )
+    .unwrap()
+});
+
+/// Characters that continue a JavaScript statement on the next line.
+const JS_CONTINUATIONS: [char; 12] =
+    ['=', ',', '(', '[', '{', '+', '-', '*', '/', '.', '?', ':'];
+
+/// The languages whose definitions can be selected, grouped by how they
+/// delimit a definition's body.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Syntax {
+    Rust,
+    Python,
+    JavaScript,
+}
+
+impl Syntax {
+    fn for_path(path: &str) -> Option<Self> {
+        match language_for(path) {
+            Some("rust") => Some(Self::Rust),
+            Some("python") => Some(Self::Python),
+            Some("javascript" | "typescript") => Some(Self::JavaScript),
+            _ => None,
+        }
+    }
+
+    /// Returns the name `line` defines. Members are the items of a type,
+    /// such as methods, and are looked for inside another definition.
+    fn defined_name(self, line: &str, member: bool) -> Option<&str> {
+        let patterns: &[&Regex] = match (self, member) {
+            (Self::Rust, _) => &[&RUST_ITEM, &RUST_IMPL],
+            (Self::Python, _) => &[&PYTHON_ITEM],
+            (Self::JavaScript, false) => &[&JS_ITEM],
+            (Self::JavaScript, true) => &[&JS_ITEM, &JS_MEMBER],
+        };
+        patterns.iter().find_map(|pattern| {
+            pattern
+                .captures(line)
+                .and_then(|captures| captures.get(1))
+                .map(|name| name.as_str())
+        })
+    }
+
+    /// The prefixes of the lines above a definition that belong to it: doc
+    /// comments, attributes and decorators.
+    const fn preamble(self) -> &'static [&'static str] {
+        match self {
+            Self::Rust => &["///", "#["],
+            Self::Python => &["@"],
+            Self::JavaScript => &["@", "/**", "*"],
+        }
+    }
+
+    /// Returns the index of the last line of the definition starting at
+    /// `start`.
+    fn item_end(self, lines: &[&str], start: usize) -> usize {
+        match self {
+            Self::Python => indented_item_end(lines, start),
+            Self::Rust | Self::JavaScript => {
+                self.braced_item_end(lines, start)
+            }
+        }
+    }
+
+    /// Ends a definition where its brackets close after a `{ ... }` body, or
+    /// at a `;` outside brackets. A JavaScript statement may also end with
+    /// its line.
+    fn braced_item_end(self, lines: &[&str], start: usize) -> usize {
+        let mut scanner = Scanner::new(self);
+        let mut depth = 0;
+        let mut braced = false;
+        for (index, line) in lines.iter().enumerate().skip(start) {
+            let code = scanner.code(line);
+            for byte in code.bytes() {
+                match byte {
+                    b'{' => {
+                        depth += 1;
+                        braced = true;
+                    }
+                    b'(' | b'[' => depth += 1,
+                    b'}' | b')' | b']' => depth -= 1,
+                    _ => {}
+                }
+            }
+            if depth > 0 {
+                continue;
+            }
+            let code = code.trim_end();
+            if braced || code.ends_with(';') {
+                return index;
+            }
+            let next = lines[index + 1..]
+                .iter()
+                .find(|line| !line.trim().is_empty());
+            if self == Self::JavaScript
+                && !code.ends_with(JS_CONTINUATIONS)
+                && !code.ends_with("=>")
+                && next.is_none_or(|next| {
+                    indentation(next) <= indentation(lines[start])
+                        && !next.trim_start().starts_with(['{', '.'])
+                })
+            {
+                return index;
+            }
+        }
+        lines.len() - 1
+    }
+}
+
+/// Ends a Python definition before the first line, after its header, that
+/// is indented no deeper than the definition. Lines inside strings are part
+/// of the body whatever their indentation.
+fn indented_item_end(lines: &[&str], start: usize) -> usize {
+    let mut scanner = Scanner::new(Syntax::Python);
+    let mut depth = 0;
+    let mut end = start;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        let in_string = scanner.open.is_some();
+        let code = scanner.code(line);
+        if index > start && depth <= 0 && !in_string {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if indentation(line) <= indentation(lines[start]) {
+                break;
+            }
+        }
+        for byte in code.bytes() {
+            match byte {
+                b'(' | b'[' | b'{' => depth += 1,
+                b')' | b']' | b'}' => depth -= 1,
+                _ => {}
+            }
+        }
+        end = index;
+    }
+    end
+}
+
+/// Strips strings and comments from lines of code, following strings and
+/// block comments across lines.
+struct Scanner {
+    syntax: Syntax,
+    /// What closes the string or comment open at the end of the last line.
+    open: Option<&'static str>,
+}
+
+impl Scanner {
+    const fn new(syntax: Syntax) -> Self {
+        Self { syntax, open: None }
+    }
+
+    /// Returns the code of `line` outside strings and comments. Non-ASCII
+    /// characters become spaces.
+    fn code(&mut self, line: &str) -> String {
+        let (line_comment, openers): (&str, &[(&str, &'static str)]) =
+            match self.syntax {
+                Syntax::Rust => {
+                    ("//", &[("/*", "*/"), ("r#\"", "\"#"), ("\"", "\"")])
+                }
+                Syntax::Python => (
+                    "#",
+                    &[
+                        ("\"\"\"", "\"\"\""),
+                        ("'''", "'''"),
+                        ("\"", "\""),
+                        ("'", "'"),
+                    ],
+                ),
+                Syntax::JavaScript => (
+                    "//",
+                    &[("/*", "*/"), ("\"", "\""), ("'", "'"), ("`", "`")],
+                ),
+            };
+        let bytes = line.as_bytes();
+        let mut code = String::new();
+        let mut index = 0;
+        while index < bytes.len() {
+            let rest = &bytes[index..];
+            if let Some(close) = self.open {
+                if rest.starts_with(close.as_bytes()) {
+                    self.open = None;
+                    index += close.len();
+                } else if close.len() == 1 && rest[0] == b'\\' {
+                    index += 2;
+                } else {
+                    index += 1;
+                }
+                continue;
+            }
+            if rest.starts_with(line_comment.as_bytes()) {
+                break;
+            }
+            if let Some((open, close)) = openers
+                .iter()
+                .find(|(open, _)| rest.starts_with(open.as_bytes()))
+            {
+                self.open = Some(close);
+                index += open.len();
+                continue;
+            }
+            // A quote in Rust opens a character literal or names a lifetime.
+            if self.syntax == Syntax::Rust && rest[0] == b'\'' {
+                index += match rest {
+                    [_, b'\\', ..] => rest[2..]
+                        .iter()
+                        .position(|byte| *byte == b'\'')
+                        .map_or(rest.len(), |position| position + 3),
+                    [_, _, b'\'', ..] => 3,
+                    _ => 1,
+                };
+                continue;
+            }
+            code.push(if rest[0].is_ascii() {
+                rest[0] as char
+            } else {
+                ' '
+            });
+            index += 1;
+        }
+        // Only Rust strings, block comments and multi-line strings continue
+        // on the next line.
+        if self.syntax != Syntax::Rust && matches!(self.open, Some("\"" | "'"))
+        {
+            self.open = None;
+        }
+        code
+    }
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Returns the lines of `symbol` in `content`, the text of the file at
+/// `path`. Nested definitions are named with `::`, as in `Type::method`, and
+/// are looked for among the members of the outer definition. The doc
+/// comments, attributes and decorators above the definition are included.
+pub(crate) fn symbol_lines(
+    path: &str,
+    content: &str,
+    symbol: &str,
+) -> io::Result<LineRange> {
+    let syntax = Syntax::for_path(path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "'{path}::{symbol}' selects a symbol, which is only supported in Rust, Python, JavaScript and TypeScript files"
+            ),
+        )
+    })?;
+    let lines = content.lines().collect::<Vec<_>>();
+
+    // Each candidate is the first and last line of a definition.
+    let mut candidates = vec![(None, lines.len().saturating_sub(1))];
+    for name in symbol.split("::") {
+        let mut found = Vec::new();
+        for (header, end) in candidates {
+            let body = header.map_or(0, |header| header + 1);
+            let Some(indent) = lines
+                .get(body..=end)
+                .into_iter()
+                .flatten()
+                .find(|line| !line.trim().is_empty())
+                .map(|line| {
+                    if header.is_some() {
+                        indentation(line)
+                    } else {
+                        0
+                    }
+                })
+            else {
+                continue;
+            };
+            for index in body..=end {
+                let line = lines[index];
+                if indentation(line) == indent
+                    && syntax.defined_name(line, header.is_some())
+                        == Some(name)
+                {
+                    found.push((Some(index), syntax.item_end(&lines, index)));
+                }
+            }
+        }
+        candidates = found;
+    }
+
+    let Some(&(Some(start), end)) = candidates.first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("'{symbol}' is not defined in '{path}'"),
+        ));
+    };
+    let start = lines[..start]
+        .iter()
+        .rposition(|line| {
+            let line = line.trim_start();
+            !syntax
+                .preamble()
+                .iter()
+                .any(|prefix| line.starts_with(prefix))
+        })
+        .map_or(0, |index| index + 1);
+    Ok(LineRange {
+        start: start + 1,
+        end: end + 1,
+    })
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/symbol.rs"]
+mod tests;
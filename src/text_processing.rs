@@ -1,9 +1,9 @@
+use crate::source::FileSource;
 use crate::timings::ProcessingTimings;
 use chardetng::{EncodingDetector, Iso2022JpDetection, Utf8Detection};
 use encoding_rs::{Encoding, ISO_2022_JP, UTF_8, UTF_16BE, UTF_16LE};
-use std::fs::File;
+use std::fmt;
 use std::io::{self, Read};
-use std::path::Path;
 use std::time::Instant;
 
 const BINARY_PROBE_SIZE: usize = 8 * 1024;
@@ -42,13 +42,34 @@ pub(crate) enum BinaryReason {
     ImplausibleDecodedData,
 }
 
+impl fmt::Display for BinaryReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RecognizedMagic(mime_type) => {
+                write!(f, "recognized {mime_type} file signature")
+            }
+            Self::NullByte => f.write_str("contains a NUL byte"),
+            Self::ControlDensity => {
+                f.write_str("high density of control characters")
+            }
+            Self::Utf16ConversionDisabled(encoding) => {
+                write!(f, "{encoding} text without --utf8 conversion")
+            }
+            Self::ImplausibleDecodedData => {
+                f.write_str("decoded text is implausible")
+            }
+        }
+    }
+}
+
 pub(crate) fn read_classify_and_decode(
-    path: &Path,
+    source: &(impl FileSource + ?Sized),
+    path: &str,
     utf8: bool,
     timings: &mut ProcessingTimings,
 ) -> io::Result<ProcessedFile> {
     let open_start = Instant::now();
-    let mut file = File::open(path)?;
+    let mut file = source.open_file(path)?;
     timings.file_classification_and_read += open_start.elapsed();
     read_classify_and_decode_from_reader(&mut file, utf8, timings)
 }
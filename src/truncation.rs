@@ -0,0 +1,114 @@
+use crate::tokenizer::TokenizerType;
+
+/// The number of lines kept from each end of a truncated file.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct Truncation {
+    pub(crate) head: usize,
+    pub(crate) tail: usize,
+    pub(crate) original_lines: usize,
+}
+
+impl Truncation {
+    pub(crate) const fn elided(&self) -> usize {
+        self.original_lines - self.head - self.tail
+    }
+}
+
+/// Parses a positive token count.
+pub(crate) fn parse_token_count(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err("token count must be a positive integer".to_string()),
+    }
+}
+
+pub(crate) fn elision_marker(count: usize) -> String {
+    format!("... [{count} lines elided] ...")
+}
+
+/// Decides which lines to keep so a file fits within `max_lines` and
+/// `max_tokens`, taking lines alternately from the start and the end.
+/// Returns `None` when the whole file fits.
+pub(crate) fn plan_truncation(
+    text: &str,
+    lines: &[&str],
+    max_lines: Option<usize>,
+    max_tokens: Option<usize>,
+    tokenizer: &TokenizerType,
+) -> Result<Option<Truncation>, String> {
+    let original_lines = lines.len();
+    let line_budget = max_lines.unwrap_or(original_lines).min(original_lines);
+    let token_budget = match max_tokens {
+        Some(max) if tokenizer.count_tokens(text)? > max => Some(max),
+        _ => None,
+    };
+    if line_budget == original_lines && token_budget.is_none() {
+        return Ok(None);
+    }
+
+    let (mut head, mut tail, mut tokens) = (0, 0, 0);
+    while head + tail < line_budget {
+        let index = if head <= tail {
+            head
+        } else {
+            original_lines - tail - 1
+        };
+        if let Some(budget) = token_budget {
+            // Each kept line also keeps its line break.
+            let cost = tokenizer.count_tokens(lines[index])? + 1;
+            if tokens + cost > budget {
+                break;
+            }
+            tokens += cost;
+        }
+        if head <= tail {
+            head += 1;
+        } else {
+            tail += 1;
+        }
+    }
+
+    Ok((head + tail < original_lines).then_some(Truncation {
+        head,
+        tail,
+        original_lines,
+    }))
+}
+
+/// Renders the kept lines around an elision marker. Line numbers, when
+/// requested, are those of the original file.
+pub(crate) fn render_truncated(
+    lines: &[&str],
+    truncation: &Truncation,
+    line_numbers: bool,
+) -> String {
+    let marker = elision_marker(truncation.elided());
+    let tail_start = truncation.original_lines - truncation.tail;
+    let kept = lines[..truncation.head]
+        .iter()
+        .enumerate()
+        .map(|(index, line)| (Some(index + 1), *line))
+        .chain([(None, marker.as_str())])
+        .chain(
+            lines[tail_start..]
+                .iter()
+                .enumerate()
+                .map(|(index, line)| (Some(tail_start + index + 1), *line)),
+        );
+
+    let width = truncation.original_lines.to_string().len();
+    let mut rendered = kept
+        .map(|(number, line)| match (line_numbers, number) {
+            (false, _) => line.to_string(),
+            (true, Some(number)) => format!("{number:>width$}  {line}"),
+            (true, None) => format!("{:>width$}  {line}", ""),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    rendered.push('\n');
+    rendered
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/truncation.rs"]
+mod tests;
@@ -0,0 +1,486 @@
+use crate::filelist::{
+    is_within_scope, join_repository_path, walk_ignore_rules,
+};
+use crate::ignore_rules::IgnoreRules;
+use crate::source::{EntryKind, FileSource};
+use crate::structs::Params;
+use config::{Config, File, FileFormat, Value};
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::io;
+
+pub(crate) const CARGO_MANIFEST: &str = "Cargo.toml";
+pub(crate) const NPM_MANIFEST: &str = "package.json";
+pub(crate) const PNPM_WORKSPACE: &str = "pnpm-workspace.yaml";
+
+const CARGO_DEPENDENCY_TABLES: [&str; 3] =
+    ["dependencies", "dev-dependencies", "build-dependencies"];
+const NPM_DEPENDENCY_FIELDS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// The package manager that defines a workspace package.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Manager {
+    Cargo,
+    Npm,
+}
+
+impl Manager {
+    /// The manifests at the repository root that describe the workspace.
+    const fn root_manifests(self) -> &'static [&'static str] {
+        match self {
+            Self::Cargo => &[CARGO_MANIFEST],
+            Self::Npm => &[NPM_MANIFEST, PNPM_WORKSPACE],
+        }
+    }
+}
+
+/// A selectable package in a Cargo or npm/pnpm workspace. The root package,
+/// if any, has an empty directory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct WorkspacePackage {
+    pub(crate) name: String,
+    pub(crate) directory: String,
+    pub(crate) manager: Manager,
+}
+
+fn read_manifest(
+    source: &(impl FileSource + ?Sized),
+    directory: &str,
+    manifest: &str,
+    format: FileFormat,
+) -> Option<Config> {
+    let content = source
+        .read_to_string(&join_repository_path(directory, manifest)?)
+        .ok()?;
+    Config::builder()
+        .add_source(File::from_str(&content, format))
+        .build()
+        .ok()
+}
+
+fn manifest_format(manager: Manager) -> (&'static str, FileFormat) {
+    match manager {
+        Manager::Cargo => (CARGO_MANIFEST, FileFormat::Toml),
+        Manager::Npm => (NPM_MANIFEST, FileFormat::Json),
+    }
+}
+
+fn strings(values: Vec<Value>) -> Vec<String> {
+    values
+        .into_iter()
+        .filter_map(|value| value.into_string().ok())
+        .collect()
+}
+
+fn glob_component(pattern: &str) -> Option<Regex> {
+    let mut expression = String::from("^");
+    for character in pattern.chars() {
+        match character {
+            '*' => expression.push_str(".*"),
+            '?' => expression.push('.'),
+            other => expression.push_str(&regex::escape(&other.to_string())),
+        }
+    }
+    expression.push('$');
+    Regex::new(&expression).ok()
+}
+
+/// Expands a workspace member pattern such as `crates/*` into the matching
+/// directories. A `**` component matches directories at any depth.
+fn expand_members(
+    source: &(impl FileSource + ?Sized),
+    pattern: &str,
+) -> Vec<String> {
+    let mut directories = vec![String::new()];
+    for component in pattern.split('/').filter(|part| !part.is_empty()) {
+        if component == "." {
+            continue;
+        }
+        let mut next = Vec::new();
+        for directory in &directories {
+            if component == "**" {
+                next.extend(all_subdirectories(source, directory));
+            } else if component.contains(['*', '?']) {
+                let Some(matcher) = glob_component(component) else {
+                    continue;
+                };
+                next.extend(
+                    subdirectories(source, directory)
+                        .into_iter()
+                        .filter(|name| matcher.is_match(name))
+                        .filter_map(|name| {
+                            join_repository_path(directory, &name)
+                        }),
+                );
+            } else if let Some(joined) =
+                join_repository_path(directory, component)
+            {
+                next.push(joined);
+            }
+        }
+        directories = next;
+    }
+    directories
+        .into_iter()
+        .filter(|directory| source.has_dir(directory))
+        .collect()
+}
+
+fn subdirectories(
+    source: &(impl FileSource + ?Sized),
+    directory: &str,
+) -> Vec<String> {
+    let Ok(entries) = source.entries(directory) else {
+        return Vec::new();
+    };
+    let mut names = entries
+        .into_iter()
+        .filter(|(_, kind)| *kind == EntryKind::Directory)
+        .map(|(name, _)| name)
+        .filter(|name| !name.starts_with('.') && name != "node_modules")
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+fn all_subdirectories(
+    source: &(impl FileSource + ?Sized),
+    directory: &str,
+) -> Vec<String> {
+    let mut found = vec![directory.to_string()];
+    for name in subdirectories(source, directory) {
+        if let Some(child) = join_repository_path(directory, &name) {
+            found.extend(all_subdirectories(source, &child));
+        }
+    }
+    found
+}
+
+/// Expands member patterns, where patterns starting with `!` and the
+/// `excluded` patterns remove directories again.
+fn member_directories(
+    source: &(impl FileSource + ?Sized),
+    patterns: &[String],
+    excluded: &[String],
+) -> BTreeSet<String> {
+    let mut members = BTreeSet::new();
+    for pattern in patterns {
+        if pattern.starts_with('!') {
+            continue;
+        }
+        members.extend(expand_members(source, pattern));
+    }
+    let negated = patterns
+        .iter()
+        .filter_map(|pattern| pattern.strip_prefix('!'))
+        .chain(excluded.iter().map(String::as_str));
+    for pattern in negated {
+        for directory in expand_members(source, pattern) {
+            members.remove(&directory);
+        }
+    }
+    members
+}
+
+fn package_name(
+    source: &(impl FileSource + ?Sized),
+    directory: &str,
+    manager: Manager,
+) -> Option<String> {
+    let (manifest, format) = manifest_format(manager);
+    let config = read_manifest(source, directory, manifest, format)?;
+    match manager {
+        Manager::Cargo => config.get_string("package.name").ok(),
+        Manager::Npm => config.get_string("name").ok(),
+    }
+}
+
+/// Lists the packages of the Cargo and npm/pnpm workspaces at the repository
+/// root, including a root package when the root manifest declares one.
+pub(crate) fn workspace_packages(
+    source: &(impl FileSource + ?Sized),
+) -> Vec<WorkspacePackage> {
+    let mut packages = Vec::new();
+
+    if let Some(cargo) =
+        read_manifest(source, "", CARGO_MANIFEST, FileFormat::Toml)
+    {
+        let members = cargo.get_array("workspace.members").map(strings);
+        let excluded = cargo
+            .get_array("workspace.exclude")
+            .map(strings)
+            .unwrap_or_default();
+        let directories = member_directories(
+            source,
+            &members.unwrap_or_default(),
+            &excluded,
+        );
+        packages.extend(workspace_units(source, directories, Manager::Cargo));
+    }
+
+    let npm = read_manifest(source, "", NPM_MANIFEST, FileFormat::Json);
+    let pnpm = read_manifest(source, "", PNPM_WORKSPACE, FileFormat::Yaml);
+    if npm.is_some() || pnpm.is_some() {
+        let mut patterns = Vec::new();
+        if let Some(npm) = &npm {
+            patterns.extend(
+                npm.get_array("workspaces")
+                    .or_else(|_| npm.get_array("workspaces.packages"))
+                    .map(strings)
+                    .unwrap_or_default(),
+            );
+        }
+        if let Some(pnpm) = &pnpm {
+            patterns.extend(
+                pnpm.get_array("packages").map(strings).unwrap_or_default(),
+            );
+        }
+        let directories = member_directories(source, &patterns, &[]);
+        packages.extend(workspace_units(source, directories, Manager::Npm));
+    }
+    packages
+}
+
+fn workspace_units(
+    source: &(impl FileSource + ?Sized),
+    members: BTreeSet<String>,
+    manager: Manager,
+) -> Vec<WorkspacePackage> {
+    std::iter::once(String::new())
+        .chain(members)
+        .filter_map(|directory| {
+            let name = package_name(source, &directory, manager)?;
+            Some(WorkspacePackage {
+                name,
+                directory,
+                manager,
+            })
+        })
+        .collect()
+}
+
+/// Returns the directories of the local path dependencies of `package`.
+fn path_dependencies(
+    source: &(impl FileSource + ?Sized),
+    package: &WorkspacePackage,
+    packages: &[WorkspacePackage],
+) -> Vec<String> {
+    let (manifest, format) = manifest_format(package.manager);
+    let Some(config) =
+        read_manifest(source, &package.directory, manifest, format)
+    else {
+        return Vec::new();
+    };
+
+    let mut directories = Vec::new();
+    match package.manager {
+        Manager::Cargo => {
+            let workspace_dependencies =
+                read_manifest(source, "", CARGO_MANIFEST, FileFormat::Toml)
+                    .and_then(|root| {
+                        root.get_table("workspace.dependencies").ok()
+                    })
+                    .unwrap_or_default();
+            for table in CARGO_DEPENDENCY_TABLES {
+                let Ok(dependencies) = config.get_table(table) else {
+                    continue;
+                };
+                for (name, dependency) in dependencies {
+                    let Ok(dependency) = dependency.into_table() else {
+                        continue;
+                    };
+                    let inherited = dependency
+                        .get("workspace")
+                        .and_then(|value| value.clone().into_bool().ok())
+                        .unwrap_or(false);
+                    let (base, dependency) = if inherited {
+                        let Some(root) = workspace_dependencies
+                            .get(&name)
+                            .and_then(|value| value.clone().into_table().ok())
+                        else {
+                            continue;
+                        };
+                        ("", root)
+                    } else {
+                        (package.directory.as_str(), dependency)
+                    };
+                    if let Some(path) = dependency
+                        .get("path")
+                        .and_then(|value| value.clone().into_string().ok())
+                        .and_then(|path| join_repository_path(base, &path))
+                    {
+                        directories.push(path);
+                    }
+                }
+            }
+        }
+        Manager::Npm => {
+            for field in NPM_DEPENDENCY_FIELDS {
+                let Ok(dependencies) = config.get_table(field) else {
+                    continue;
+                };
+                for (name, version) in dependencies {
+                    let Ok(version) = version.into_string() else {
+                        continue;
+                    };
+                    if version.starts_with("workspace:") {
+                        directories.extend(
+                            packages
+                                .iter()
+                                .filter(|other| {
+                                    other.manager == Manager::Npm
+                                        && other.name == name
+                                })
+                                .map(|other| other.directory.clone()),
+                        );
+                    } else if let Some(path) = version
+                        .strip_prefix("file:")
+                        .or_else(|| version.strip_prefix("link:"))
+                        .and_then(|path| {
+                            join_repository_path(&package.directory, path)
+                        })
+                    {
+                        directories.push(path);
+                    }
+                }
+            }
+        }
+    }
+    directories
+}
+
+/// Returns the entries below `directory` that lie outside every directory in
+/// `others`, descending into directories that contain one of them. Ignored
+/// entries are left out, since scopes are walked without checking the scope
+/// itself against ignore files.
+fn entries_outside(
+    source: &(impl FileSource + ?Sized),
+    directory: &str,
+    others: &[&str],
+    rules: &mut IgnoreRules,
+) -> io::Result<Vec<String>> {
+    let mut entries = source.entries(directory)?;
+    entries.sort();
+    let mut scopes = Vec::new();
+    for (name, kind) in entries {
+        let Some(path) = join_repository_path(directory, &name) else {
+            continue;
+        };
+        let is_dir = kind == EntryKind::Directory;
+        if name == ".git"
+            || others.contains(&path.as_str())
+            || rules.ignored(&path, is_dir).is_some()
+        {
+            continue;
+        }
+        if others.iter().any(|other| is_within_scope(other, &path)) {
+            scopes.extend(entries_outside(source, &path, others, rules)?);
+        } else {
+            scopes.push(path);
+        }
+    }
+    Ok(scopes)
+}
+
+/// Resolves `--package` names to the repository paths they cover: each
+/// package directory, optionally its local path dependencies, and the root
+/// manifests of its workspace. A root package covers the repository outside
+/// the other workspace members.
+pub(crate) fn package_scopes(
+    source: &(impl FileSource + ?Sized),
+    flags: &Params,
+) -> io::Result<Vec<String>> {
+    let Some(names) = &flags.packages else {
+        return Ok(Vec::new());
+    };
+    let packages = workspace_packages(source);
+    if packages.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no Cargo or npm workspace packages found in the repository",
+        ));
+    }
+
+    let mut selected = Vec::new();
+    for name in names {
+        let matching = packages
+            .iter()
+            .filter(|package| &package.name == name)
+            .cloned()
+            .collect::<Vec<_>>();
+        if matching.is_empty() {
+            let mut available = packages
+                .iter()
+                .map(|package| package.name.as_str())
+                .collect::<Vec<_>>();
+            available.sort_unstable();
+            available.dedup();
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Unknown package '{name}'. Workspace packages: {}",
+                    available.join(", ")
+                ),
+            ));
+        }
+        selected.extend(matching);
+    }
+
+    if flags.package_path_dependencies {
+        let mut index = 0;
+        while index < selected.len() {
+            for directory in
+                path_dependencies(source, &selected[index], &packages)
+            {
+                if selected
+                    .iter()
+                    .any(|package| package.directory == directory)
+                    || !source.has_dir(&directory)
+                {
+                    continue;
+                }
+                let manager = selected[index].manager;
+                selected.push(WorkspacePackage {
+                    name: package_name(source, &directory, manager)
+                        .unwrap_or_default(),
+                    directory,
+                    manager,
+                });
+            }
+            index += 1;
+        }
+    }
+
+    let mut scopes = Vec::new();
+    for package in &selected {
+        if package.directory.is_empty() {
+            let others = packages
+                .iter()
+                .map(|other| other.directory.as_str())
+                .filter(|directory| !directory.is_empty())
+                .collect::<Vec<_>>();
+            let mut rules = walk_ignore_rules(source, flags);
+            scopes.extend(entries_outside(source, "", &others, &mut rules)?);
+        } else {
+            scopes.push(package.directory.clone());
+        }
+        scopes.extend(
+            package
+                .manager
+                .root_manifests()
+                .iter()
+                .filter(|manifest| source.has_file(manifest))
+                .map(|manifest| (*manifest).to_string()),
+        );
+    }
+    Ok(scopes)
+}
+
+#[cfg(test)]
+#[path = "../tests/crate/workspace.rs"]
+mod tests;
@@ -1,23 +1,53 @@
+use crate::changes::{ChangeKind, FileChange};
 use crate::filelist::{FileTree, FolderNode};
+use crate::grep::{grep_pattern, match_ranges};
+use crate::history::{CommitSummary, Hotspot, RECENT_CHURN_DAYS};
+use crate::language::language_for;
+use crate::path_spec::{
+    LineRange, line_ranges, parse_path_spec, render_line_range,
+    selected_path_specs,
+};
 use crate::progress::ProgressReporter;
+use crate::source::FileSource;
 use crate::structs::{DEFAULT_OUTPUT_FILE, Params};
 use crate::text_processing::{
     DecodedText, ProcessedFile, read_classify_and_decode,
 };
 use crate::timings::ProcessingTimings;
 use crate::tokenizer::TokenizerType;
+use crate::truncation::{plan_truncation, render_truncated};
 use arboard::Clipboard;
 use dirs_next::home_dir;
 use flate2::Compression;
 use flate2::write::GzEncoder;
 use std::borrow::Cow;
-use std::fs::{File, metadata};
+use std::fs::File;
 use std::io::{self, Cursor, IsTerminal, Write};
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 use xml::common::{XmlVersion, is_xml10_char};
+use xml::writer::events::StartElementBuilder;
 use xml::writer::{EmitterConfig, EventWriter, XmlEvent};
 
+/// A `<file>` entry's path and, in a `--diff` bundle, how the file changed,
+/// with the last commit to touch it under `--last-commit`.
+#[derive(Clone, Copy)]
+struct FileEntry<'a> {
+    path: &'a str,
+    change: Option<&'a FileChange>,
+    last_commit: Option<&'a str>,
+}
+
+impl<'a> From<&'a str> for FileEntry<'a> {
+    fn from(path: &'a str) -> Self {
+        Self {
+            path,
+            change: None,
+            last_commit: None,
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 struct InvalidXml10Char {
     byte_index: usize,
@@ -29,7 +59,7 @@ struct InvalidXml10Char {
 pub fn output_repo_as_xml(
     flags: &Params,
     file_tree: FileTree,
-    base_path: &Path,
+    source: &(impl FileSource + ?Sized),
     tokenizer: &TokenizerType,
 ) -> Result<(usize, u64, usize), std::io::Error> {
     let mut reporter =
@@ -37,7 +67,7 @@ pub fn output_repo_as_xml(
     output_repo_as_xml_with_timings(
         flags,
         file_tree,
-        base_path,
+        source,
         tokenizer,
         "GPT-4",
         &mut reporter,
@@ -48,7 +78,7 @@ pub fn output_repo_as_xml(
 pub fn output_repo_as_xml_with_timings<N: Write, D: Write>(
     flags: &Params,
     file_tree: FileTree,
-    base_path: &Path,
+    source: &(impl FileSource + ?Sized),
     tokenizer: &TokenizerType,
     model_name: &str,
     reporter: &mut ProgressReporter<N, D>,
@@ -60,7 +90,7 @@ pub fn output_repo_as_xml_with_timings<N: Write, D: Write>(
     let xml_start = Instant::now();
 
     let xml_bytes = serialize_repository_xml(
-        flags, &file_tree, base_path, reporter, timings,
+        flags, &file_tree, source, tokenizer, reporter, timings,
     )?;
     let classification_elapsed = timings
         .file_classification_and_read
@@ -90,7 +120,8 @@ pub fn output_repo_as_xml_with_timings<N: Write, D: Write>(
 fn serialize_repository_xml<N: Write, D: Write>(
     flags: &Params,
     file_tree: &FileTree,
-    base_path: &Path,
+    source: &(impl FileSource + ?Sized),
+    tokenizer: &TokenizerType,
     reporter: &mut ProgressReporter<N, D>,
     timings: &mut ProcessingTimings,
 ) -> io::Result<Vec<u8>> {
@@ -110,16 +141,23 @@ fn serialize_repository_xml<N: Write, D: Write>(
     writer
         .write(XmlEvent::start_element("repository"))
         .map_err(map_xml_error)?;
-    write_file_summary(&mut writer, flags)?;
+    write_file_summary(&mut writer, flags, file_tree.bundlerepoignore)?;
     write_repository_structure(&mut writer, &file_tree.folder_node)?;
     write_repository_files_to_xml(
         &mut writer,
-        &file_tree.file_paths,
-        base_path,
+        file_tree,
+        source,
         flags,
+        tokenizer,
         reporter,
         timings,
     )?;
+    if flags.hotspots_section {
+        write_hotspots(&mut writer, &file_tree.history.hotspots)?;
+    }
+    if flags.history.is_some() {
+        write_git_history(&mut writer, &file_tree.history.commits)?;
+    }
     writer
         .write(XmlEvent::end_element())
         .map_err(map_xml_error)?;
@@ -146,7 +184,12 @@ fn validate_file_tree_xml_metadata(file_tree: &FileTree) -> io::Result<()> {
 }
 
 fn validate_folder_xml_metadata(folder: &FolderNode) -> io::Result<()> {
-    for basename in &folder.files {
+    for basename in folder
+        .files
+        .iter()
+        .chain(folder.skipped.iter().map(|(basename, _)| basename))
+        .chain(&folder.deleted)
+    {
         validate_xml_attribute(basename, "repository structure file path")?;
     }
     for (name, child) in &folder.subfolders {
@@ -409,6 +452,32 @@ fn write_folder_to_xml<W: Write>(
             .map_err(map_xml_error)?;
     }
 
+    for file in &folder_node.deleted {
+        writer
+            .write(
+                XmlEvent::start_element("file")
+                    .attr("path", file)
+                    .attr("change", ChangeKind::Deleted.as_str()),
+            )
+            .map_err(map_xml_error)?;
+        writer
+            .write(XmlEvent::end_element())
+            .map_err(map_xml_error)?;
+    }
+
+    for (file, reason) in &folder_node.skipped {
+        writer
+            .write(
+                XmlEvent::start_element("file")
+                    .attr("path", file)
+                    .attr("skipped", reason.as_str()),
+            )
+            .map_err(map_xml_error)?;
+        writer
+            .write(XmlEvent::end_element())
+            .map_err(map_xml_error)?;
+    }
+
     for (subfolder_name, subfolder_node) in &folder_node.subfolders {
         writer
             .write(
@@ -427,9 +496,10 @@ fn write_folder_to_xml<W: Write>(
 /// Writes repository files and their contents using XML writer events.
 fn write_repository_files_to_xml<W: Write, N: Write, D: Write>(
     writer: &mut EventWriter<W>,
-    file_paths: &[String],
-    base_path: &Path,
+    file_tree: &FileTree,
+    source: &(impl FileSource + ?Sized),
     flags: &Params,
+    tokenizer: &TokenizerType,
     reporter: &mut ProgressReporter<N, D>,
     timings: &mut ProcessingTimings,
 ) -> Result<(), std::io::Error> {
@@ -442,17 +512,56 @@ fn write_repository_files_to_xml<W: Write, N: Write, D: Write>(
         "This node contains a list of files with their full paths and contents serialized as CDATA.",
     )?;
 
-    for file_path in file_paths {
-        let full_path = base_path.join(file_path);
-        let file_size = metadata(&full_path)?.len();
-        match read_classify_and_decode(&full_path, flags.utf8, timings) {
-            Ok(ProcessedFile::Text(decoded)) => write_processed_text_file(
-                writer, file_path, file_size, decoded, flags, reporter,
-            )?,
+    let line_ranges = line_ranges(source, flags)?;
+    let grep_context = grep_pattern(flags)?.zip(flags.grep_context);
+    for file_path in &file_tree.file_paths {
+        let entry = FileEntry {
+            path: file_path,
+            change: file_tree.changes.get(file_path),
+            last_commit: file_tree
+                .history
+                .last_commits
+                .get(file_path)
+                .map(String::as_str),
+        };
+        if entry.change.is_some_and(|change| {
+            change.kind == ChangeKind::Deleted || flags.patch_only
+        }) {
+            write_patch_file_entry(writer, entry)?;
+            continue;
+        }
+        let file_size = source.file_size(file_path)?;
+        match read_classify_and_decode(source, file_path, flags.utf8, timings)
+        {
+            Ok(ProcessedFile::Text(decoded)) => {
+                let ranges = match (line_ranges.get(file_path), &grep_context)
+                {
+                    (Some(ranges), _) => ranges.clone(),
+                    (None, Some((pattern, context))) => {
+                        let normalized = xml_logical_text(&decoded.text);
+                        let lines = normalized.lines().collect::<Vec<_>>();
+                        match_ranges(&lines, pattern, *context)
+                    }
+                    (None, None) => Vec::new(),
+                };
+                // A pattern that only matches across lines selects the
+                // whole file.
+                if ranges.is_empty() {
+                    write_processed_text_file(
+                        writer, entry, file_size, decoded, flags, tokenizer,
+                        reporter,
+                    )?;
+                } else {
+                    write_line_range_entries(
+                        writer, entry, file_size, &decoded, &ranges, flags,
+                        reporter,
+                    )?;
+                }
+            }
             Ok(ProcessedFile::Binary(_)) => {
                 write_placeholder_file_entry(
                     writer,
-                    file_path,
+                    entry,
                     file_size,
                     "This file is a binary file and not included",
                 )?;
@@ -461,14 +570,10 @@ fn write_repository_files_to_xml<W: Write, N: Write, D: Write>(
                 let error_message = err.to_string();
                 reporter.error(&format!(
                     "Error reading file '{}': {}",
-                    full_path.display(),
+                    source.describe(file_path),
                     error_message
                 ))?;
-                write_read_error_file_entry(
-                    writer,
-                    file_path,
-                    &error_message,
-                )?;
+                write_read_error_file_entry(writer, entry, &error_message)?;
             }
         }
     }
@@ -478,75 +583,226 @@ fn write_repository_files_to_xml<W: Write, N: Write, D: Write>(
 
 fn write_processed_text_file<W: Write, N: Write, D: Write>(
     writer: &mut EventWriter<W>,
-    path: &str,
+    entry: FileEntry<'_>,
     size: u64,
     mut decoded: DecodedText,
     flags: &Params,
+    tokenizer: &TokenizerType,
     reporter: &mut ProgressReporter<N, D>,
 ) -> io::Result<()> {
-    if let Some(ref conversion) = decoded.conversion {
-        reporter.conversion(path, conversion)?;
+    if write_unrepresentable_text_entry(
+        writer, entry, size, &decoded, reporter,
+    )? {
+        return Ok(());
     }
-    if decoded.utf8_had_replacements {
-        reporter.malformed_utf8_replacement(path)?;
-    }
-    if let Some(invalid) = first_invalid_xml10_char(&decoded.text) {
-        let code_point = format_code_point(invalid.character);
-        reporter.warning(&format!(
-            "warning: '{path}' content was omitted because XML 1.0 cannot represent character {code_point}"
-        ))?;
-        let comment = format!(
-            "Text content omitted: XML 1.0 cannot represent character {}",
-            code_point,
-        );
-        return write_placeholder_file_entry(writer, path, size, &comment);
+    if flags.truncate_lines.is_some() || flags.truncate_tokens.is_some() {
+        let normalized = xml_logical_text(&decoded.text);
+        let lines = normalized.lines().collect::<Vec<_>>();
+        if let Some(truncation) = plan_truncation(
+            &normalized,
+            &lines,
+            flags.truncate_lines,
+            flags.truncate_tokens,
+            tokenizer,
+        )
+        .map_err(io::Error::other)?
+        {
+            let content =
+                render_truncated(&lines, &truncation, flags.line_numbers);
+            let original_lines = truncation.original_lines.to_string();
+            return write_file_content_entry(
+                writer,
+                entry,
+                size,
+                &content,
+                &[("truncated", "true"), ("original_lines", &original_lines)],
+            );
+        }
     }
     if flags.line_numbers {
         decoded.text = add_line_numbers(&decoded.text);
     }
-    write_text_file_entry(writer, path, size, &decoded.text)
+    write_text_file_entry(writer, entry, size, &decoded.text)
 }
 
 fn write_text_file_entry<W: Write>(
     writer: &mut EventWriter<W>,
-    path: &str,
+    entry: FileEntry<'_>,
     size: u64,
     content: &str,
+) -> io::Result<()> {
+    write_file_content_entry(writer, entry, size, content, &[])
+}
+
+/// Reports conversions and replacements in decoded text, and writes a
+/// placeholder entry instead when XML 1.0 cannot represent the text. Returns
+/// whether the placeholder was written.
+fn write_unrepresentable_text_entry<W: Write, N: Write, D: Write>(
+    writer: &mut EventWriter<W>,
+    entry: FileEntry<'_>,
+    size: u64,
+    decoded: &DecodedText,
+    reporter: &mut ProgressReporter<N, D>,
+) -> io::Result<bool> {
+    if let Some(ref conversion) = decoded.conversion {
+        reporter.conversion(entry.path, conversion)?;
+    }
+    if decoded.utf8_had_replacements {
+        reporter.malformed_utf8_replacement(entry.path)?;
+    }
+    let Some(invalid) = first_invalid_xml10_char(&decoded.text) else {
+        return Ok(false);
+    };
+    let code_point = format_code_point(invalid.character);
+    reporter.warning(&format!(
+        "warning: '{}' content was omitted because XML 1.0 cannot represent character {code_point}",
+        entry.path
+    ))?;
+    let comment = format!(
+        "Text content omitted: XML 1.0 cannot represent character {}",
+        code_point,
+    );
+    write_placeholder_file_entry(writer, entry, size, &comment)?;
+    Ok(true)
+}
+
+/// Writes one partial `<file>` entry for each selected line range, keeping
+/// the line numbers of the original file.
+fn write_line_range_entries<W: Write, N: Write, D: Write>(
+    writer: &mut EventWriter<W>,
+    entry: FileEntry<'_>,
+    size: u64,
+    decoded: &DecodedText,
+    ranges: &[LineRange],
+    flags: &Params,
+    reporter: &mut ProgressReporter<N, D>,
+) -> io::Result<()> {
+    if write_unrepresentable_text_entry(
+        writer, entry, size, decoded, reporter,
+    )? {
+        return Ok(());
+    }
+    let normalized = xml_logical_text(&decoded.text);
+    let lines = normalized.lines().collect::<Vec<_>>();
+    for range in ranges {
+        let Some((content, end)) =
+            render_line_range(&lines, *range, flags.line_numbers)
+        else {
+            reporter.warning(&format!(
+                "warning: '{}' has {} lines, so lines {}-{} were not included",
+                entry.path,
+                lines.len(),
+                range.start,
+                range.end
+            ))?;
+            continue;
+        };
+        let (start, end) = (range.start.to_string(), end.to_string());
+        write_file_content_entry(
+            writer,
+            entry,
+            size,
+            &content,
+            &[("start_line", &start), ("end_line", &end)],
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes a `<file>` entry with its contents, followed by any `attributes`
+/// describing partial content.
+fn write_file_content_entry<W: Write>(
+    writer: &mut EventWriter<W>,
+    entry: FileEntry<'_>,
+    size: u64,
+    content: &str,
+    attributes: &[(&str, &str)],
 ) -> io::Result<()> {
     let size = size.to_string();
     let lines = xml_logical_text(content).lines().count().to_string();
-    writer
-        .write(
-            XmlEvent::start_element("file")
-                .attr("path", path)
-                .attr("size", &size)
-                .attr("lines", &lines),
-        )
-        .map_err(map_xml_error)?;
+    let element = attributes.iter().fold(
+        file_start_element(entry)
+            .attr("size", &size)
+            .attr("lines", &lines),
+        |element, (name, value)| element.attr(*name, value),
+    );
+    writer.write(element).map_err(map_xml_error)?;
     writer
         .write(XmlEvent::cdata(content))
         .map_err(map_xml_error)?;
+    end_file_element(writer, entry)
+}
+
+/// Starts a `<file>` entry with its path, its language when recognised and,
+/// in a `--diff` bundle, how it changed.
+fn file_start_element(entry: FileEntry<'_>) -> StartElementBuilder<'_> {
+    let mut element = XmlEvent::start_element("file").attr("path", entry.path);
+    if let Some(language) = language_for(entry.path) {
+        element = element.attr("language", language);
+    }
+    if let Some(change) = entry.change {
+        element = element.attr("change", change.kind.as_str());
+        if let Some(previous_path) = &change.previous_path {
+            element = element.attr("previous_path", previous_path);
+        }
+    }
+    if let Some(last_commit) = entry.last_commit {
+        element = element.attr("last_commit", last_commit);
+    }
+    element
+}
+
+/// Ends a `<file>` entry, first writing its patch in a `<diff>` element when
+/// one was computed.
+fn end_file_element<W: Write>(
+    writer: &mut EventWriter<W>,
+    entry: FileEntry<'_>,
+) -> io::Result<()> {
+    if let Some(patch) = entry.change.and_then(|change| change.patch.as_ref())
+    {
+        writer
+            .write(XmlEvent::start_element("diff"))
+            .map_err(map_xml_error)?;
+        writer
+            .write(XmlEvent::cdata(&xml_safe_diagnostic_comment(patch)))
+            .map_err(map_xml_error)?;
+        writer
+            .write(XmlEvent::end_element())
+            .map_err(map_xml_error)?;
+    }
     writer.write(XmlEvent::end_element()).map_err(map_xml_error)
 }
 
+/// Writes an entry with its patch but no content, for a deleted file or
+/// with `--patch-only`.
+fn write_patch_file_entry<W: Write>(
+    writer: &mut EventWriter<W>,
+    entry: FileEntry<'_>,
+) -> io::Result<()> {
+    writer
+        .write(file_start_element(entry))
+        .map_err(map_xml_error)?;
+    end_file_element(writer, entry)
+}
+
 fn write_placeholder_file_entry<W: Write>(
     writer: &mut EventWriter<W>,
-    path: &str,
+    entry: FileEntry<'_>,
     size: u64,
     diagnostic: &str,
 ) -> io::Result<()> {
     let size = size.to_string();
-    write_file_entry_with_comment(writer, path, &size, diagnostic)
+    write_file_entry_with_comment(writer, entry, &size, diagnostic)
 }
 
 fn write_read_error_file_entry<W: Write>(
     writer: &mut EventWriter<W>,
-    path: &str,
+    entry: FileEntry<'_>,
     diagnostic: &str,
 ) -> io::Result<()> {
     write_file_entry_with_comment(
         writer,
-        path,
+        entry,
         "0",
         &format!("Failed to read file: {diagnostic}"),
     )
@@ -554,14 +810,13 @@ fn write_read_error_file_entry<W: Write>(
 
 fn write_file_entry_with_comment<W: Write>(
     writer: &mut EventWriter<W>,
-    path: &str,
+    entry: FileEntry<'_>,
     size: &str,
     diagnostic: &str,
 ) -> io::Result<()> {
     writer
         .write(
-            XmlEvent::start_element("file")
-                .attr("path", path)
+            file_start_element(entry)
                 .attr("size", size)
                 .attr("lines", "0"),
         )
@@ -570,7 +825,7 @@ fn write_file_entry_with_comment<W: Write>(
     writer
         .write(XmlEvent::comment(&comment))
         .map_err(map_xml_error)?;
-    writer.write(XmlEvent::end_element()).map_err(map_xml_error)
+    end_file_element(writer, entry)
 }
 
 fn xml_safe_diagnostic_comment(diagnostic: &str) -> String {
@@ -586,14 +841,91 @@ fn xml_safe_diagnostic_comment(diagnostic: &str) -> String {
         .collect()
 }
 
+/// Writes the `--hotspots-section` ranking, riskiest first.
+fn write_hotspots<W: Write>(
+    writer: &mut EventWriter<W>,
+    hotspots: &[Hotspot],
+) -> io::Result<()> {
+    writer
+        .write(XmlEvent::start_element("hotspots"))
+        .map_err(map_xml_error)?;
+    write_text_element(
+        writer,
+        "summary",
+        &format!(
+            "This node ranks the bundled files by the number of commits that touched them, then by distinct authors, then by lines changed in the last {} days.",
+            RECENT_CHURN_DAYS
+        ),
+    )?;
+    for (index, hotspot) in hotspots.iter().enumerate() {
+        writer
+            .write(
+                XmlEvent::start_element("hotspot")
+                    .attr("rank", &(index + 1).to_string())
+                    .attr("path", &hotspot.path)
+                    .attr("commits", &hotspot.commits.to_string())
+                    .attr("authors", &hotspot.authors.to_string())
+                    .attr("recent_churn", &hotspot.recent_churn.to_string()),
+            )
+            .map_err(map_xml_error)?;
+        writer
+            .write(XmlEvent::end_element())
+            .map_err(map_xml_error)?;
+    }
+    writer.write(XmlEvent::end_element()).map_err(map_xml_error)
+}
+
+/// Writes the `--history` commits. Commit metadata that XML 1.0 cannot
+/// represent is replaced rather than failing the bundle.
+fn write_git_history<W: Write>(
+    writer: &mut EventWriter<W>,
+    commits: &[CommitSummary],
+) -> io::Result<()> {
+    writer
+        .write(XmlEvent::start_element("git_history"))
+        .map_err(map_xml_error)?;
+    write_text_element(
+        writer,
+        "summary",
+        "This node lists the most recent commits that touched the bundled files, newest first.",
+    )?;
+    for commit in commits {
+        let author =
+            xml_safe_diagnostic_comment(&commit.author).replace('\t', " ");
+        writer
+            .write(
+                XmlEvent::start_element("commit")
+                    .attr("sha", &commit.sha)
+                    .attr("author", &author)
+                    .attr("date", &commit.date),
+            )
+            .map_err(map_xml_error)?;
+        write_text_element(
+            writer,
+            "subject",
+            &xml_safe_diagnostic_comment(&commit.subject),
+        )?;
+        for path in &commit.paths {
+            write_text_element(writer, "path", path)?;
+        }
+        writer
+            .write(XmlEvent::end_element())
+            .map_err(map_xml_error)?;
+    }
+    writer.write(XmlEvent::end_element()).map_err(map_xml_error)
+}
+
 /// Map XML writing errors to IO errors
 fn map_xml_error(err: xml::writer::Error) -> std::io::Error {
     std::io::Error::other(err)
 }
 
+/// Writes the summary and its notes. `bundlerepoignore` tells whether a
+/// `.bundlerepoignore` file was found and applied while listing the files.
 fn write_file_summary<W: Write>(
     writer: &mut EventWriter<W>,
     flags: &Params,
+    bundlerepoignore: bool,
 ) -> io::Result<()> {
     writer
         .write(XmlEvent::start_element("file_summary"))
@@ -623,11 +955,82 @@ fn write_file_summary<W: Write>(
         "usage_guidelines",
         "- This file should be treated as read-only. Any changes should be made to the\n  original repository files, not this packed version.\n- When processing this file, use the file path to distinguish\n  between different files in the repository.\n- Be aware that this file may contain sensitive information. Handle it with\n  the same level of security as you would the original repository.",
     )?;
-    write_text_element(
-        writer,
-        "notes",
-        "- Some files may have been excluded based on .gitignore rules and bundlerepo's\n  configuration.\n- Binary files and text that XML 1.0 cannot represent are not included in this\n  packed representation. Please refer to the Repository Structure section for\n  a complete list of file paths, including omitted files.",
-    )?;
+    let bundlerepoignore_note = if bundlerepoignore {
+        "\n- Files matched by .bundlerepoignore rules in the repository have been\n  excluded."
+    } else {
+        ""
+    };
+    let scope_note = match (&flags.files_from, &flags.paths) {
+        (Some(_), _) => {
+            "\n- Only the files given in a file list have been included."
+                .to_string()
+        }
+        (None, Some(paths)) => format!(
+            "\n- Only these paths within the repository have been included: {}.",
+            paths.join(", ")
+        ),
+        (None, None) => String::new(),
+    };
+    let package_note = flags
+        .packages
+        .as_ref()
+        .map(|packages| {
+            let dependencies = if flags.package_path_dependencies {
+                " and their local path dependencies"
+            } else {
+                ""
+            };
+            format!(
+                "\n- Only these workspace packages{dependencies}, with the workspace\n  root manifest, have been included: {}.",
+                packages.join(", ")
+            )
+        })
+        .unwrap_or_default();
+    let line_range_note = if selected_path_specs(flags)
+        .any(|path| parse_path_spec(path).is_ok_and(|spec| spec.is_partial()))
+    {
+        "\n- Files selected with a line range or a symbol contain only those lines,\n  and have start_line and end_line attributes giving their place in the\n  original file."
+    } else {
+        ""
+    };
+    let grep_note = match (&flags.grep, flags.grep_context) {
+        (Some(pattern), None) => format!(
+            "\n- Only files whose text matches the pattern '{pattern}' have been included."
+        ),
+        (Some(pattern), Some(context)) => format!(
+            "\n- Only files whose text matches the pattern '{pattern}' have been included,\n  and only the matching lines with {context} lines either side, as entries\n  with start_line and end_line attributes."
+        ),
+        (None, _) => String::new(),
+    };
+    let follow_note = flags
+        .follow
+        .as_ref()
+        .map(|seeds| {
+            format!(
+                "\n- Only these files and the local files they import have been included: {}.",
+                seeds.join(", ")
+            )
+        })
+        .unwrap_or_default();
+    let limits_note = if flags.max_file_size.is_some()
+        || flags.max_file_lines.is_some()
+        || flags.modified_within.is_some()
+    {
+        "\n- Files over the configured size, line count or age limits are listed in the\n  Repository Structure section with a \"skipped\" reason, but their contents\n  are not included."
+    } else {
+        ""
+    };
+    let truncation_note = if flags.truncate_lines.is_some()
+        || flags.truncate_tokens.is_some()
+    {
+        "\n- Long files have been truncated to their first and last lines, with a\n  \"... [N lines elided] ...\" marker in place of the rest. These files have\n  truncated=\"true\" and original_lines attributes."
+    } else {
+        ""
+    };
+    let notes = format!(
+        "- Some files may have been excluded based on .gitignore rules and bundlerepo's\n  configuration.{bundlerepoignore_note}{scope_note}{package_note}{line_range_note}{grep_note}{follow_note}{limits_note}{truncation_note}\n- Binary files and text that XML 1.0 cannot represent are not included in this\n  packed representation. Please refer to the Repository Structure section for\n  a complete list of file paths, including omitted files."
+    );
+    write_text_element(writer, "notes", &notes)?;
     write_text_element(
         writer,
         "additional_info",
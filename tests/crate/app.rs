@@ -69,6 +69,33 @@ fn test_invalid_config_falls_back_to_defaults() {
     assert_eq!(params, Params::default());
 }
 
+#[test]
+fn test_token_in_a_committable_local_config_is_warned_about() {
+    let temp_dir = tempdir().unwrap();
+    let local_config = temp_dir.path().join(".bundlerepo.toml");
+    fs::write(&local_config, "token = \"secret\"\n").unwrap();
+    assert_eq!(local_token_warning(&local_config), None);
+
+    Repository::init(temp_dir.path()).unwrap();
+    let warning = local_token_warning(&local_config).unwrap();
+    assert!(warning.starts_with(&format!(
+        "warning: '{}' holds a token and is not ignored by git",
+        local_config.display()
+    )));
+
+    fs::write(&local_config, "[tokens]\n\"gitlab.com\" = \"secret\"\n")
+        .unwrap();
+    assert!(local_token_warning(&local_config).is_some());
+
+    fs::write(temp_dir.path().join(".gitignore"), ".bundlerepo.toml\n")
+        .unwrap();
+    assert_eq!(local_token_warning(&local_config), None);
+
+    fs::remove_file(temp_dir.path().join(".gitignore")).unwrap();
+    fs::write(&local_config, "model = \"gpt5\"\n").unwrap();
+    assert_eq!(local_token_warning(&local_config), None);
+}
+
 #[test]
 fn test_success_report_names_file_and_metrics() {
     let params = Params {
@@ -90,6 +117,37 @@ fn test_success_report_names_file_and_metrics() {
     assert!(diagnostic.is_empty());
 }
 
+#[test]
+fn test_hotspot_report_shows_the_top_files() {
+    let hotspots = (0..12)
+        .map(|index| history::Hotspot {
+            path: format!("src/file{index}.rs"),
+            commits: 20 - index,
+            authors: 2,
+            recent_churn: 40,
+        })
+        .collect::<Vec<_>>();
+    let mut reporter =
+        progress::ProgressReporter::new(Vec::new(), Vec::new(), false);
+
+    report_hotspots(&hotspots, &mut reporter).unwrap();
+
+    let (normal, _) = reporter.into_parts();
+    let normal = String::from_utf8(normal).unwrap();
+    assert!(normal.starts_with("Hotspots (recent churn is lines changed"));
+    let rows = normal
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows[1],
+        ["Rank", "File", "Commits", "Authors", "Recent", "churn"]
+    );
+    assert_eq!(rows[2], ["1", "src/file0.rs", "20", "2", "40"]);
+    assert_eq!(rows[11], ["10", "src/file9.rs", "11", "2", "40"]);
+    assert!(!normal.contains("src/file10.rs"));
+}
+
 #[test]
 fn test_success_report_names_clipboard_destination() {
     let params = Params {
@@ -304,6 +362,365 @@ fn test_application_runs_local_repository_and_reports_success() {
     assert!(diagnostic.is_empty());
 }
 
+#[test]
+fn test_path_scopes_keep_repository_relative_paths() {
+    let temp_dir = tempdir().unwrap();
+    initialize_repository(temp_dir.path());
+    for path in [
+        "services/billing/src/lib.rs",
+        "services/search/src/lib.rs",
+        "libs/common/util.rs",
+        "README.md",
+    ] {
+        let path = temp_dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "content").unwrap();
+    }
+    let output_path = temp_dir.path().join("output.xml");
+    let args = Flags::parse_from([
+        "program",
+        "--path",
+        "services/billing",
+        "--path",
+        "libs/common",
+    ]);
+    let params = Params {
+        output_file: Some(output_path.to_string_lossy().into_owned()),
+        ..Params::from_args_and_config(&args, Params::default())
+    };
+    let mut reporter =
+        progress::ProgressReporter::new(Vec::new(), Vec::new(), false);
+    let mut timings = timings::ProcessingTimings::default();
+
+    // Scopes resolve from the repository root, not the starting directory.
+    run_application(
+        &args,
+        &params,
+        &temp_dir.path().join("services/search"),
+        &mut reporter,
+        &mut timings,
+    )
+    .unwrap();
+
+    let xml = fs::read_to_string(output_path).unwrap();
+    assert!(xml.contains(r#"<file path="services/billing/src/lib.rs" "#));
+    assert!(xml.contains(r#"<file path="libs/common/util.rs" "#));
+    assert!(!xml.contains("services/search"));
+    assert!(!xml.contains("README.md"));
+}
+
+#[test]
+fn test_repository_argument_bundles_a_local_directory() {
+    let temp_dir = tempdir().unwrap();
+    let other = temp_dir.path().join("other-service");
+    initialize_repository(&other);
+    fs::write(other.join("service.rs"), "other content").unwrap();
+    let output_path = temp_dir.path().join("output.xml");
+    let args = Flags::parse_from(["program", &other.to_string_lossy()]);
+    let params = Params {
+        output_file: Some(output_path.to_string_lossy().into_owned()),
+        ..Params::default()
+    };
+    let mut reporter =
+        progress::ProgressReporter::new(Vec::new(), Vec::new(), false);
+    let mut timings = timings::ProcessingTimings::default();
+
+    run_application(
+        &args,
+        &params,
+        temp_dir.path(),
+        &mut reporter,
+        &mut timings,
+    )
+    .unwrap();
+
+    let xml = fs::read_to_string(output_path).unwrap();
+    assert!(xml.contains(r#"<file path="service.rs" "#));
+    assert!(xml.contains("other content"));
+}
+
+#[test]
+fn test_from_root_bundles_the_whole_repository_from_a_subdirectory() {
+    let temp_dir = tempdir().unwrap();
+    initialize_repository(temp_dir.path());
+    for path in ["src/main.rs", "README.md"] {
+        let path = temp_dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "content").unwrap();
+    }
+    let output_path = temp_dir.path().join("output.xml");
+    let args = Flags::parse_from(["program", "--from-root"]);
+    let params = Params {
+        output_file: Some(output_path.to_string_lossy().into_owned()),
+        ..Params::from_args_and_config(&args, Params::default())
+    };
+    let mut reporter =
+        progress::ProgressReporter::new(Vec::new(), Vec::new(), false);
+    let mut timings = timings::ProcessingTimings::default();
+
+    run_application(
+        &args,
+        &params,
+        &temp_dir.path().join("src"),
+        &mut reporter,
+        &mut timings,
+    )
+    .unwrap();
+
+    let xml = fs::read_to_string(output_path).unwrap();
+    assert!(xml.contains(r#"<file path="src/main.rs" "#));
+    assert!(xml.contains(r#"<file path="README.md" "#));
+}
+
+#[test]
+fn test_files_are_bundled_in_path_order_by_default() {
+    let temp_dir = tempdir().unwrap();
+    initialize_repository(temp_dir.path());
+    for path in ["src/main.rs", "README.md", "build.rs"] {
+        let path = temp_dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "content").unwrap();
+    }
+    let output_path = temp_dir.path().join("output.xml");
+    let args = Flags::parse_from(["program"]);
+    let params = Params {
+        output_file: Some(output_path.to_string_lossy().into_owned()),
+        files_from: Some(
+            ["src/main.rs", "build.rs", "README.md"]
+                .map(String::from)
+                .into(),
+        ),
+        ..Params::from_args_and_config(&args, Params::default())
+    };
+    let mut reporter =
+        progress::ProgressReporter::new(Vec::new(), Vec::new(), false);
+    let mut timings = timings::ProcessingTimings::default();
+
+    run_application(
+        &args,
+        &params,
+        temp_dir.path(),
+        &mut reporter,
+        &mut timings,
+    )
+    .unwrap();
+
+    let xml = fs::read_to_string(output_path).unwrap();
+    let (_, files) = xml.split_once("<repository_files>").unwrap();
+    let positions = ["README.md", "build.rs", "src/main.rs"]
+        .map(|path| files.find(&format!(r#"<file path="{path}" "#)).unwrap());
+    assert!(positions.is_sorted());
+}
+
+fn commit_all(repo: &Repository, message: &str) -> git2::Oid {
+    let mut index = repo.index().unwrap();
+    index
+        .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    index.update_all(["*"], None).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let parent = repo.head().unwrap().peel_to_commit().unwrap();
+    let signature = Signature::now("Test", "test@example.com").unwrap();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &[&parent],
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_diff_bundles_only_the_changed_files() {
+    let temp_dir = tempdir().unwrap();
+    let repo_path = temp_dir.path().join("repo");
+    initialize_repository(&repo_path);
+    let repo = Repository::open(&repo_path).unwrap();
+    fs::create_dir_all(repo_path.join("src")).unwrap();
+    for (path, content) in [
+        ("src/main.rs", "fn main() {}\n"),
+        ("src/kept.rs", "pub fn kept() {}\n"),
+        ("src/old.rs", "pub fn old() {}\n"),
+    ] {
+        fs::write(repo_path.join(path), content).unwrap();
+    }
+    let base = commit_all(&repo, "base");
+    repo.tag_lightweight(
+        "base",
+        &repo.find_object(base, None).unwrap(),
+        false,
+    )
+    .unwrap();
+    fs::write(repo_path.join("src/main.rs"), "fn main() { run(); }\n")
+        .unwrap();
+    fs::remove_file(repo_path.join("src/old.rs")).unwrap();
+    commit_all(&repo, "change");
+    fs::write(repo_path.join("src/main.rs"), "uncommitted").unwrap();
+    let output_path = temp_dir.path().join("output.xml");
+    let args =
+        Flags::parse_from(["program", "--diff", "base..HEAD", "--diff-patch"]);
+    let params = Params {
+        output_file: Some(output_path.to_string_lossy().into_owned()),
+        ..Params::from_args_and_config(&args, Params::default())
+    };
+    let mut reporter =
+        progress::ProgressReporter::new(Vec::new(), Vec::new(), false);
+    let mut timings = timings::ProcessingTimings::default();
+
+    assert_eq!(params.git_ref.as_deref(), Some("HEAD"));
+    run_application(
+        &args,
+        &params,
+        &repo_path.join("src"),
+        &mut reporter,
+        &mut timings,
+    )
+    .unwrap();
+
+    let xml = fs::read_to_string(output_path).unwrap();
+    assert!(xml.contains(
+        r#"<file path="src/main.rs" language="rust" change="modified""#
+    ));
+    assert!(xml.contains("fn main() { run(); }"));
+    assert!(xml.contains("+fn main() { run(); }"));
+    assert!(!xml.contains("uncommitted"));
+    assert!(xml.contains(
+        r#"<file path="src/old.rs" language="rust" change="deleted">"#
+    ));
+    assert!(xml.contains("-pub fn old() {}"));
+    assert!(!xml.contains("src/kept.rs"));
+}
+
+#[test]
+fn test_staged_patch_only_leaves_out_file_content() {
+    let temp_dir = tempdir().unwrap();
+    let repo_path = temp_dir.path().join("repo");
+    initialize_repository(&repo_path);
+    let repo = Repository::open(&repo_path).unwrap();
+    fs::write(repo_path.join("lib.rs"), "pub fn lib() {}\n").unwrap();
+    fs::write(repo_path.join("other.rs"), "pub fn other() {}\n").unwrap();
+    commit_all(&repo, "base");
+    fs::write(repo_path.join("lib.rs"), "pub fn lib() -> u8 { 1 }\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("lib.rs")).unwrap();
+    index.write().unwrap();
+    fs::write(repo_path.join("other.rs"), "unstaged").unwrap();
+    let output_path = temp_dir.path().join("output.xml");
+    let args = Flags::parse_from(["program", "--staged", "--patch-only"]);
+    let params = Params {
+        output_file: Some(output_path.to_string_lossy().into_owned()),
+        ..Params::from_args_and_config(&args, Params::default())
+    };
+    let mut reporter =
+        progress::ProgressReporter::new(Vec::new(), Vec::new(), false);
+    let mut timings = timings::ProcessingTimings::default();
+
+    assert!(params.diff_patch);
+    run_application(&args, &params, &repo_path, &mut reporter, &mut timings)
+        .unwrap();
+
+    let xml = fs::read_to_string(output_path).unwrap();
+    assert!(xml.contains(
+        r#"<file path="lib.rs" language="rust" change="modified">"#
+    ));
+    assert!(xml.contains("+pub fn lib() -> u8 { 1 }"));
+    assert!(!xml.contains("<![CDATA[pub fn lib() -> u8 { 1 }"));
+    assert!(!xml.contains("other.rs"));
+}
+
+#[test]
+fn test_bare_repository_bundles_head_with_its_gitignore() {
+    let temp_dir = tempdir().unwrap();
+    let source_path = temp_dir.path().join("source");
+    initialize_repository(&source_path);
+    let source = Repository::open(&source_path).unwrap();
+    fs::write(source_path.join("lib.rs"), "pub fn lib() {}\n").unwrap();
+    fs::write(source_path.join(".gitignore"), "*.log\n").unwrap();
+    fs::write(source_path.join("debug.log"), "log content").unwrap();
+    let mut index = source.index().unwrap();
+    index.add_path(Path::new("debug.log")).unwrap();
+    index.write().unwrap();
+    commit_all(&source, "tracked log");
+    let mirror_path = temp_dir.path().join("mirror.git");
+    git2::build::RepoBuilder::new()
+        .bare(true)
+        .clone(&format!("file://{}", source_path.display()), &mirror_path)
+        .unwrap();
+    let output_path = temp_dir.path().join("output.xml");
+    let args = Flags::parse_from(["program", &mirror_path.to_string_lossy()]);
+    let params = Params {
+        output_file: Some(output_path.to_string_lossy().into_owned()),
+        ..Params::from_args_and_config(&args, Params::default())
+    };
+    let mut reporter =
+        progress::ProgressReporter::new(Vec::new(), Vec::new(), false);
+    let mut timings = timings::ProcessingTimings::default();
+
+    assert_eq!(params.git_ref, None);
+    run_application(
+        &args,
+        &params,
+        temp_dir.path(),
+        &mut reporter,
+        &mut timings,
+    )
+    .unwrap();
+
+    let xml = fs::read_to_string(output_path).unwrap();
+    assert!(xml.contains(r#"<file path="lib.rs" "#));
+    assert!(!xml.contains("debug.log"));
+    assert!(!mirror_path.join("lib.rs").exists());
+}
+
+#[test]
+fn test_repository_argument_bundles_an_archive() {
+    let temp_dir = tempdir().unwrap();
+    let archive_path = temp_dir.path().join("vendor.tar");
+    let mut builder =
+        tar::Builder::new(fs::File::create(&archive_path).unwrap());
+    for (path, content) in [
+        ("vendor/lib.rs", "vendor content"),
+        ("vendor/.gitignore", "*.log\n"),
+        ("vendor/debug.log", "log content"),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, path, content.as_bytes())
+            .unwrap();
+    }
+    builder.finish().unwrap();
+    let output_path = temp_dir.path().join("output.xml");
+    let args = Flags::parse_from(["program", &archive_path.to_string_lossy()]);
+    let params = Params {
+        output_file: Some(output_path.to_string_lossy().into_owned()),
+        ..Params::from_args_and_config(&args, Params::default())
+    };
+    let mut reporter =
+        progress::ProgressReporter::new(Vec::new(), Vec::new(), false);
+    let mut timings = timings::ProcessingTimings::default();
+
+    assert!(params.no_git);
+    run_application(
+        &args,
+        &params,
+        temp_dir.path(),
+        &mut reporter,
+        &mut timings,
+    )
+    .unwrap();
+
+    let xml = fs::read_to_string(output_path).unwrap();
+    assert!(xml.contains(r#"<file path="lib.rs" "#));
+    assert!(xml.contains("vendor content"));
+    assert!(!xml.contains("debug.log"));
+}
+
 #[test]
 fn test_application_maps_tokenizer_failure_to_exit_code() {
     let params = Params {
@@ -373,7 +790,43 @@ fn test_application_maps_discovery_failure_to_exit_code() {
     .unwrap_err();
 
     assert_eq!(error.exit_code(), 3);
-    assert_eq!(error.to_string(), "Error: Not a git repository");
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Error: No git repository found at '{}'. Use --no-git to bundle it as a plain directory.",
+            temp_dir.path().display()
+        )
+    );
+}
+
+#[test]
+fn test_no_git_bundles_a_plain_directory() {
+    let temp_dir = tempdir().unwrap();
+    fs::write(temp_dir.path().join("example.txt"), "example content").unwrap();
+    fs::write(temp_dir.path().join(".gitignore"), "*.log\n").unwrap();
+    fs::write(temp_dir.path().join("debug.log"), "log content").unwrap();
+    let output_path = temp_dir.path().join("output.xml");
+    let args = Flags::parse_from(["program", "--no-git"]);
+    let params = Params {
+        output_file: Some(output_path.to_string_lossy().into_owned()),
+        ..Params::from_args_and_config(&args, Params::default())
+    };
+    let mut reporter =
+        progress::ProgressReporter::new(Vec::new(), Vec::new(), false);
+    let mut timings = timings::ProcessingTimings::default();
+
+    run_application(
+        &args,
+        &params,
+        temp_dir.path(),
+        &mut reporter,
+        &mut timings,
+    )
+    .unwrap();
+
+    let xml = fs::read_to_string(output_path).unwrap();
+    assert!(xml.contains("example content"));
+    assert!(!xml.contains("debug.log"));
 }
 
 #[test]
@@ -408,6 +861,98 @@ fn test_application_maps_output_failure_to_exit_code() {
     assert!(error.to_string().starts_with("X  Failed to write XML: "));
 }
 
+#[test]
+fn test_git_state_cli_and_config_precedence() {
+    let cases = [
+        ("", vec!["program"], (false, false)),
+        ("tracked_only = true", vec!["program"], (true, false)),
+        (
+            "tracked_only = true",
+            vec!["program", "--include-untracked"],
+            (false, false),
+        ),
+        ("", vec!["program", "--tracked-only"], (true, false)),
+        ("include_ignored = true", vec!["program"], (false, true)),
+        ("", vec!["program", "--include-ignored"], (false, true)),
+        (
+            "tracked_only = true\ninclude_ignored = true",
+            vec!["program", "--no-git"],
+            (false, false),
+        ),
+    ];
+
+    for (config, arguments, (tracked_only, include_ignored)) in cases {
+        let args = Flags::parse_from(arguments);
+        let params =
+            Params::from_args_and_config(&args, create_test_config(config));
+        assert_eq!(params.tracked_only, tracked_only);
+        assert_eq!(params.include_ignored, include_ignored);
+    }
+}
+
+#[test]
+fn test_dry_run_lists_candidates_without_writing_output() {
+    let temp_dir = tempdir().unwrap();
+    initialize_repository(temp_dir.path());
+    fs::write(temp_dir.path().join(".gitignore"), "*.log\n").unwrap();
+    fs::write(temp_dir.path().join("example.txt"), "example").unwrap();
+    fs::write(temp_dir.path().join("debug.log"), "log").unwrap();
+    let output_path = temp_dir.path().join("output.xml");
+    let params = Params {
+        output_file: Some(output_path.to_string_lossy().into_owned()),
+        model: Some("unknown".to_string()),
+        ..Params::default()
+    };
+    let args = Flags::parse_from(["program", "--dry-run"]);
+    let mut reporter =
+        progress::ProgressReporter::new(Vec::new(), Vec::new(), true);
+    let mut timings = timings::ProcessingTimings::default();
+
+    run_application(
+        &args,
+        &params,
+        temp_dir.path(),
+        &mut reporter,
+        &mut timings,
+    )
+    .unwrap();
+
+    assert!(!output_path.exists());
+    let (normal, _) = reporter.into_parts();
+    let normal = String::from_utf8(normal).unwrap();
+    assert!(normal.contains("include  example.txt\n"));
+    assert!(
+        normal
+            .contains("exclude  debug.log  (ignored by .gitignore:1: *.log)")
+    );
+}
+
+#[test]
+fn test_explain_maps_missing_path_to_exit_code() {
+    let temp_dir = tempdir().unwrap();
+    initialize_repository(temp_dir.path());
+    let params = Params::default();
+    let args = Flags::parse_from(["program", "--explain", "missing.rs"]);
+    let mut reporter =
+        progress::ProgressReporter::new(Vec::new(), Vec::new(), false);
+    let mut timings = timings::ProcessingTimings::default();
+
+    let error = run_application(
+        &args,
+        &params,
+        temp_dir.path(),
+        &mut reporter,
+        &mut timings,
+    )
+    .unwrap_err();
+
+    assert_eq!(error.exit_code(), 5);
+    assert_eq!(
+        error.to_string(),
+        "Error: 'missing.rs' does not exist in the repository"
+    );
+}
+
 #[test]
 fn test_utf8_flag_overrides_config_false() {
     let config = create_test_config(
@@ -477,9 +1022,13 @@ fn test_utf8_precedence_controls_utf16_conversion() {
         assert_eq!(params.utf8, expected_utf8);
 
         let mut timings = timings::ProcessingTimings::default();
-        let processed =
-            read_classify_and_decode(&fixture_path, params.utf8, &mut timings)
-                .unwrap();
+        let processed = read_classify_and_decode(
+            temp_dir.path(),
+            "utf-16le.txt",
+            params.utf8,
+            &mut timings,
+        )
+        .unwrap();
         if expected_utf8 {
             match processed {
                 ProcessedFile::Text(decoded) => {
@@ -547,3 +1096,84 @@ fn test_no_gzip_allows_clipboard_override() {
     assert!(!params.gzip);
     assert!(params.clipboard);
 }
+
+#[test]
+fn test_bundlerepoignore_cli_and_config_precedence() {
+    let cases = [
+        ("", vec!["program"], true),
+        ("bundlerepoignore = false", vec!["program"], false),
+        ("", vec!["program", "--no-bundlerepoignore"], false),
+        (
+            "bundlerepoignore = true",
+            vec!["program", "--no-bundlerepoignore"],
+            false,
+        ),
+    ];
+
+    for (config, arguments, expected) in cases {
+        let args = Flags::parse_from(arguments);
+        let params =
+            Params::from_args_and_config(&args, create_test_config(config));
+        assert_eq!(params.bundlerepoignore, expected);
+    }
+}
+
+#[test]
+fn test_cache_command_lists_and_prunes_cached_clones() {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path().join("repos");
+    let mut output = Vec::new();
+    run_cache_command(&cli::CacheAction::List, Some(&root), &mut output)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        format!("No cached repositories in '{}'\n", root.display())
+    );
+
+    let clone_path = root.join("example.com/owner/repo");
+    git2::Repository::init_bare(&clone_path).unwrap();
+    let mut output = Vec::new();
+    run_cache_command(&cli::CacheAction::List, Some(&root), &mut output)
+        .unwrap();
+    let listing = String::from_utf8(output).unwrap();
+    let header = listing.lines().next().unwrap();
+    assert_eq!(
+        header.split_whitespace().collect::<Vec<_>>(),
+        ["Repository", "Size", "(bytes)", "Last", "used"]
+    );
+    assert!(listing.contains("example.com/owner/repo"));
+    assert!(listing.contains("never"));
+    assert!(listing.contains("1 cached repositories"));
+
+    let mut output = Vec::new();
+    run_cache_command(
+        &cli::CacheAction::Prune { older_than: None },
+        Some(&root),
+        &mut output,
+    )
+    .unwrap();
+    let pruned = String::from_utf8(output).unwrap();
+    assert!(pruned.starts_with("-> Removed 'example.com/owner/repo'\n"));
+    assert!(!clone_path.exists());
+
+    let error =
+        run_cache_command(&cli::CacheAction::List, None, &mut Vec::new())
+            .unwrap_err();
+    assert_eq!(error.exit_code(), 7);
+}
+
+#[test]
+fn test_unreadable_token_file_is_a_configuration_error() {
+    let temp_dir = tempdir().unwrap();
+    let missing = temp_dir.path().join("token");
+
+    let error = auth::read_token_file(missing.to_str().unwrap())
+        .map_err(ApplicationError::Config)
+        .unwrap_err();
+
+    assert_eq!(error.exit_code(), 8);
+    assert!(error.to_string().starts_with(&format!(
+        "Error: cannot read token file '{}'",
+        missing.display()
+    )));
+}
@@ -0,0 +1,238 @@
+use super::*;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::io::Write;
+use tempfile::TempDir;
+use zip::write::SimpleFileOptions;
+
+fn append_file<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    content: &str,
+) {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, path, content.as_bytes())
+        .unwrap();
+}
+
+#[test]
+fn test_archive_format_is_read_from_the_file_name() {
+    assert_eq!(
+        ArchiveFormat::from_path(Path::new("release-1.2.TAR.GZ")),
+        Some(ArchiveFormat::TarGz)
+    );
+    assert_eq!(
+        ArchiveFormat::from_path(Path::new("src.tgz")),
+        Some(ArchiveFormat::TarGz)
+    );
+    assert_eq!(
+        ArchiveFormat::from_path(Path::new("src.tar")),
+        Some(ArchiveFormat::Tar)
+    );
+    assert_eq!(
+        ArchiveFormat::from_path(Path::new("src.zip")),
+        Some(ArchiveFormat::Zip)
+    );
+    assert_eq!(ArchiveFormat::from_path(Path::new("src.rs")), None);
+    assert_eq!(archive_input("missing.zip"), None);
+}
+
+#[test]
+fn test_entry_path_rejects_paths_outside_the_archive() {
+    assert_eq!(
+        entry_path("./src/main.rs").unwrap(),
+        Path::new("src/main.rs")
+    );
+    for name in [
+        "../evil.rs",
+        "src/../../evil.rs",
+        "/etc/passwd",
+        "..\\evil.rs",
+    ] {
+        assert_eq!(
+            entry_path(name).unwrap_err().to_string(),
+            format!("archive entry '{name}' escapes the archive root")
+        );
+    }
+}
+
+#[test]
+fn test_tar_gz_unpacks_into_its_top_level_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("release-1.2.tar.gz");
+    let mut builder = tar::Builder::new(GzEncoder::new(
+        File::create(&archive_path).unwrap(),
+        Compression::default(),
+    ));
+    append_file(&mut builder, "release-1.2/src/main.rs", "fn main() {}\n");
+    append_file(&mut builder, "release-1.2/README.md", "# Release\n");
+    let mut link = tar::Header::new_gnu();
+    link.set_entry_type(tar::EntryType::Symlink);
+    link.set_size(0);
+    builder
+        .append_link(&mut link, "release-1.2/passwd", "/etc/passwd")
+        .unwrap();
+    builder.into_inner().unwrap().finish().unwrap();
+
+    let root = extract_archive(
+        &archive_path,
+        ArchiveFormat::TarGz,
+        &temp_dir.path().join("unpacked"),
+        &EntryFilter::new(&Params::default()).unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(root, temp_dir.path().join("unpacked/release-1.2"));
+    assert_eq!(
+        fs::read_to_string(root.join("src/main.rs")).unwrap(),
+        "fn main() {}\n"
+    );
+    assert!(root.join("README.md").is_file());
+    assert!(fs::symlink_metadata(root.join("passwd")).is_err());
+}
+
+#[test]
+fn test_only_files_passing_the_filters_are_unpacked_with_content() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("release.tar");
+    let mut builder = tar::Builder::new(File::create(&archive_path).unwrap());
+    append_file(&mut builder, "release/src/main.rs", "fn main() {}\n");
+    append_file(&mut builder, "release/src/big.rs", &"x".repeat(64));
+    append_file(&mut builder, "release/src/style.css", "body {}\n");
+    append_file(&mut builder, "release/src/notes.md", "# Notes\n");
+    append_file(&mut builder, "release/docs/guide.md", "# Guide\n");
+    append_file(&mut builder, "release/.gitignore", "*.log\n");
+    append_file(&mut builder, "release/Cargo.toml", "[package]\n");
+    builder.into_inner().unwrap();
+    let flags = Params {
+        paths: Some(vec!["src".to_string()]),
+        extend_exclude: Some(vec!["notes.md".to_string()]),
+        languages: Some(vec!["rust".to_string()]),
+        max_file_size: Some(32),
+        ..Params::default()
+    };
+    let filter = EntryFilter::new(&flags).unwrap();
+
+    let root = extract_archive(
+        &archive_path,
+        ArchiveFormat::Tar,
+        &temp_dir.path().join("unpacked"),
+        &filter,
+    )
+    .unwrap();
+
+    assert_eq!(root, temp_dir.path().join("unpacked/release"));
+    assert_eq!(
+        fs::read_to_string(root.join("src/main.rs")).unwrap(),
+        "fn main() {}\n"
+    );
+    assert_eq!(
+        fs::read_to_string(root.join(".gitignore")).unwrap(),
+        "*.log\n"
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("Cargo.toml")).unwrap(),
+        "[package]\n"
+    );
+    // The files left out keep their size and modification time but not their
+    // content.
+    for (path, size) in [
+        ("src/big.rs", 64),
+        ("src/style.css", 8),
+        ("src/notes.md", 8),
+        ("docs/guide.md", 8),
+    ] {
+        let content = fs::read(root.join(path)).unwrap();
+        assert_eq!(content, vec![0; size], "{path}");
+        let modified = fs::metadata(root.join(path)).unwrap().modified();
+        assert_eq!(modified.unwrap(), UNIX_EPOCH, "{path}");
+    }
+}
+
+#[test]
+fn test_zip_files_keep_their_modification_times() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("release.zip");
+    let mut writer = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+    let old = zip::DateTime::from_date_and_time(2001, 2, 3, 4, 5, 6).unwrap();
+    let recent =
+        zip::DateTime::from_date_and_time(2020, 1, 1, 0, 0, 0).unwrap();
+    for (path, modified) in
+        [("old.rs", old), ("new.rs", recent), ("notes.md", old)]
+    {
+        writer
+            .start_file(
+                path,
+                SimpleFileOptions::default().last_modified_time(modified),
+            )
+            .unwrap();
+        writer.write_all(b"content\n").unwrap();
+    }
+    writer.finish().unwrap();
+    let since_2010 = SystemTime::now()
+        .duration_since(UNIX_EPOCH + Duration::from_secs(1_262_304_000))
+        .unwrap();
+    let flags = Params {
+        languages: Some(vec!["rust".to_string()]),
+        modified_within: Some(since_2010.as_secs()),
+        ..Params::default()
+    };
+
+    let root = extract_archive(
+        &archive_path,
+        ArchiveFormat::Zip,
+        &temp_dir.path().join("unpacked"),
+        &EntryFilter::new(&flags).unwrap(),
+    )
+    .unwrap();
+
+    // 2001-02-03T04:05:06Z, for a file unpacked and for a placeholder.
+    for path in ["old.rs", "notes.md"] {
+        let modified = fs::metadata(root.join(path)).unwrap().modified();
+        assert_eq!(
+            modified.unwrap(),
+            UNIX_EPOCH + Duration::from_secs(981_173_106),
+            "{path}"
+        );
+    }
+    let limits = crate::limits::FileLimits::new(&flags);
+    assert_eq!(
+        limits.check(root.as_path(), "old.rs").unwrap(),
+        Some(crate::limits::SkipReason::Age)
+    );
+    assert_eq!(limits.check(root.as_path(), "new.rs").unwrap(), None);
+}
+
+#[test]
+fn test_zip_with_traversal_entry_is_rejected() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("vendor.zip");
+    let mut writer = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+    writer
+        .start_file("lib.rs", SimpleFileOptions::default())
+        .unwrap();
+    writer.write_all(b"pub fn lib() {}\n").unwrap();
+    writer
+        .start_file("../escaped.rs", SimpleFileOptions::default())
+        .unwrap();
+    writer.write_all(b"pub fn escaped() {}\n").unwrap();
+    writer.finish().unwrap();
+
+    let error = extract_archive(
+        &archive_path,
+        ArchiveFormat::Zip,
+        &temp_dir.path().join("unpacked"),
+        &EntryFilter::new(&Params::default()).unwrap(),
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "archive entry '../escaped.rs' escapes the archive root"
+    );
+    assert!(!temp_dir.path().join("escaped.rs").exists());
+}
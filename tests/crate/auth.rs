@@ -0,0 +1,252 @@
+use super::*;
+use std::fs;
+use tempfile::tempdir;
+
+fn credential_type(credentials: &Cred) -> CredentialType {
+    CredentialType::from_bits_truncate(credentials.credtype())
+}
+
+#[test]
+fn test_username_is_taken_from_the_url() {
+    let mut authenticator = Authenticator::new(None, None, None, None);
+
+    let credentials = authenticator
+        .credentials(
+            "ssh://deploy@git.example.com/repo.git",
+            Some("deploy"),
+            CredentialType::USERNAME,
+        )
+        .unwrap();
+
+    assert_eq!(credential_type(&credentials), CredentialType::USERNAME);
+}
+
+#[test]
+fn test_ssh_tries_the_key_the_agent_and_default_keys_in_turn() {
+    let ssh_dir = tempdir().unwrap();
+    fs::write(ssh_dir.path().join("id_rsa"), "key").unwrap();
+    fs::write(ssh_dir.path().join("id_ed25519"), "key").unwrap();
+    let explicit_key = ssh_dir.path().join("deploy_key");
+    let mut authenticator = Authenticator::new(
+        Some("token"),
+        Some(explicit_key.clone()),
+        Some(ssh_dir.path()),
+        None,
+    );
+    let url = "git@github.com:org/repo.git";
+
+    for _ in 0..4 {
+        let credentials = authenticator
+            .credentials(url, Some("git"), CredentialType::SSH_KEY)
+            .unwrap();
+        assert_eq!(credential_type(&credentials), CredentialType::SSH_KEY);
+    }
+    let error = authenticator
+        .credentials(url, Some("git"), CredentialType::SSH_KEY)
+        .err()
+        .unwrap();
+
+    assert_eq!(error.code(), ErrorCode::Auth);
+    assert_eq!(error.class(), ErrorClass::Ssh);
+    assert_eq!(
+        error.message(),
+        format!(
+            "Authentication failed for '{url}' after trying the SSH key '{}', the SSH agent, the SSH key '{}' and the SSH key '{}'. Add a key to the SSH agent or pass one with --ssh-key.",
+            explicit_key.display(),
+            ssh_dir.path().join("id_ed25519").display(),
+            ssh_dir.path().join("id_rsa").display(),
+        )
+    );
+}
+
+#[test]
+fn test_https_tries_the_token_then_the_credential_helpers() {
+    let url = "https://github.com/org/private.git";
+    let mut authenticator = Authenticator::new(
+        Some("secret"),
+        None,
+        None,
+        Some(Config::new().unwrap()),
+    );
+
+    let credentials = authenticator
+        .credentials(url, None, CredentialType::USER_PASS_PLAINTEXT)
+        .unwrap();
+    assert_eq!(
+        credential_type(&credentials),
+        CredentialType::USER_PASS_PLAINTEXT
+    );
+    let error = authenticator
+        .credentials(url, None, CredentialType::USER_PASS_PLAINTEXT)
+        .err()
+        .unwrap();
+
+    assert_eq!(error.code(), ErrorCode::Auth);
+    assert_eq!(error.class(), ErrorClass::Http);
+    assert_eq!(
+        error.message(),
+        format!(
+            "Authentication failed for '{url}' after trying the token and git's credential helpers. Pass a token with --token or configure a git credential helper."
+        )
+    );
+}
+
+#[test]
+fn test_https_without_a_token_only_tries_the_credential_helpers() {
+    let url = "https://gitlab.com/group/private.git";
+    let mut authenticator =
+        Authenticator::new(None, None, None, Some(Config::new().unwrap()));
+
+    let error = authenticator
+        .credentials(url, None, CredentialType::USER_PASS_PLAINTEXT)
+        .err()
+        .unwrap();
+
+    assert_eq!(
+        error.message(),
+        format!(
+            "Authentication failed for '{url}' after trying git's credential helpers. Pass a token with --token or configure a git credential helper."
+        )
+    );
+}
+
+#[test]
+fn test_token_precedence() {
+    let tokens = BTreeMap::from([(
+        "gitlab.com".to_string(),
+        "configured-gitlab".to_string(),
+    )]);
+    let env = |name: &str| match name {
+        "GITHUB_TOKEN" => Some("github-env".to_string()),
+        "GH_TOKEN" => Some("gh-env".to_string()),
+        _ => None,
+    };
+
+    assert_eq!(
+        resolve_token(Some("cli"), Some("gitlab.com"), &tokens, None, env)
+            .as_deref(),
+        Some("cli")
+    );
+    assert_eq!(
+        resolve_token(None, Some("gitlab.com"), &tokens, None, env).as_deref(),
+        Some("configured-gitlab")
+    );
+    assert_eq!(
+        resolve_token(None, Some("github.com"), &tokens, Some("legacy"), env)
+            .as_deref(),
+        Some("github-env")
+    );
+    assert_eq!(
+        resolve_token(
+            None,
+            Some("github.com"),
+            &tokens,
+            Some("legacy"),
+            |_| { None }
+        )
+        .as_deref(),
+        Some("legacy")
+    );
+    assert_eq!(
+        resolve_token(None, None, &tokens, Some("legacy"), |_| {
+            Some("env".to_string())
+        }),
+        None
+    );
+}
+
+#[test]
+fn test_per_host_token_beats_the_environment() {
+    let tokens = BTreeMap::from([
+        ("github.com".to_string(), "configured-github".to_string()),
+        ("gitlab.com".to_string(), "configured-gitlab".to_string()),
+    ]);
+    let env = |name: &str| match name {
+        "BUNDLEREPO_TOKEN" => Some("bundlerepo-env".to_string()),
+        "GITHUB_TOKEN" => Some("github-env".to_string()),
+        _ => None,
+    };
+
+    assert_eq!(
+        resolve_token(None, Some("github.com"), &tokens, None, env).as_deref(),
+        Some("configured-github")
+    );
+    assert_eq!(
+        resolve_token(None, Some("gitlab.com"), &tokens, None, env).as_deref(),
+        Some("configured-gitlab")
+    );
+    assert_eq!(
+        resolve_token(None, Some("codeberg.org"), &tokens, None, env)
+            .as_deref(),
+        Some("bundlerepo-env")
+    );
+}
+
+#[test]
+fn test_github_tokens_are_not_sent_to_other_forges() {
+    let tokens = BTreeMap::new();
+    let env = |name: &str| match name {
+        "GITHUB_TOKEN" => Some("github-env".to_string()),
+        "GH_TOKEN" => Some("gh-env".to_string()),
+        _ => None,
+    };
+
+    for host in ["gitlab.com", "bitbucket.org", "codeberg.org", "git.corp"] {
+        assert_eq!(
+            resolve_token(None, Some(host), &tokens, Some("legacy"), env),
+            None
+        );
+    }
+}
+
+#[test]
+fn test_bundlerepo_token_applies_to_every_host() {
+    let tokens = BTreeMap::new();
+    let env = |name: &str| match name {
+        "BUNDLEREPO_TOKEN" => Some("bundlerepo-env".to_string()),
+        "GITHUB_TOKEN" => Some("github-env".to_string()),
+        _ => None,
+    };
+
+    for host in ["github.com", "gitlab.com"] {
+        assert_eq!(
+            resolve_token(None, Some(host), &tokens, None, env).as_deref(),
+            Some("bundlerepo-env")
+        );
+    }
+    assert_eq!(
+        resolve_token(None, Some("github.com"), &tokens, None, |name| {
+            (name == "GH_TOKEN").then(|| "gh-env".to_string())
+        })
+        .as_deref(),
+        Some("gh-env")
+    );
+    assert_eq!(
+        resolve_token(None, Some("github.com"), &tokens, None, |_| {
+            Some(String::new())
+        }),
+        None
+    );
+}
+
+#[test]
+fn test_token_file_is_trimmed() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("token");
+    fs::write(&path, "  secret\n").unwrap();
+    assert_eq!(read_token_file(path.to_str().unwrap()).unwrap(), "secret");
+
+    fs::write(&path, "\n").unwrap();
+    let error = read_token_file(path.to_str().unwrap()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!("token file '{}' is empty", path.display())
+    );
+
+    let missing = dir.path().join("missing");
+    let error = read_token_file(missing.to_str().unwrap()).unwrap_err();
+    assert!(error.to_string().starts_with(&format!(
+        "cannot read token file '{}'",
+        missing.display()
+    )));
+}
@@ -0,0 +1,228 @@
+use super::*;
+use git2::Signature;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tempfile::tempdir;
+
+fn commit_file(repo: &Repository, path: &str, content: &str) -> git2::Oid {
+    let workdir = repo.workdir().unwrap();
+    fs::write(workdir.join(path), content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(path)).unwrap();
+    index.write().unwrap();
+    let parents = repo
+        .head()
+        .ok()
+        .map(|head| head.peel_to_commit().unwrap())
+        .into_iter()
+        .collect::<Vec<_>>();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("Test", "test@example.com").unwrap();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "test",
+        &tree,
+        &parents.iter().collect::<Vec<_>>(),
+    )
+    .unwrap()
+}
+
+fn head_commit(repository: &Repository) -> git2::Oid {
+    repository.head().unwrap().peel_to_commit().unwrap().id()
+}
+
+#[test]
+fn test_cache_root_comes_from_the_configuration() {
+    let params = Params {
+        cache_dir: Some("/srv/cache".to_string()),
+        ..Params::default()
+    };
+    assert_eq!(cache_root(&params).unwrap(), Path::new("/srv/cache"));
+
+    if let Some(home) = home_dir() {
+        assert_eq!(
+            cache_root(&Params::default()).unwrap(),
+            home.join(".cache/bundlerepo/repos")
+        );
+        let params = Params {
+            cache_dir: Some("~/clones".to_string()),
+            ..Params::default()
+        };
+        assert_eq!(cache_root(&params).unwrap(), home.join("clones"));
+    }
+}
+
+#[test]
+fn test_cache_path_follows_host_and_repository_path() {
+    let root = Path::new("/cache");
+
+    assert_eq!(
+        cache_path(root, "https://github.com/owner/repo.git").unwrap(),
+        Path::new("/cache/github.com/owner/repo")
+    );
+    assert_eq!(
+        cache_path(root, "https://gitlab.com/group/subgroup/repo").unwrap(),
+        Path::new("/cache/gitlab.com/group/subgroup/repo")
+    );
+    assert_eq!(
+        cache_path(root, "file:///srv/git/origin.git").unwrap(),
+        Path::new("/cache/localhost/srv/git/origin")
+    );
+    assert_eq!(cache_path(root, "https://github.com/"), None);
+    assert_eq!(cache_path(root, "owner/repo"), None);
+}
+
+#[test]
+fn test_fetch_into_cache_creates_and_updates_the_clone() {
+    let source_dir = tempdir().unwrap();
+    let source = Repository::init(source_dir.path()).unwrap();
+    commit_file(&source, "lib.rs", "pub fn v1() {}\n");
+    let url = Url::from_file_path(source_dir.path()).unwrap();
+    let cache_dir = tempdir().unwrap();
+    let path = cache_path(cache_dir.path(), url.as_str()).unwrap();
+
+    let cached = fetch_into_cache(
+        &path,
+        url.as_str(),
+        FetchTarget::DefaultBranch,
+        false,
+        &mut FetchOptions::new(),
+    )
+    .unwrap();
+    assert!(cached.is_bare());
+    assert_eq!(head_commit(&cached), head_commit(&source));
+
+    let second = commit_file(&source, "lib.rs", "pub fn v2() {}\n");
+    let cached = fetch_into_cache(
+        &path,
+        url.as_str(),
+        FetchTarget::DefaultBranch,
+        false,
+        &mut FetchOptions::new(),
+    )
+    .unwrap();
+    assert_eq!(head_commit(&cached), second);
+    assert_eq!(head_commit(&open_cached_clone(&path).unwrap()), second);
+}
+
+#[test]
+fn test_fetch_into_cache_fetches_only_the_target() {
+    let source_dir = tempdir().unwrap();
+    let source = Repository::init(source_dir.path()).unwrap();
+    let first = commit_file(&source, "lib.rs", "pub fn v1() {}\n");
+    source
+        .branch("stable", &source.find_commit(first).unwrap(), false)
+        .unwrap();
+    source
+        .tag_lightweight(
+            "v1.0.0",
+            &source.find_object(first, None).unwrap(),
+            false,
+        )
+        .unwrap();
+    let second = commit_file(&source, "lib.rs", "pub fn v2() {}\n");
+    let default_branch = source.head().unwrap().name().unwrap().to_string();
+    let url = Url::from_file_path(source_dir.path()).unwrap();
+    let fetch = |target| {
+        let cache_dir = tempdir().unwrap();
+        let path = cache_path(cache_dir.path(), url.as_str()).unwrap();
+        let cached = fetch_into_cache(
+            &path,
+            url.as_str(),
+            target,
+            false,
+            &mut FetchOptions::new(),
+        )
+        .unwrap();
+        let mut names = cached
+            .references()
+            .unwrap()
+            .map(|reference| reference.unwrap().name().unwrap().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        (names, cached.head().ok().and_then(|head| head.target()))
+    };
+
+    assert_eq!(
+        fetch(FetchTarget::DefaultBranch),
+        (vec![default_branch.clone()], Some(second))
+    );
+    assert_eq!(
+        fetch(FetchTarget::Branch("stable")).0,
+        vec!["refs/heads/stable"]
+    );
+    assert_eq!(
+        fetch(FetchTarget::Ref("v1.0.0")).0,
+        vec!["refs/tags/v1.0.0"]
+    );
+    let mut everything = vec![default_branch, "refs/heads/stable".to_string()];
+    everything.push("refs/tags/v1.0.0".to_string());
+    everything.sort();
+    let commit_id = first.to_string();
+    assert_eq!(fetch(FetchTarget::Ref(&commit_id)).0, everything);
+    assert_eq!(fetch(FetchTarget::Everything), (everything, Some(second)));
+}
+
+#[test]
+fn test_failed_first_fetch_leaves_no_clone() {
+    let cache_dir = tempdir().unwrap();
+    let missing = cache_dir.path().join("missing");
+    let url = Url::from_file_path(&missing).unwrap();
+    let path = cache_path(cache_dir.path(), url.as_str()).unwrap();
+
+    assert!(
+        fetch_into_cache(
+            &path,
+            url.as_str(),
+            FetchTarget::DefaultBranch,
+            false,
+            &mut FetchOptions::new(),
+        )
+        .is_err()
+    );
+    assert!(!path.exists());
+    assert!(open_cached_clone(&path).is_err());
+}
+
+#[test]
+fn test_cached_repositories_are_listed_and_pruned() {
+    let source_dir = tempdir().unwrap();
+    let source = Repository::init(source_dir.path()).unwrap();
+    commit_file(&source, "lib.rs", "pub fn lib() {}\n");
+    let url = Url::from_file_path(source_dir.path()).unwrap();
+    let cache_dir = tempdir().unwrap();
+    let root = cache_dir.path();
+    let path = cache_path(root, url.as_str()).unwrap();
+    fetch_into_cache(
+        &path,
+        url.as_str(),
+        FetchTarget::DefaultBranch,
+        false,
+        &mut FetchOptions::new(),
+    )
+    .unwrap();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    let repositories = cached_repositories(root).unwrap();
+    assert_eq!(repositories.len(), 1);
+    let name = path
+        .strip_prefix(root)
+        .unwrap()
+        .to_string_lossy()
+        .replace('\\', "/");
+    assert_eq!(repositories[0].name, name);
+    assert!(repositories[0].size > 0);
+    assert!(repositories[0].last_used.is_some());
+
+    assert!(prune_cache(root, Some(now - 3600)).unwrap().is_empty());
+    let removed = prune_cache(root, None).unwrap();
+    assert_eq!(removed.len(), 1);
+    assert!(!path.exists());
+    assert!(!root.join("localhost").exists());
+    assert!(root.is_dir());
+    assert!(cached_repositories(root).unwrap().is_empty());
+}
@@ -0,0 +1,352 @@
+use super::*;
+use git2::{Oid, Signature};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+const RENAMED_CONTENT: &str =
+    "pub fn parse() {}\npub fn render() {}\npub fn write() {}\n";
+
+fn commit_changes(
+    repo: &Repository,
+    files: &[(&str, &str)],
+    removed: &[&str],
+) -> Oid {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+    for (path, content) in files {
+        let full_path = workdir.join(path);
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        fs::write(full_path, content).unwrap();
+        index.add_path(Path::new(path)).unwrap();
+    }
+    for path in removed {
+        fs::remove_file(workdir.join(path)).unwrap();
+        index.remove_path(Path::new(path)).unwrap();
+    }
+    index.write().unwrap();
+    let parents = repo
+        .head()
+        .ok()
+        .map(|head| head.peel_to_commit().unwrap())
+        .into_iter()
+        .collect::<Vec<_>>();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("Test", "test@example.com").unwrap();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "test",
+        &tree,
+        &parents.iter().collect::<Vec<_>>(),
+    )
+    .unwrap()
+}
+
+fn repository_with_changes(path: &Path) -> Repository {
+    let repo = Repository::init(path).unwrap();
+    let base = commit_changes(
+        &repo,
+        &[
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/old.rs", "pub fn old() {}\n"),
+            ("src/parser.rs", RENAMED_CONTENT),
+        ],
+        &[],
+    );
+    repo.tag_lightweight(
+        "base",
+        &repo.find_object(base, None).unwrap(),
+        false,
+    )
+    .unwrap();
+    commit_changes(
+        &repo,
+        &[
+            ("src/main.rs", "fn main() { run(); }\n"),
+            ("src/syntax.rs", RENAMED_CONTENT),
+            ("src/new.rs", "pub fn new() {}\n"),
+        ],
+        &["src/old.rs", "src/parser.rs"],
+    );
+    repo
+}
+
+#[test]
+fn test_parse_diff_range_reads_both_forms() {
+    assert_eq!(
+        parse_diff_range("v1.0..v2.0").unwrap(),
+        DiffRange {
+            base: "v1.0".to_string(),
+            head: "v2.0".to_string(),
+            merge_base: false,
+        }
+    );
+    assert_eq!(
+        parse_diff_range("main...feature").unwrap(),
+        DiffRange {
+            base: "main".to_string(),
+            head: "feature".to_string(),
+            merge_base: true,
+        }
+    );
+    assert_eq!(
+        parse_diff_range("main").unwrap_err(),
+        "expected 'A..B' or 'A...B', not 'main'"
+    );
+    assert_eq!(
+        parse_diff_range("main..").unwrap_err(),
+        "both sides of 'main..' must name a ref"
+    );
+}
+
+#[test]
+fn test_changed_files_reports_each_kind_of_change() {
+    let temp_dir = tempdir().unwrap();
+    let repo = repository_with_changes(temp_dir.path());
+    let range = parse_diff_range("base..HEAD").unwrap();
+
+    let changes =
+        changed_files(&repo, &Comparison::Refs(range), false).unwrap();
+
+    let kinds = changes
+        .iter()
+        .map(|(path, change)| (path.as_str(), change.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            ("src/main.rs", ChangeKind::Modified),
+            ("src/new.rs", ChangeKind::Added),
+            ("src/old.rs", ChangeKind::Deleted),
+            ("src/syntax.rs", ChangeKind::Renamed),
+        ]
+    );
+    assert_eq!(
+        changes["src/syntax.rs"].previous_path.as_deref(),
+        Some("src/parser.rs")
+    );
+    assert!(changes.values().all(|change| change.patch.is_none()));
+}
+
+#[test]
+fn test_changed_files_includes_patches_when_asked() {
+    let temp_dir = tempdir().unwrap();
+    let repo = repository_with_changes(temp_dir.path());
+    let range = DiffRange {
+        base: "base".to_string(),
+        head: "HEAD".to_string(),
+        merge_base: true,
+    };
+
+    let changes =
+        changed_files(&repo, &Comparison::Refs(range), true).unwrap();
+
+    let patch = changes["src/main.rs"].patch.as_deref().unwrap();
+    assert!(patch.contains("-fn main() {}\n"));
+    assert!(patch.contains("+fn main() { run(); }\n"));
+    assert!(
+        changes["src/old.rs"]
+            .patch
+            .as_deref()
+            .unwrap()
+            .contains("-pub fn old() {}\n")
+    );
+}
+
+#[test]
+fn test_changed_files_rejects_an_unknown_ref() {
+    let temp_dir = tempdir().unwrap();
+    let repo = repository_with_changes(temp_dir.path());
+    let range = parse_diff_range("missing..HEAD").unwrap();
+
+    let error =
+        changed_files(&repo, &Comparison::Refs(range), false).unwrap_err();
+
+    assert_eq!(
+        error.message(),
+        "The ref 'missing' does not exist in the repository."
+    );
+}
+
+#[test]
+fn test_comparison_follows_the_flags() {
+    assert_eq!(comparison(&Params::default()), None);
+    assert_eq!(
+        comparison(&Params {
+            diff_base: Some("main".to_string()),
+            ..Params::default()
+        }),
+        Some(Comparison::Refs(DiffRange {
+            base: "main".to_string(),
+            head: "HEAD".to_string(),
+            merge_base: true,
+        }))
+    );
+    assert_eq!(
+        comparison(&Params {
+            staged: true,
+            ..Params::default()
+        }),
+        Some(Comparison::Local {
+            unstaged: false,
+            staged: true,
+        })
+    );
+}
+
+fn repository_with_local_edits(path: &Path) -> Repository {
+    let repo = repository_with_changes(path);
+    fs::write(path.join("src/main.rs"), "fn main() { run(); stop(); }\n")
+        .unwrap();
+    fs::write(path.join("src/staged.rs"), "pub fn staged() {}\n").unwrap();
+    fs::write(path.join("notes.txt"), "untracked\n").unwrap();
+    fs::remove_file(path.join("src/new.rs")).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("src/staged.rs")).unwrap();
+    index.write().unwrap();
+    repo
+}
+
+#[test]
+fn test_uncommitted_changes_are_compared_with_head() {
+    let temp_dir = tempdir().unwrap();
+    let repo = repository_with_local_edits(temp_dir.path());
+    let unstaged = Comparison::Local {
+        unstaged: true,
+        staged: false,
+    };
+
+    let changes = changed_files(&repo, &unstaged, true).unwrap();
+
+    let kinds = changes
+        .iter()
+        .map(|(path, change)| (path.as_str(), change.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            ("notes.txt", ChangeKind::Added),
+            ("src/main.rs", ChangeKind::Modified),
+            ("src/new.rs", ChangeKind::Deleted),
+        ]
+    );
+    let patch = changes["src/main.rs"].patch.as_deref().unwrap();
+    assert!(patch.contains("+fn main() { run(); stop(); }\n"));
+}
+
+#[test]
+fn test_staged_changes_are_read_from_the_index() {
+    let temp_dir = tempdir().unwrap();
+    let repo = repository_with_local_edits(temp_dir.path());
+    let staged = Comparison::Local {
+        unstaged: false,
+        staged: true,
+    };
+    let both = Comparison::Local {
+        unstaged: true,
+        staged: true,
+    };
+
+    let changes = changed_files(&repo, &staged, false).unwrap();
+    assert_eq!(changes.keys().collect::<Vec<_>>(), ["src/staged.rs"]);
+    assert_eq!(changes["src/staged.rs"].kind, ChangeKind::Added);
+
+    let changes = changed_files(&repo, &both, false).unwrap();
+    assert_eq!(
+        changes.keys().collect::<Vec<_>>(),
+        ["notes.txt", "src/main.rs", "src/new.rs", "src/staged.rs"]
+    );
+}
+
+#[test]
+fn test_changed_files_with_imports_selects_imported_files() {
+    let temp_dir = tempdir().unwrap();
+    let repo = repository_with_local_edits(temp_dir.path());
+    fs::write(
+        temp_dir.path().join("src/main.rs"),
+        "mod syntax;\nfn main() {}\n",
+    )
+    .unwrap();
+    let unstaged = Comparison::Local {
+        unstaged: true,
+        staged: false,
+    };
+    let changes = changed_files(&repo, &unstaged, false).unwrap();
+    let params = Params {
+        with_imports: true,
+        ..Params::default()
+    };
+
+    let selected =
+        ChangedFiles::new(temp_dir.path(), &params, changes).unwrap();
+
+    assert_eq!(
+        selected.imports.iter().collect::<Vec<_>>(),
+        ["src/syntax.rs"]
+    );
+    assert!(selected.contains("src/syntax.rs"));
+    assert!(selected.contains("src/main.rs"));
+    assert!(!selected.contains("src/staged.rs"));
+    let file_list = selected.retain(FileList {
+        files: vec![
+            "notes.txt".to_string(),
+            "src/main.rs".to_string(),
+            "src/staged.rs".to_string(),
+            "src/syntax.rs".to_string(),
+        ],
+        ..FileList::default()
+    });
+    assert_eq!(
+        file_list.files,
+        ["notes.txt", "src/main.rs", "src/syntax.rs"]
+    );
+}
+
+#[test]
+fn test_deleted_files_pass_the_same_filters_as_listed_files() {
+    let deleted = || FileChange {
+        kind: ChangeKind::Deleted,
+        previous_path: None,
+        patch: None,
+    };
+    let changes = [
+        "Cargo.lock",
+        "docs/guide.md",
+        "scripts/old.py",
+        "src/old.rs",
+        "src/vendor/old.rs",
+        "tests/old_test.rs",
+    ]
+    .into_iter()
+    .map(|path| (path.to_string(), deleted()))
+    .collect::<ChangeSet>();
+    let params = Params {
+        languages: Some(vec!["rust".to_string()]),
+        include_tests: false,
+        include_docs: false,
+        extend_exclude: Some(vec!["vendor".to_string()]),
+        ..Params::default()
+    };
+
+    let file_tree =
+        add_changes(FileTree::default(), &params, &[], changes).unwrap();
+
+    assert_eq!(file_tree.file_paths, ["src/old.rs"]);
+    assert_eq!(file_tree.changes.keys().collect::<Vec<_>>(), ["src/old.rs"]);
+    assert_eq!(file_tree.folder_node.subfolders["src"].deleted, ["old.rs"]);
+    assert!(!file_tree.folder_node.subfolders.contains_key("docs"));
+
+    let scoped = add_changes(
+        FileTree::default(),
+        &Params::default(),
+        &["scripts".to_string()],
+        ["scripts/old.py", "src/old.rs"]
+            .into_iter()
+            .map(|path| (path.to_string(), deleted()))
+            .collect(),
+    )
+    .unwrap();
+    assert_eq!(scoped.file_paths, ["scripts/old.py"]);
+}
@@ -177,12 +177,264 @@ fn test_token_option() {
     assert_eq!(args.token, Some("abc123".to_string()));
 }
 
+#[test]
+fn test_token_file_option() {
+    let args = Flags::parse_from([
+        "program",
+        "user/repo",
+        "--token-file",
+        "~/.config/bundlerepo/token",
+    ]);
+    assert_eq!(
+        args.token_file.as_deref(),
+        Some("~/.config/bundlerepo/token")
+    );
+    assert_eq!(args.token, None);
+
+    assert!(
+        Flags::try_parse_from([
+            "program",
+            "user/repo",
+            "--token",
+            "abc123",
+            "--token-file",
+            "-",
+        ])
+        .is_err()
+    );
+}
+
+#[test]
+fn test_no_bundlerepoignore_flag() {
+    let args = Flags::parse_from(["program"]);
+    assert!(!args.no_bundlerepoignore);
+
+    let args = Flags::parse_from(["program", "--no-bundlerepoignore"]);
+    assert!(args.no_bundlerepoignore);
+}
+
+#[test]
+fn test_git_state_flags() {
+    let args = Flags::parse_from(["program"]);
+    assert!(!args.tracked_only);
+    assert!(!args.include_untracked);
+    assert!(!args.include_ignored);
+
+    let args =
+        Flags::parse_from(["program", "--tracked-only", "--include-ignored"]);
+    assert!(args.tracked_only);
+    assert!(args.include_ignored);
+
+    let args = Flags::parse_from(["program", "--include-untracked"]);
+    assert!(args.include_untracked);
+
+    assert!(
+        Flags::try_parse_from([
+            "program",
+            "--tracked-only",
+            "--include-untracked"
+        ])
+        .is_err()
+    );
+}
+
+#[test]
+fn test_no_git_flag() {
+    let args = Flags::parse_from(["program"]);
+    assert!(!args.no_git);
+
+    let args = Flags::parse_from(["program", "--no-git"]);
+    assert!(args.no_git);
+
+    for conflicting in ["--tracked-only", "--include-ignored", "--from-root"] {
+        assert!(
+            Flags::try_parse_from(["program", "--no-git", conflicting])
+                .is_err()
+        );
+    }
+}
+
+#[test]
+fn test_repository_argument_and_from_root_flag() {
+    let args = Flags::parse_from(["program"]);
+    assert_eq!(args.repo, None);
+    assert!(!args.from_root);
+
+    let args =
+        Flags::parse_from(["program", "../other-service", "--from-root"]);
+    assert_eq!(args.repo.as_deref(), Some("../other-service"));
+    assert!(args.from_root);
+}
+
+#[test]
+fn test_ref_flag() {
+    let args = Flags::parse_from(["program"]);
+    assert_eq!(args.git_ref, None);
+
+    let args = Flags::parse_from(["program", "--ref", "v1.2.0"]);
+    assert_eq!(args.git_ref.as_deref(), Some("v1.2.0"));
+
+    for conflicting in [
+        ["--branch", "main"].as_slice(),
+        &["--no-git"],
+        &["--tracked-only"],
+    ] {
+        let mut arguments = vec!["program", "--ref", "v1.2.0"];
+        arguments.extend(conflicting);
+        assert!(Flags::try_parse_from(arguments).is_err());
+    }
+}
+
+#[test]
+fn test_dry_run_and_explain_flags() {
+    let args = Flags::parse_from(["program"]);
+    assert!(!args.dry_run);
+    assert_eq!(args.explain, None);
+
+    let args = Flags::parse_from([
+        "program",
+        "--dry-run",
+        "--explain",
+        "src/main.rs",
+    ]);
+    assert!(args.dry_run);
+    assert_eq!(args.explain.as_deref(), Some("src/main.rs"));
+}
+
 #[test]
 fn test_version_flag() {
     let args = Flags::parse_from(["program", "--version"]);
     assert!(args.version);
 }
 
+#[test]
+fn test_multiple_path_scopes() {
+    let args = Flags::parse_from(["program"]);
+    assert_eq!(args.paths, None);
+
+    let args = Flags::parse_from([
+        "program",
+        "--path",
+        "services/billing",
+        "--path",
+        "libs/common",
+    ]);
+    assert_eq!(
+        args.paths,
+        Some(vec![
+            "services/billing".to_string(),
+            "libs/common".to_string()
+        ])
+    );
+}
+
+#[test]
+fn test_files_from_conflicts_with_path() {
+    let args = Flags::parse_from(["program", "--files-from", "-"]);
+    assert_eq!(args.files_from, Some("-".to_string()));
+
+    assert!(
+        Flags::try_parse_from([
+            "program",
+            "--files-from",
+            "files.txt",
+            "--path",
+            "src",
+        ])
+        .is_err()
+    );
+}
+
+#[test]
+fn test_grep_flags_validate_pattern() {
+    let args = Flags::parse_from([
+        "program",
+        "--grep",
+        "Progress\\w+",
+        "--grep-context",
+        "3",
+    ]);
+    assert_eq!(args.grep, Some("Progress\\w+".to_string()));
+    assert_eq!(args.grep_context, Some(3));
+
+    assert!(Flags::try_parse_from(["program", "--grep", "(oops"]).is_err());
+    assert!(
+        Flags::try_parse_from(["program", "--grep-context", "3"]).is_err()
+    );
+}
+
+#[test]
+fn test_language_and_category_flags() {
+    let args = Flags::parse_from(["program"]);
+    assert_eq!(args.languages, None);
+    assert!(!args.no_tests && !args.no_docs && !args.no_examples);
+
+    let args = Flags::parse_from([
+        "program",
+        "--lang",
+        "Rust,toml",
+        "--lang",
+        "python",
+        "--no-tests",
+        "--no-docs",
+        "--no-examples",
+    ]);
+    assert_eq!(
+        args.languages,
+        Some(vec![
+            "rust".to_string(),
+            "toml".to_string(),
+            "python".to_string()
+        ])
+    );
+    assert!(args.no_tests && args.no_docs && args.no_examples);
+
+    assert!(Flags::try_parse_from(["program", "--lang", "klingon"]).is_err());
+}
+
+#[test]
+fn test_limit_flags_parse_units() {
+    let args = Flags::parse_from(["program"]);
+    assert_eq!(args.max_file_size, None);
+    assert_eq!(args.max_file_lines, None);
+    assert_eq!(args.modified_within, None);
+
+    let args = Flags::parse_from([
+        "program",
+        "--max-file-size",
+        "10MB",
+        "--max-file-lines",
+        "2000",
+        "--modified-within",
+        "30d",
+    ]);
+    assert_eq!(args.max_file_size, Some(10 * 1024 * 1024));
+    assert_eq!(args.max_file_lines, Some(2000));
+    assert_eq!(args.modified_within, Some(30 * 24 * 60 * 60));
+
+    assert!(
+        Flags::try_parse_from(["program", "--modified-within", "soon"])
+            .is_err()
+    );
+}
+
+#[test]
+fn test_truncation_flags_parse_counts() {
+    let args = Flags::parse_from([
+        "program",
+        "--truncate-lines",
+        "200",
+        "--truncate-tokens",
+        "4000",
+    ]);
+    assert_eq!(args.truncate_lines, Some(200));
+    assert_eq!(args.truncate_tokens, Some(4000));
+
+    assert!(
+        Flags::try_parse_from(["program", "--truncate-tokens", "0"]).is_err()
+    );
+}
+
 #[test]
 fn test_extend_exclude_patterns() {
     let args = Flags::parse_from([
@@ -320,3 +572,150 @@ fn test_utf8_flag_values() {
     let result = Flags::try_parse_from(["program", "-u", "-U"]);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_diff_flags() {
+    let args = Flags::parse_from(["program", "--diff-base", "main"]);
+    assert_eq!(args.diff_base.as_deref(), Some("main"));
+    assert!(!args.diff_patch);
+
+    let args =
+        Flags::parse_from(["program", "--diff", "v1.0..v2.0", "--diff-patch"]);
+    assert_eq!(args.diff.as_deref(), Some("v1.0..v2.0"));
+    assert!(args.diff_patch);
+
+    for invalid in [
+        ["--diff", "main"].as_slice(),
+        &["--diff-patch"],
+        &["--diff-base", "main", "--diff", "main..HEAD"],
+        &["--diff-base", "main", "--ref", "v1.2.0"],
+        &["--diff", "main..HEAD", "--no-git"],
+        &["--diff", "main..HEAD", "--staged"],
+        &["--uncommitted", "--ref", "v1.2.0"],
+        &["--patch-only"],
+        &["--with-imports"],
+        &["--staged", "--with-imports", "--follow", "src/main.rs"],
+    ] {
+        let mut arguments = vec!["program"];
+        arguments.extend(invalid);
+        assert!(Flags::try_parse_from(arguments).is_err());
+    }
+}
+
+#[test]
+fn test_local_change_flags() {
+    let args = Flags::parse_from(["program", "--uncommitted", "--staged"]);
+    assert!(args.uncommitted);
+    assert!(args.staged);
+
+    let args = Flags::parse_from([
+        "program",
+        "--staged",
+        "--patch-only",
+        "--with-imports",
+    ]);
+    assert!(args.staged);
+    assert!(args.patch_only);
+    assert!(args.with_imports);
+}
+
+#[test]
+fn test_history_flags() {
+    let args = Flags::parse_from(["program"]);
+    assert_eq!(args.history, None);
+    assert!(!args.last_commit);
+
+    let args =
+        Flags::parse_from(["program", "--history", "20", "--last-commit"]);
+    assert_eq!(args.history, Some(20));
+    assert!(args.last_commit);
+
+    assert!(Flags::try_parse_from(["program", "--history", "-1"]).is_err());
+    assert!(
+        Flags::try_parse_from(["program", "--history", "5", "--no-git"])
+            .is_err()
+    );
+}
+
+#[test]
+fn test_hotspot_flags() {
+    let args = Flags::parse_from([
+        "program",
+        "--hotspots",
+        "--hotspots-section",
+        "--order",
+        "hotspots",
+    ]);
+    assert!(args.hotspots);
+    assert!(args.hotspots_section);
+    assert_eq!(args.order.as_deref(), Some("hotspots"));
+
+    assert!(Flags::try_parse_from(["program", "--order", "size"]).is_err());
+    assert!(
+        Flags::try_parse_from(["program", "--hotspots", "--no-git"]).is_err()
+    );
+}
+
+#[test]
+fn test_cache_flags_and_commands() {
+    let args = Flags::parse_from(["program", "owner/repo", "--offline"]);
+    assert!(args.offline);
+    assert!(!args.no_cache);
+    let args = Flags::parse_from(["program", "owner/repo", "--no-cache"]);
+    assert!(args.no_cache);
+    assert!(
+        Flags::try_parse_from([
+            "program",
+            "owner/repo",
+            "--offline",
+            "--no-cache"
+        ])
+        .is_err()
+    );
+    assert!(args.command.is_none());
+
+    let args = Flags::parse_from(["program", "cache", "list"]);
+    assert_eq!(args.repo, None);
+    assert!(matches!(
+        args.command,
+        Some(Command::Cache {
+            action: CacheAction::List
+        })
+    ));
+
+    let args =
+        Flags::parse_from(["program", "cache", "prune", "--older-than", "2w"]);
+    assert!(matches!(
+        args.command,
+        Some(Command::Cache {
+            action: CacheAction::Prune {
+                older_than: Some(1_209_600)
+            }
+        })
+    ));
+    assert!(
+        Flags::try_parse_from([
+            "program",
+            "cache",
+            "prune",
+            "--older-than",
+            "soon"
+        ])
+        .is_err()
+    );
+
+    let args = Flags::parse_from(["program", "./cache"]);
+    assert_eq!(args.repo.as_deref(), Some("./cache"));
+}
+
+#[test]
+fn test_ssh_key_flag() {
+    let args = Flags::parse_from([
+        "program",
+        "git@github.com:org/repo.git",
+        "--ssh-key",
+        "~/.ssh/deploy_key",
+    ]);
+    assert_eq!(args.repo.as_deref(), Some("git@github.com:org/repo.git"));
+    assert_eq!(args.ssh_key.as_deref(), Some("~/.ssh/deploy_key"));
+}
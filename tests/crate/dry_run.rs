@@ -0,0 +1,420 @@
+use super::*;
+use git2::Repository;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create_repository(files: &[(&str, &[u8])]) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    Repository::init(temp_dir.path()).unwrap();
+    for (path, content) in files {
+        let path = temp_dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    temp_dir
+}
+
+fn decision_for<'a>(candidates: &'a [Candidate], path: &str) -> &'a Decision {
+    &candidates
+        .iter()
+        .find(|candidate| candidate.path == path)
+        .unwrap_or_else(|| panic!("missing candidate {path}"))
+        .decision
+}
+
+#[test]
+fn test_dry_run_explains_every_exclusion_kind() {
+    let temp_dir = create_repository(&[
+        (".gitignore", b"# output\ntarget/\n"),
+        ("target/debug/app", b"compiled"),
+        ("src/main.rs", b"fn main() {}\n"),
+        ("src/.bundlerepoignore", b"*.snap\n"),
+        ("src/output.snap", b"snapshot"),
+        ("Cargo.lock", b"lock"),
+        ("notes.tmp", b"scratch"),
+        ("image.bin", &[0, 1, 2, 3]),
+    ]);
+    let params = Params {
+        extend_exclude: Some(vec!["notes.tmp".to_string()]),
+        ..Params::default()
+    };
+
+    let candidates = dry_run_candidates(temp_dir.path(), &params).unwrap();
+
+    assert_eq!(decision_for(&candidates, "src/main.rs"), &Decision::Include);
+    assert_eq!(
+        decision_for(&candidates, "image.bin"),
+        &Decision::Omit(BinaryReason::NullByte)
+    );
+    assert_eq!(
+        decision_for(&candidates, "target"),
+        &Decision::Exclude(Exclusion::IgnoreFile(IgnoreMatch {
+            source: ".gitignore".to_string(),
+            line: 2,
+            pattern: "target/".to_string(),
+            whitelist: false,
+        }))
+    );
+    assert!(
+        !candidates
+            .iter()
+            .any(|candidate| candidate.path.starts_with("target/"))
+    );
+    let Decision::Exclude(snapshot) =
+        decision_for(&candidates, "src/output.snap")
+    else {
+        panic!("snapshot should be excluded");
+    };
+    assert_eq!(
+        snapshot.to_string(),
+        "ignored by src/.bundlerepoignore:1: *.snap"
+    );
+    assert_eq!(
+        decision_for(&candidates, "Cargo.lock"),
+        &Decision::Exclude(Exclusion::Pattern(r"(?i)\.lock$".to_string()))
+    );
+    assert_eq!(
+        decision_for(&candidates, "notes.tmp"),
+        &Decision::Exclude(Exclusion::Pattern("notes.tmp".to_string()))
+    );
+    assert_eq!(
+        decision_for(&candidates, ".git"),
+        &Decision::Exclude(Exclusion::Pattern(r"(?i)\.git".to_string()))
+    );
+}
+
+#[test]
+fn test_dry_run_agrees_with_file_listing() {
+    let temp_dir = create_repository(&[
+        (".gitignore", b"*.log\n!keep.log\n"),
+        ("debug.log", b"log"),
+        ("keep.log", b"log"),
+        ("docs/guide.md", b"guide"),
+        ("LICENSE", b"licence"),
+    ]);
+    let params = Params::default();
+
+    let candidates = dry_run_candidates(temp_dir.path(), &params).unwrap();
+    let mut included = candidates
+        .iter()
+        .filter(|candidate| {
+            matches!(candidate.decision, Decision::Include | Decision::Omit(_))
+        })
+        .map(|candidate| candidate.path.clone())
+        .collect::<Vec<_>>();
+    included.sort();
+    let mut listed =
+        crate::filelist::list_files_in_repo(temp_dir.path(), &params)
+            .unwrap()
+            .files;
+    listed.sort();
+
+    assert_eq!(included, listed);
+}
+
+#[test]
+fn test_dry_run_from_nested_directory_agrees_with_file_listing() {
+    let temp_dir = create_repository(&[
+        (".gitignore", b"*.log\n"),
+        ("top.txt", b"top"),
+        ("sub/.gitignore", b"inner/\n"),
+        ("sub/keep.txt", b"keep"),
+        ("sub/debug.log", b"log"),
+        ("sub/inner/generated.txt", b"generated"),
+    ]);
+    let sub = temp_dir.path().join("sub");
+    let params = Params::default();
+
+    let candidates = dry_run_candidates(sub.as_path(), &params).unwrap();
+    assert!(matches!(
+        decision_for(&candidates, "debug.log"),
+        Decision::Exclude(exclusion)
+            if exclusion.to_string().contains("../.gitignore:1")
+    ));
+    let mut included = candidates
+        .iter()
+        .filter(|candidate| {
+            matches!(candidate.decision, Decision::Include | Decision::Omit(_))
+        })
+        .map(|candidate| candidate.path.clone())
+        .collect::<Vec<_>>();
+    included.sort();
+    let mut listed =
+        crate::filelist::list_files_in_repo(sub.as_path(), &params)
+            .unwrap()
+            .files;
+    listed.sort();
+
+    assert_eq!(included, listed);
+    assert_eq!(included, ["keep.txt"]);
+}
+
+#[test]
+fn test_explain_reports_ignored_parent_directory() {
+    let temp_dir = create_repository(&[
+        (".gitignore", b"/build\n"),
+        ("build/out/app.js", b"code"),
+    ]);
+
+    let candidate = explain_path(
+        temp_dir.path(),
+        "./build/out/app.js",
+        &Params::default(),
+    )
+    .unwrap();
+
+    assert_eq!(candidate.path, "build/out/app.js");
+    assert_eq!(
+        candidate.to_string(),
+        "exclude  build/out/app.js  (ignored by .gitignore:1: /build)"
+    );
+}
+
+#[test]
+fn test_explain_reports_included_and_binary_files() {
+    let temp_dir = create_repository(&[
+        ("src/lib.rs", b"pub fn lib() {}\n"),
+        ("logo.png", b"\x89PNG\r\n\x1a\nrest"),
+    ]);
+
+    let included =
+        explain_path(temp_dir.path(), "src/lib.rs", &Params::default())
+            .unwrap();
+    let binary =
+        explain_path(temp_dir.path(), "logo.png", &Params::default()).unwrap();
+
+    assert_eq!(included.to_string(), "include  src/lib.rs");
+    assert_eq!(
+        binary.to_string(),
+        "omit     logo.png  (content omitted: recognized image/png file signature)"
+    );
+}
+
+#[test]
+fn test_explain_rejects_missing_and_outside_paths() {
+    let temp_dir = create_repository(&[]);
+
+    let missing =
+        explain_path(temp_dir.path(), "missing.rs", &Params::default())
+            .unwrap_err();
+    let outside =
+        explain_path(temp_dir.path(), "../secret.txt", &Params::default())
+            .unwrap_err();
+
+    assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    assert_eq!(
+        missing.to_string(),
+        "'missing.rs' does not exist in the repository"
+    );
+    assert_eq!(outside.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_written_listing_ends_with_counts() {
+    let candidates = [
+        Candidate {
+            path: "src/main.rs".to_string(),
+            is_dir: false,
+            decision: Decision::Include,
+        },
+        Candidate {
+            path: "target".to_string(),
+            is_dir: true,
+            decision: Decision::Exclude(Exclusion::Pattern(
+                "target".to_string(),
+            )),
+        },
+    ];
+    let mut reporter = ProgressReporter::new(Vec::new(), Vec::new(), true);
+
+    write_candidates(&mut reporter, &candidates).unwrap();
+
+    let (normal, _) = reporter.into_parts();
+    assert_eq!(
+        String::from_utf8(normal).unwrap(),
+        "include  src/main.rs\n\
+         exclude  target/  (matches exclude pattern 'target')\n\
+         \n1 included, 0 omitted, 0 skipped, 1 excluded\n"
+    );
+}
+
+#[test]
+fn test_dry_run_reports_git_states() {
+    let temp_dir = create_repository(&[
+        (".gitignore", b"/build\n"),
+        ("src/lib.rs", b"pub fn lib() {}\n"),
+        ("scratch.txt", b"scratch"),
+        ("drafts/idea.md", b"idea"),
+        ("build/out.js", b"code"),
+    ]);
+    let repository = Repository::open(temp_dir.path()).unwrap();
+    let mut index = repository.index().unwrap();
+    index.add_path(Path::new(".gitignore")).unwrap();
+    index.add_path(Path::new("src/lib.rs")).unwrap();
+    index.write().unwrap();
+    let params = Params {
+        tracked_only: true,
+        ..Params::default()
+    };
+
+    let candidates = dry_run_candidates(temp_dir.path(), &params).unwrap();
+
+    assert_eq!(decision_for(&candidates, "src/lib.rs"), &Decision::Include);
+    assert_eq!(
+        decision_for(&candidates, "scratch.txt"),
+        &Decision::Exclude(Exclusion::Untracked)
+    );
+    assert_eq!(
+        decision_for(&candidates, "drafts"),
+        &Decision::Exclude(Exclusion::Untracked)
+    );
+    assert_eq!(
+        decision_for(&candidates, "build"),
+        &Decision::Exclude(Exclusion::IgnoreFile(IgnoreMatch {
+            source: ".gitignore".to_string(),
+            line: 1,
+            pattern: "/build".to_string(),
+            whitelist: false,
+        }))
+    );
+
+    let params = Params {
+        tracked_only: true,
+        include_ignored: true,
+        ..Params::default()
+    };
+    let explained =
+        explain_path(temp_dir.path(), "build/out.js", &params).unwrap();
+    assert_eq!(explained.decision, Decision::Include);
+}
+
+#[test]
+fn test_dry_run_reports_language_and_category_selection() {
+    let temp_dir = create_repository(&[
+        ("src/lib.rs", b"pub fn lib() {}\n"),
+        ("README.md", b"readme"),
+        ("tests/app.rs", b"#[test]\nfn app() {}\n"),
+    ]);
+    let params = Params {
+        languages: Some(vec!["rust".to_string()]),
+        include_tests: false,
+        ..Params::default()
+    };
+
+    let candidates = dry_run_candidates(temp_dir.path(), &params).unwrap();
+
+    assert_eq!(decision_for(&candidates, "src/lib.rs"), &Decision::Include);
+    assert_eq!(
+        decision_for(&candidates, "tests"),
+        &Decision::Exclude(Exclusion::Deselected(Deselection::Category(
+            crate::language::Category::Tests
+        )))
+    );
+    let explained = explain_path(temp_dir.path(), "README.md", &params)
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        explained,
+        "exclude  README.md  (language 'markdown' is not selected by --lang)"
+    );
+    let explained = explain_path(temp_dir.path(), "tests/app.rs", &params)
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        explained,
+        "exclude  tests/app.rs  (test files are excluded by --no-tests)"
+    );
+}
+
+#[test]
+fn test_dry_run_reports_skipped_files() {
+    let temp_dir = create_repository(&[
+        ("src/lib.rs", b"pub fn lib() {}\n"),
+        ("fixtures/big.json", &[b'x'; 4096]),
+    ]);
+    let params = Params {
+        max_file_size: Some(1024),
+        ..Params::default()
+    };
+
+    let candidates = dry_run_candidates(temp_dir.path(), &params).unwrap();
+
+    let skipped = candidates
+        .iter()
+        .find(|candidate| candidate.path == "fixtures/big.json")
+        .unwrap();
+    assert_eq!(
+        skipped.to_string(),
+        "skip     fixtures/big.json  (skipped: too-large, larger than --max-file-size)"
+    );
+}
+
+#[test]
+fn test_dry_run_lists_files_from_entries() {
+    let temp_dir = create_repository(&[
+        ("src/lib.rs", b"pub fn lib() {}\n"),
+        ("src/main.rs", b"fn main() {}\n"),
+    ]);
+    let params = Params {
+        files_from: Some(vec![
+            "src/main.rs".to_string(),
+            "gone.rs".to_string(),
+        ]),
+        ..Params::default()
+    };
+
+    let candidates = dry_run_candidates(temp_dir.path(), &params).unwrap();
+
+    assert_eq!(
+        candidates
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        [
+            "error    gone.rs  (does not exist in the repository)",
+            "include  src/main.rs",
+        ]
+    );
+}
+
+#[test]
+fn test_dry_run_excludes_files_not_matching_grep() {
+    let temp_dir = create_repository(&[
+        ("src/main.rs", b"let reporter = ProgressReporter::new();\n"),
+        ("src/lib.rs", b"pub fn lib() {}\n"),
+    ]);
+    let params = Params {
+        grep: Some("ProgressReporter".to_string()),
+        ..Params::default()
+    };
+
+    let candidates = dry_run_candidates(temp_dir.path(), &params).unwrap();
+
+    assert_eq!(decision_for(&candidates, "src/main.rs"), &Decision::Include);
+    assert_eq!(
+        decision_for(&candidates, "src/lib.rs"),
+        &Decision::Exclude(Exclusion::Unmatched)
+    );
+}
+
+#[test]
+fn test_dry_run_excludes_files_not_reached_by_follow() {
+    let temp_dir = create_repository(&[
+        ("src/main.rs", b"mod cli;\n"),
+        ("src/cli.rs", b"pub fn run() {}\n"),
+        ("src/unused.rs", b"pub fn unused() {}\n"),
+    ]);
+    let params = Params {
+        follow: Some(vec!["src/main.rs".to_string()]),
+        ..Params::default()
+    };
+
+    let candidates = dry_run_candidates(temp_dir.path(), &params).unwrap();
+
+    assert_eq!(decision_for(&candidates, "src/cli.rs"), &Decision::Include);
+    assert_eq!(
+        decision_for(&candidates, "src/unused.rs"),
+        &Decision::Exclude(Exclusion::Unreachable)
+    );
+}
@@ -18,7 +18,9 @@ fn test_list_files_basic() {
     let test_files = ["file1.txt", "src/file2.rs", "src/nested/file3.rs"];
     create_test_files(&temp_dir, &test_files);
 
-    let files = list_files_in_repo(temp_dir.path(), None, None);
+    let files = list_files_in_repo(temp_dir.path(), &Params::default())
+        .unwrap()
+        .files;
 
     assert_eq!(files.len(), 3);
     assert!(files.contains(&"file1.txt".to_string()));
@@ -32,7 +34,9 @@ fn test_list_files_with_exclude() {
     let test_files = ["file1.txt", "src/file2.rs", "test.lock", ".gitignore"];
     create_test_files(&temp_dir, &test_files);
 
-    let files = list_files_in_repo(temp_dir.path(), None, None);
+    let files = list_files_in_repo(temp_dir.path(), &Params::default())
+        .unwrap()
+        .files;
 
     assert_eq!(files.len(), 2);
     assert!(files.contains(&"file1.txt".to_string()));
@@ -65,9 +69,12 @@ fn test_custom_exclude_replaces_defaults_through_file_listing() {
         &temp_dir,
         &["ordinary.txt", "custom.tmp", "Cargo.lock"],
     );
-    let exclude = vec!["custom.tmp".to_string()];
+    let params = Params {
+        exclude: Some(vec!["custom.tmp".to_string()]),
+        ..Params::default()
+    };
 
-    let files = list_files_in_repo(temp_dir.path(), None, Some(&exclude));
+    let files = list_files_in_repo(temp_dir.path(), &params).unwrap().files;
 
     assert_eq!(files.len(), 2);
     assert!(files.contains(&"ordinary.txt".to_string()));
@@ -82,10 +89,12 @@ fn test_extend_exclude_augments_defaults_through_file_listing() {
         &temp_dir,
         &["ordinary.txt", "custom.tmp", "Cargo.lock"],
     );
-    let extend_exclude = vec!["custom.tmp".to_string()];
+    let params = Params {
+        extend_exclude: Some(vec!["custom.tmp".to_string()]),
+        ..Params::default()
+    };
 
-    let files =
-        list_files_in_repo(temp_dir.path(), Some(&extend_exclude), None);
+    let files = list_files_in_repo(temp_dir.path(), &params).unwrap().files;
 
     assert_eq!(files, vec!["ordinary.txt"]);
 }
@@ -97,8 +106,11 @@ fn test_windows_exclude_accepts_backslash_paths() {
     let test_files = ["file1.txt", "src/file2.rs"];
     create_test_files(&temp_dir, &test_files);
 
-    let exclude = vec![r"src\file2.rs".to_string()];
-    let files = list_files_in_repo(temp_dir.path(), None, Some(&exclude));
+    let params = Params {
+        exclude: Some(vec![r"src\file2.rs".to_string()]),
+        ..Params::default()
+    };
+    let files = list_files_in_repo(temp_dir.path(), &params).unwrap().files;
 
     assert_eq!(files, vec!["file1.txt"]);
 }
@@ -110,6 +122,7 @@ fn test_default_exclude_patterns() {
         "file1.txt",
         ".git/config",
         ".gitignore",
+        ".bundlerepoignore",
         "renovate.json",
         "requirements.txt",
         "Cargo.lock",
@@ -124,7 +137,9 @@ fn test_default_exclude_patterns() {
     ];
     create_test_files(&temp_dir, &test_files);
 
-    let files = list_files_in_repo(temp_dir.path(), None, None);
+    let files = list_files_in_repo(temp_dir.path(), &Params::default())
+        .unwrap()
+        .files;
 
     // Only file1.txt should remain, all others should be excluded by default patterns
     assert_eq!(files.len(), 1);
@@ -148,6 +163,88 @@ fn test_default_exclude_patterns() {
     }
 }
 
+#[test]
+fn test_bundlerepoignore_files_apply_at_any_level() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(
+        &temp_dir,
+        &[
+            "keep.txt",
+            "dump.sql",
+            "src/main.rs",
+            "src/generated/schema.rs",
+            "src/nested/fixture.json",
+            "src/nested/keep.json",
+        ],
+    );
+    fs::write(temp_dir.path().join(".bundlerepoignore"), "*.sql\n").unwrap();
+    fs::write(
+        temp_dir.path().join("src/.bundlerepoignore"),
+        "generated/\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("src/nested/.bundlerepoignore"),
+        "fixture.json\n",
+    )
+    .unwrap();
+
+    let mut files = list_files_in_repo(temp_dir.path(), &Params::default())
+        .unwrap()
+        .files;
+    files.sort();
+
+    assert_eq!(
+        files,
+        vec!["keep.txt", "src/main.rs", "src/nested/keep.json"]
+    );
+}
+
+#[test]
+fn test_file_list_records_whether_a_bundlerepoignore_file_applied() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(&temp_dir, &["keep.txt", "src/main.rs"]);
+    let applied = |params: &Params| {
+        list_files_in_repo(temp_dir.path(), params)
+            .unwrap()
+            .bundlerepoignore
+    };
+    assert!(!applied(&Params::default()));
+
+    fs::write(temp_dir.path().join("src/.bundlerepoignore"), "*.sql\n")
+        .unwrap();
+    assert!(applied(&Params::default()));
+    assert!(applied(&Params {
+        paths: Some(vec!["src/main.rs".to_string()]),
+        ..Params::default()
+    }));
+    assert!(!applied(&Params {
+        paths: Some(vec!["keep.txt".to_string()]),
+        ..Params::default()
+    }));
+    assert!(!applied(&Params {
+        bundlerepoignore: false,
+        ..Params::default()
+    }));
+}
+
+#[test]
+fn test_bundlerepoignore_can_be_disabled() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(&temp_dir, &["keep.txt", "dump.sql"]);
+    fs::write(temp_dir.path().join(".bundlerepoignore"), "*.sql\n").unwrap();
+    let params = Params {
+        bundlerepoignore: false,
+        ..Params::default()
+    };
+
+    let mut files =
+        list_files_in_repo(temp_dir.path(), &params).unwrap().files;
+    files.sort();
+
+    assert_eq!(files, vec!["dump.sql", "keep.txt"]);
+}
+
 #[test]
 fn test_group_files_by_directory() {
     let files = vec![
@@ -157,7 +254,10 @@ fn test_group_files_by_directory() {
         "src/nested/deep/file4.rs".to_string(),
     ];
 
-    let file_tree = group_files_by_directory(files);
+    let file_tree = group_files_by_directory(FileList {
+        files,
+        ..FileList::default()
+    });
 
     // Test root level
     assert_eq!(file_tree.folder_node.files, vec!["file1.txt"]);
@@ -189,3 +289,320 @@ fn test_group_files_by_directory() {
             .contains(&"src/nested/deep/file4.rs".to_string())
     );
 }
+
+#[test]
+fn test_repository_relative_path_normalizes_inputs() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir(temp_dir.path().join("src")).unwrap();
+    let absolute = temp_dir.path().join("src/main.rs");
+
+    assert_eq!(
+        repository_relative_path(temp_dir.path(), "./src/../src/main.rs")
+            .unwrap(),
+        "src/main.rs"
+    );
+    assert_eq!(
+        repository_relative_path(temp_dir.path(), &absolute.to_string_lossy())
+            .unwrap(),
+        "src/main.rs"
+    );
+    assert_eq!(
+        repository_relative_path(temp_dir.path(), "../outside.txt")
+            .unwrap_err()
+            .to_string(),
+        "'../outside.txt' is outside the repository"
+    );
+    assert!(repository_relative_path(temp_dir.path(), ".").is_err());
+}
+
+#[test]
+fn test_git_state_listing_applies_exclusions_and_bundlerepoignore() {
+    let temp_dir = TempDir::new().unwrap();
+    let repository = git2::Repository::init(temp_dir.path()).unwrap();
+    for (path, content) in [
+        (".gitignore", "*.log\n"),
+        (".bundlerepoignore", "*.sql\n"),
+        ("src/main.rs", "fn main() {}\n"),
+        ("Cargo.lock", "lock"),
+        ("schema.sql", "create"),
+        ("forced.log", "tracked despite .gitignore"),
+        ("scratch.txt", "untracked"),
+        ("debug.log", "ignored"),
+    ] {
+        let path = temp_dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    let mut index = repository.index().unwrap();
+    for path in [
+        ".gitignore",
+        ".bundlerepoignore",
+        "src/main.rs",
+        "Cargo.lock",
+        "schema.sql",
+        "forced.log",
+    ] {
+        index.add_path(Path::new(path)).unwrap();
+    }
+    index.write().unwrap();
+    let list = |tracked_only, include_ignored| {
+        let params = Params {
+            tracked_only,
+            include_ignored,
+            ..Params::default()
+        };
+        let mut files =
+            list_files_in_repo(temp_dir.path(), &params).unwrap().files;
+        files.sort();
+        files
+    };
+
+    assert_eq!(list(true, false), ["forced.log", "src/main.rs"]);
+    assert_eq!(
+        list(false, true),
+        ["debug.log", "forced.log", "scratch.txt", "src/main.rs"]
+    );
+    assert_eq!(list(true, true), ["debug.log", "forced.log", "src/main.rs"]);
+    let params = Params {
+        tracked_only: true,
+        ..Params::default()
+    };
+    assert!(
+        list_files_in_repo(temp_dir.path(), &params)
+            .unwrap()
+            .bundlerepoignore
+    );
+}
+
+#[test]
+fn test_git_state_listing_requires_a_repository() {
+    let temp_dir = TempDir::new().unwrap();
+    let params = Params {
+        tracked_only: true,
+        ..Params::default()
+    };
+
+    assert!(list_files_in_repo(temp_dir.path(), &params).is_err());
+}
+
+#[test]
+fn test_no_git_listing_honours_only_the_directory_git_ignores() {
+    let temp_dir = TempDir::new().unwrap();
+    git2::Repository::init(temp_dir.path()).unwrap();
+    create_test_files(
+        &temp_dir,
+        &["scratch/notes.txt", "scratch/debug.log", "scratch/main.rs"],
+    );
+    fs::write(temp_dir.path().join(".gitignore"), "*.txt\n").unwrap();
+    fs::write(temp_dir.path().join("scratch/.gitignore"), "*.log\n").unwrap();
+    let flags = Params {
+        no_git: true,
+        ..Params::default()
+    };
+
+    let mut files =
+        list_files_in_repo(&temp_dir.path().join("scratch"), &flags)
+            .unwrap()
+            .files;
+    files.sort();
+
+    assert_eq!(files, ["main.rs", "notes.txt"]);
+}
+
+#[test]
+fn test_path_scopes_select_subtrees_with_repository_relative_paths() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(
+        &temp_dir,
+        &[
+            "services/billing/src/lib.rs",
+            "services/billing-v2/src/lib.rs",
+            "services/search/src/lib.rs",
+            "libs/common/util.rs",
+            "README.md",
+        ],
+    );
+    let params = Params {
+        paths: Some(vec![
+            "services/billing".to_string(),
+            "./libs/common/".to_string(),
+            "services/billing/src".to_string(),
+            "README.md".to_string(),
+        ]),
+        ..Params::default()
+    };
+
+    let mut files =
+        list_files_in_repo(temp_dir.path(), &params).unwrap().files;
+    files.sort();
+
+    assert_eq!(
+        files,
+        [
+            "README.md",
+            "libs/common/util.rs",
+            "services/billing/src/lib.rs"
+        ]
+    );
+    assert_eq!(
+        path_scopes(temp_dir.path(), &params).unwrap(),
+        ["README.md", "libs/common", "services/billing"]
+    );
+}
+
+#[test]
+fn test_path_scopes_reject_missing_and_outside_paths() {
+    let temp_dir = TempDir::new().unwrap();
+    let scopes = |path: &str| {
+        let params = Params {
+            paths: Some(vec![path.to_string()]),
+            ..Params::default()
+        };
+        list_files_in_repo(temp_dir.path(), &params).unwrap_err()
+    };
+
+    assert_eq!(
+        scopes("services").to_string(),
+        "'services' does not exist in the repository"
+    );
+    assert_eq!(
+        scopes("../elsewhere").to_string(),
+        "'../elsewhere' is outside the repository"
+    );
+
+    fs::create_dir(temp_dir.path().join("src")).unwrap();
+    assert_eq!(
+        scopes("src:1-10").to_string(),
+        "'src:1-10' selects lines of a folder, not a file"
+    );
+    assert_eq!(
+        scopes("src/main.rs:10-1").to_string(),
+        "invalid line range '10-1' in 'src/main.rs:10-1': lines are counted \
+         from 1 and the range must not end before it starts"
+    );
+}
+
+#[test]
+fn test_parse_file_list_accepts_newline_and_nul_separators() {
+    assert_eq!(
+        parse_file_list("src/main.rs\r\n\nREADME.md\n"),
+        ["src/main.rs", "README.md"]
+    );
+    assert_eq!(
+        parse_file_list("with space.md\0new\nline.txt\0"),
+        ["with space.md", "new\nline.txt"]
+    );
+}
+
+#[test]
+fn test_files_from_skips_walker_and_reports_missing_files() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(
+        &temp_dir,
+        &["src/main.rs", "target/app.rs", "README.md"],
+    );
+    fs::write(temp_dir.path().join(".gitignore"), "target/\n").unwrap();
+    let params = |entries: &[&str]| Params {
+        files_from: Some(
+            entries.iter().map(|entry| entry.to_string()).collect(),
+        ),
+        ..Params::default()
+    };
+
+    let file_list = list_files_in_repo(
+        temp_dir.path(),
+        &params(&[
+            "target/app.rs",
+            "./src/main.rs",
+            "src/main.rs:1-3",
+            "src/gone.rs",
+            "src",
+        ]),
+    )
+    .unwrap();
+    assert_eq!(file_list.files, ["target/app.rs", "src/main.rs"]);
+    assert_eq!(file_list.missing, ["src/gone.rs", "src"]);
+
+    assert_eq!(
+        list_files_in_repo(temp_dir.path(), &params(&["../outside.rs"]))
+            .unwrap_err()
+            .to_string(),
+        "'../outside.rs' is outside the repository"
+    );
+}
+
+#[test]
+fn test_language_and_category_selection_filters_listing() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(
+        &temp_dir,
+        &[
+            "Cargo.toml",
+            "README.md",
+            "src/lib.rs",
+            "src/lib_test.go",
+            "tests/app.rs",
+            "docs/guide.md",
+            "examples/demo.rs",
+        ],
+    );
+    let list = |params: Params| {
+        let mut files =
+            list_files_in_repo(temp_dir.path(), &params).unwrap().files;
+        files.sort();
+        files
+    };
+
+    assert_eq!(
+        list(Params {
+            languages: Some(vec!["rust".to_string(), "toml".to_string()]),
+            ..Params::default()
+        }),
+        [
+            "Cargo.toml",
+            "examples/demo.rs",
+            "src/lib.rs",
+            "tests/app.rs"
+        ]
+    );
+    assert_eq!(
+        list(Params {
+            include_tests: false,
+            include_docs: false,
+            include_examples: false,
+            ..Params::default()
+        }),
+        ["Cargo.toml", "README.md", "src/lib.rs"]
+    );
+}
+
+#[test]
+fn test_limits_skip_files_but_keep_them_in_structure() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(&temp_dir, &["src/lib.rs"]);
+    fs::write(temp_dir.path().join("src/lib.rs"), "pub fn lib() {}\n")
+        .unwrap();
+    fs::create_dir(temp_dir.path().join("data")).unwrap();
+    fs::write(temp_dir.path().join("data/dump.sql"), "x".repeat(4096))
+        .unwrap();
+    let params = Params {
+        max_file_size: Some(1024),
+        ..Params::default()
+    };
+
+    let file_list = list_files_in_repo(temp_dir.path(), &params).unwrap();
+
+    assert_eq!(file_list.files, ["src/lib.rs"]);
+    assert_eq!(
+        file_list.skipped,
+        [SkippedFile {
+            path: "data/dump.sql".to_string(),
+            reason: SkipReason::Size,
+        }]
+    );
+    let file_tree = group_files_by_directory(file_list);
+    assert_eq!(file_tree.file_paths, ["src/lib.rs"]);
+    let data = file_tree.folder_node.subfolders.get("data").unwrap();
+    assert!(data.files.is_empty());
+    assert_eq!(data.skipped, [("dump.sql".to_string(), SkipReason::Size)]);
+}
@@ -0,0 +1,146 @@
+use super::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn create_files(files: &[(&str, &str)]) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    for (path, content) in files {
+        let path = temp_dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    temp_dir
+}
+
+fn reached(graph: &DependencyGraph) -> Vec<&str> {
+    graph.imports.keys().map(String::as_str).collect()
+}
+
+#[test]
+fn test_rust_modules_and_path_attributes_are_followed() {
+    let temp_dir = create_files(&[
+        (
+            "src/main.rs",
+            "mod cli;\npub(crate) mod output;\nmod missing;\n\n#[cfg(test)]\n#[path = \"../tests/crate/app.rs\"]\nmod tests;\n",
+        ),
+        ("src/cli.rs", "mod args;\nmod inline {\n}\n"),
+        ("src/cli/args.rs", "// mod commented;\n"),
+        ("src/output/mod.rs", "mod xml;\n"),
+        ("src/output/xml.rs", ""),
+        ("src/unused.rs", ""),
+        ("tests/crate/app.rs", "use super::*;\n"),
+    ]);
+
+    let graph =
+        DependencyGraph::build(temp_dir.path(), &["src/main.rs".to_string()])
+            .unwrap();
+
+    assert_eq!(
+        reached(&graph),
+        [
+            "src/cli.rs",
+            "src/cli/args.rs",
+            "src/main.rs",
+            "src/output/mod.rs",
+            "src/output/xml.rs",
+            "tests/crate/app.rs",
+        ]
+    );
+    assert_eq!(
+        graph.imports["src/main.rs"],
+        ["src/cli.rs", "src/output/mod.rs", "tests/crate/app.rs"]
+    );
+}
+
+#[test]
+fn test_python_imports_are_followed() {
+    let temp_dir = create_files(&[
+        (
+            "app/main.py",
+            "import os\nimport app.config as config\nfrom . import util\nfrom .models import user, *\nfrom ..shared import helpers\n",
+        ),
+        ("app/config.py", ""),
+        ("app/util.py", ""),
+        ("app/models/__init__.py", ""),
+        ("app/models/user.py", ""),
+        ("shared/helpers.py", ""),
+        ("src/lib/tools.py", ""),
+        ("app/other.py", "from lib import tools\n"),
+    ]);
+
+    let graph =
+        DependencyGraph::build(temp_dir.path(), &["app/main.py".to_string()])
+            .unwrap();
+
+    assert_eq!(
+        graph.imports["app/main.py"],
+        [
+            "app/config.py",
+            "app/util.py",
+            "app/models/__init__.py",
+            "app/models/user.py",
+            "shared/helpers.py",
+        ]
+    );
+
+    let graph =
+        DependencyGraph::build(temp_dir.path(), &["app/other.py".to_string()])
+            .unwrap();
+    assert_eq!(graph.imports["app/other.py"], ["src/lib/tools.py"]);
+}
+
+#[test]
+fn test_relative_js_and_ts_imports_are_followed() {
+    let temp_dir = create_files(&[
+        (
+            "web/index.ts",
+            "import React from 'react';\nimport { api } from './api';\nimport './styles.css';\nexport * from \"../lib\";\nconst util = require('./util.js');\nconst page = await import('./pages/home');\n",
+        ),
+        ("web/api.ts", ""),
+        ("web/styles.css", ""),
+        ("web/util.ts", ""),
+        ("web/pages/home.tsx", ""),
+        ("lib/index.js", ""),
+    ]);
+
+    let graph =
+        DependencyGraph::build(temp_dir.path(), &["web/index.ts".to_string()])
+            .unwrap();
+
+    assert_eq!(
+        graph.imports["web/index.ts"],
+        [
+            "web/api.ts",
+            "web/styles.css",
+            "lib/index.js",
+            "web/util.ts",
+            "web/pages/home.tsx",
+        ]
+    );
+}
+
+#[test]
+fn test_missing_seed_is_rejected() {
+    let temp_dir = create_files(&[("src/main.rs", "")]);
+
+    assert_eq!(
+        DependencyGraph::build(temp_dir.path(), &["src/lib.rs".to_string()])
+            .unwrap_err()
+            .to_string(),
+        "'src/lib.rs' does not exist in the repository"
+    );
+}
+
+#[test]
+fn test_graph_display_lists_imports() {
+    let temp_dir =
+        create_files(&[("src/main.rs", "mod cli;\n"), ("src/cli.rs", "")]);
+    let graph =
+        DependencyGraph::build(temp_dir.path(), &["src/main.rs".to_string()])
+            .unwrap();
+
+    assert_eq!(
+        graph.to_string(),
+        "Dependency graph from src/main.rs (2 files):\n  src/cli.rs\n  src/main.rs\n    -> src/cli.rs"
+    );
+}
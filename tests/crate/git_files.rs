@@ -0,0 +1,82 @@
+use super::*;
+use tempfile::TempDir;
+
+fn create_repository(tracked: &[&str], untracked: &[&str]) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let repository = Repository::init(temp_dir.path()).unwrap();
+    for path in tracked.iter().chain(untracked) {
+        let path = temp_dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "content").unwrap();
+    }
+    let mut index = repository.index().unwrap();
+    for path in tracked {
+        index.add_path(Path::new(path)).unwrap();
+    }
+    index.write().unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_list_selects_files_by_git_state() {
+    let temp_dir = create_repository(
+        &[".gitignore", "src/main.rs", "build/keep.txt"],
+        &["notes.md", "build/output.bin", "debug.log"],
+    );
+    fs::write(temp_dir.path().join(".gitignore"), "build/\n*.log\n").unwrap();
+    let files = GitFiles::open(temp_dir.path()).unwrap();
+
+    assert_eq!(
+        files.list(false, false).unwrap(),
+        [".gitignore", "build/keep.txt", "src/main.rs"]
+    );
+    assert_eq!(
+        files.list(true, false).unwrap(),
+        [".gitignore", "build/keep.txt", "notes.md", "src/main.rs"]
+    );
+    assert_eq!(
+        files.list(false, true).unwrap(),
+        [
+            ".gitignore",
+            "build/keep.txt",
+            "build/output.bin",
+            "debug.log",
+            "src/main.rs"
+        ]
+    );
+}
+
+#[test]
+fn test_tracked_files_deleted_from_working_tree_are_skipped() {
+    let temp_dir = create_repository(&["kept.rs", "deleted.rs"], &[]);
+    fs::remove_file(temp_dir.path().join("deleted.rs")).unwrap();
+
+    let files = GitFiles::open(temp_dir.path()).unwrap();
+
+    assert!(files.is_tracked("deleted.rs"));
+    assert_eq!(files.list(false, false).unwrap(), ["kept.rs"]);
+}
+
+#[test]
+fn test_paths_are_relative_to_a_subdirectory_root() {
+    let temp_dir =
+        create_repository(&["README.md", "crates/core/src/lib.rs"], &[]);
+
+    let files = GitFiles::open(&temp_dir.path().join("crates/core")).unwrap();
+
+    assert_eq!(files.list(true, false).unwrap(), ["src/lib.rs"]);
+    assert!(files.contains_tracked("src"));
+    assert!(!files.contains_tracked("sr"));
+}
+
+#[test]
+fn test_ignored_paths_include_contents_of_ignored_directories() {
+    let temp_dir = create_repository(&[".gitignore"], &["target/debug/app"]);
+    fs::write(temp_dir.path().join(".gitignore"), "/target\n").unwrap();
+
+    let files = GitFiles::open(temp_dir.path()).unwrap();
+
+    assert!(files.is_ignored("target"));
+    assert!(files.is_ignored("target/debug/app"));
+    assert!(!files.is_ignored(".gitignore"));
+}
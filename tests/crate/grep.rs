@@ -0,0 +1,74 @@
+use super::*;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_parse_grep_pattern_rejects_invalid_regex() {
+    assert_eq!(
+        parse_grep_pattern("Progress(Reporter)?"),
+        Ok("Progress(Reporter)?".to_string())
+    );
+    assert!(parse_grep_pattern("(unclosed").is_err());
+}
+
+#[test]
+fn test_retain_matching_files_reads_decoded_text() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("main.rs"),
+        "let reporter = ProgressReporter::new();\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("lib.rs"), "pub fn lib() {}\n").unwrap();
+    fs::write(temp_dir.path().join("blob.bin"), b"ProgressReporter\0")
+        .unwrap();
+    let file_list = FileList {
+        files: vec![
+            "main.rs".to_string(),
+            "lib.rs".to_string(),
+            "blob.bin".to_string(),
+            "gone.rs".to_string(),
+        ],
+        ..FileList::default()
+    };
+    let flags = Params {
+        grep: Some("Progress\\w+".to_string()),
+        ..Params::default()
+    };
+
+    let file_list = retain_matching_files(
+        temp_dir.path(),
+        &flags,
+        file_list,
+        &mut ProcessingTimings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(file_list.files, ["main.rs", "gone.rs"]);
+}
+
+#[test]
+fn test_match_ranges_merge_overlapping_context() {
+    let lines = [
+        "one", "two", "hit", "four", "hit", "six", "seven", "eight", "nine",
+        "hit",
+    ];
+    let pattern = Regex::new("hit").unwrap();
+
+    assert_eq!(
+        match_ranges(&lines, &pattern, 1),
+        [
+            LineRange { start: 2, end: 6 },
+            LineRange { start: 9, end: 10 },
+        ]
+    );
+    assert_eq!(
+        match_ranges(&lines, &pattern, 0),
+        [
+            LineRange { start: 3, end: 3 },
+            LineRange { start: 5, end: 5 },
+            LineRange { start: 10, end: 10 },
+        ]
+    );
+    assert!(match_ranges(&lines, &Regex::new("miss").unwrap(), 2).is_empty());
+}
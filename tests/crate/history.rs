@@ -0,0 +1,202 @@
+use super::*;
+use git2::{Oid, Signature};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn commit_at(
+    repo: &Repository,
+    files: &[(&str, &str)],
+    subject: &str,
+    seconds: i64,
+) -> Oid {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+    for (path, content) in files {
+        let full_path = workdir.join(path);
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        fs::write(full_path, content).unwrap();
+        index.add_path(Path::new(path)).unwrap();
+    }
+    index.write().unwrap();
+    let parents = repo
+        .head()
+        .ok()
+        .map(|head| head.peel_to_commit().unwrap())
+        .into_iter()
+        .collect::<Vec<_>>();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature =
+        Signature::new("Ann Dev", "ann@example.com", &Time::new(seconds, 60))
+            .unwrap();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        subject,
+        &tree,
+        &parents.iter().collect::<Vec<_>>(),
+    )
+    .unwrap()
+}
+
+fn repository_with_history(path: &Path) -> (Repository, [Oid; 3]) {
+    let repo = Repository::init(path).unwrap();
+    let first = commit_at(
+        &repo,
+        &[("src/main.rs", "fn main() {}\n"), ("README.md", "# Demo\n")],
+        "Initial commit",
+        1_700_000_000,
+    );
+    let second = commit_at(
+        &repo,
+        &[("src/lib.rs", "pub fn lib() {}\n")],
+        "Add the library\n\nWith a body.",
+        1_700_000_100,
+    );
+    let third = commit_at(
+        &repo,
+        &[("README.md", "# Demo\n\nUsage.\n")],
+        "Document usage",
+        1_700_000_200,
+    );
+    (repo, [first, second, third])
+}
+
+#[test]
+fn test_format_time_uses_the_commit_offset() {
+    assert_eq!(format_time(Time::new(0, 0)), "1970-01-01T00:00:00+00:00");
+    assert_eq!(
+        format_time(Time::new(1_700_000_000, 60)),
+        "2023-11-14T23:13:20+01:00"
+    );
+    assert_eq!(
+        format_time(Time::new(951_782_400, -330)),
+        "2000-02-28T18:30:00-05:30"
+    );
+}
+
+#[test]
+fn test_git_history_lists_recent_commits_touching_the_paths() {
+    let temp_dir = tempdir().unwrap();
+    let (repo, [first, second, _]) = repository_with_history(temp_dir.path());
+    let paths = ["lib.rs".to_string(), "main.rs".to_string()];
+
+    let history = git_history(&repo, "HEAD", "src", &paths, 5, false).unwrap();
+
+    assert_eq!(
+        history.commits,
+        [
+            CommitSummary {
+                sha: second.to_string(),
+                author: "Ann Dev".to_string(),
+                date: "2023-11-14T23:15:00+01:00".to_string(),
+                subject: "Add the library".to_string(),
+                paths: vec!["lib.rs".to_string()],
+            },
+            CommitSummary {
+                sha: first.to_string(),
+                author: "Ann Dev".to_string(),
+                date: "2023-11-14T23:13:20+01:00".to_string(),
+                subject: "Initial commit".to_string(),
+                paths: vec!["main.rs".to_string()],
+            },
+        ]
+    );
+    assert!(history.last_commits.is_empty());
+}
+
+#[test]
+fn test_git_history_records_the_last_commit_per_file() {
+    let temp_dir = tempdir().unwrap();
+    let (repo, [first, second, third]) =
+        repository_with_history(temp_dir.path());
+    let paths = [
+        "README.md".to_string(),
+        "src/lib.rs".to_string(),
+        "src/main.rs".to_string(),
+    ];
+
+    let history = git_history(&repo, "HEAD", "", &paths, 1, true).unwrap();
+
+    assert_eq!(history.commits.len(), 1);
+    assert_eq!(history.commits[0].sha, third.to_string());
+    assert_eq!(history.last_commits["README.md"], third.to_string());
+    assert_eq!(history.last_commits["src/lib.rs"], second.to_string());
+    assert_eq!(history.last_commits["src/main.rs"], first.to_string());
+
+    let history =
+        git_history(&repo, &second.to_string(), "", &paths, 0, true).unwrap();
+    assert!(history.commits.is_empty());
+    assert_eq!(history.last_commits["README.md"], first.to_string());
+}
+
+#[test]
+fn test_hotspots_rank_by_commits_authors_and_recent_churn() {
+    let temp_dir = tempdir().unwrap();
+    let (repo, _) = repository_with_history(temp_dir.path());
+    commit_at(
+        &repo,
+        &[("src/lib.rs", "pub fn lib() {}\npub fn more() {}\n")],
+        "Extend the library",
+        1_700_000_300,
+    );
+    let paths = [
+        "README.md".to_string(),
+        "src/lib.rs".to_string(),
+        "src/main.rs".to_string(),
+        "untracked.txt".to_string(),
+    ];
+
+    let ranked = hotspots(&repo, "HEAD", "", &paths, 1_700_000_150).unwrap();
+
+    assert_eq!(
+        ranked,
+        [
+            Hotspot {
+                path: "README.md".to_string(),
+                commits: 2,
+                authors: 1,
+                recent_churn: 2,
+            },
+            Hotspot {
+                path: "src/lib.rs".to_string(),
+                commits: 2,
+                authors: 1,
+                recent_churn: 1,
+            },
+            Hotspot {
+                path: "src/main.rs".to_string(),
+                commits: 1,
+                authors: 1,
+                recent_churn: 0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_order_by_hotspots_puts_ranked_files_first() {
+    let mut paths = ["a.rs", "b.rs", "c.rs", "d.rs"].map(str::to_string);
+    let ranked = ["c.rs", "a.rs"].map(|path| Hotspot {
+        path: path.to_string(),
+        ..Hotspot::default()
+    });
+
+    order_by_hotspots(&mut paths, &ranked);
+
+    assert_eq!(paths, ["c.rs", "a.rs", "b.rs", "d.rs"]);
+}
+
+#[test]
+fn test_order_by_hotspots_puts_unranked_files_in_path_order() {
+    let mut paths = ["d.rs", "src/b.rs", "a.rs", "c.rs"].map(str::to_string);
+    let ranked = [Hotspot {
+        path: "c.rs".to_string(),
+        ..Hotspot::default()
+    }];
+
+    order_by_hotspots(&mut paths, &ranked);
+
+    assert_eq!(paths, ["c.rs", "a.rs", "d.rs", "src/b.rs"]);
+}
@@ -0,0 +1,99 @@
+use super::*;
+use crate::filelist::BUNDLEREPO_IGNORE_FILENAME;
+use git2::Repository;
+use tempfile::TempDir;
+
+fn write(temp_dir: &TempDir, path: &str, content: &str) {
+    let path = temp_dir.path().join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_match_reports_source_file_line_and_pattern() {
+    let temp_dir = TempDir::new().unwrap();
+    Repository::init(temp_dir.path()).unwrap();
+    write(
+        &temp_dir,
+        ".gitignore",
+        "# build output\n\n/target\n*.log\n",
+    );
+
+    let mut rules = IgnoreRules::new(temp_dir.path(), &[]);
+
+    assert_eq!(
+        rules.matched("target", true),
+        Some(IgnoreMatch {
+            source: ".gitignore".to_string(),
+            line: 3,
+            pattern: "/target".to_string(),
+            whitelist: false,
+        })
+    );
+    let found = rules.matched("src/debug.log", false).unwrap();
+    assert_eq!(found.to_string(), ".gitignore:4: *.log");
+    assert_eq!(rules.matched("src/main.rs", false), None);
+}
+
+#[test]
+fn test_deeper_and_custom_ignore_files_take_precedence() {
+    let temp_dir = TempDir::new().unwrap();
+    Repository::init(temp_dir.path()).unwrap();
+    write(&temp_dir, ".gitignore", "*.json\n");
+    write(&temp_dir, "fixtures/.gitignore", "!keep.json\n");
+    write(&temp_dir, "fixtures/.bundlerepoignore", "big.txt\n");
+    write(&temp_dir, "fixtures/.ignore", "!big.txt\n");
+
+    let mut rules =
+        IgnoreRules::new(temp_dir.path(), &[BUNDLEREPO_IGNORE_FILENAME]);
+
+    let keep = rules.matched("fixtures/keep.json", false).unwrap();
+    assert!(keep.whitelist);
+    assert_eq!(keep.source, "fixtures/.gitignore");
+    assert_eq!(
+        rules.ignored("fixtures/other.json", false).unwrap().source,
+        ".gitignore"
+    );
+    let big = rules.ignored("fixtures/big.txt", false).unwrap();
+    assert_eq!(big.source, "fixtures/.bundlerepoignore");
+}
+
+#[test]
+fn test_ignored_reports_rule_for_parent_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    Repository::init(temp_dir.path()).unwrap();
+    write(&temp_dir, ".gitignore", "build/\n");
+
+    let mut rules = IgnoreRules::new(temp_dir.path(), &[]);
+
+    let found = rules.ignored("build/out/app.js", false).unwrap();
+    assert_eq!(found.pattern, "build/");
+    assert_eq!(rules.matched("build/out/app.js", false), None);
+}
+
+#[test]
+fn test_git_ignore_files_require_a_repository() {
+    let temp_dir = TempDir::new().unwrap();
+    write(&temp_dir, ".gitignore", "*.log\n");
+    write(&temp_dir, ".bundlerepoignore", "*.sql\n");
+
+    let mut rules =
+        IgnoreRules::new(temp_dir.path(), &[BUNDLEREPO_IGNORE_FILENAME]);
+
+    assert_eq!(rules.matched("debug.log", false), None);
+    assert!(rules.matched("dump.sql", false).is_some());
+}
+
+#[test]
+fn test_plain_directory_honours_git_ignore_files() {
+    let temp_dir = TempDir::new().unwrap();
+    write(&temp_dir, ".gitignore", "*.log\n");
+
+    let mut rules = IgnoreRules::plain_directory(temp_dir.path(), &[]);
+
+    assert_eq!(
+        rules.matched("debug.log", false).unwrap().source,
+        ".gitignore"
+    );
+    assert_eq!(rules.matched("main.rs", false), None);
+}
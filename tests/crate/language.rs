@@ -0,0 +1,104 @@
+use super::*;
+
+#[test]
+fn test_language_for_uses_filenames_then_extensions() {
+    let cases = [
+        ("src/main.rs", Some("rust")),
+        ("Cargo.toml", Some("toml")),
+        ("web/App.TSX", Some("typescript")),
+        ("build/CMakeLists.txt", Some("cmake")),
+        ("notes.txt", Some("text")),
+        ("Dockerfile", Some("dockerfile")),
+        ("Makefile", Some("makefile")),
+        (".bashrc", None),
+        ("LICENSE", None),
+        ("image.png", None),
+    ];
+
+    for (path, expected) in cases {
+        assert_eq!(language_for(path), expected, "{path}");
+    }
+}
+
+#[test]
+fn test_language_table_names_are_sorted_and_unique() {
+    let names = language_names().collect::<Vec<_>>();
+    let mut sorted = names.clone();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    assert_eq!(names, sorted);
+}
+
+#[test]
+fn test_parse_language_is_case_insensitive_and_lists_names() {
+    assert_eq!(parse_language("Rust"), Ok("rust".to_string()));
+    assert_eq!(parse_language(" toml "), Ok("toml".to_string()));
+
+    let error = parse_language("klingon").unwrap_err();
+    assert!(error.starts_with("Unknown language 'klingon'. Supported"));
+    assert!(error.contains("rust, scala"));
+}
+
+#[test]
+fn test_categories_follow_common_conventions() {
+    let cases = [
+        ("tests/app.rs", vec![Category::Tests]),
+        ("pkg/server_test.go", vec![Category::Tests]),
+        ("lib/test_parser.py", vec![Category::Tests]),
+        ("web/button.spec.tsx", vec![Category::Tests]),
+        ("src/main/java/AppTest.java", vec![Category::Tests]),
+        ("docs/guide.md", vec![Category::Docs]),
+        ("examples/basic/main.rs", vec![Category::Examples]),
+        (
+            "docs/examples/setup.md",
+            vec![Category::Docs, Category::Examples],
+        ),
+        ("src/testing.rs", vec![]),
+        ("src/contest.py", vec![]),
+        ("README.md", vec![]),
+    ];
+
+    for (path, expected) in cases {
+        assert_eq!(categories_for(path), expected, "{path}");
+    }
+}
+
+#[test]
+fn test_selection_reports_category_before_language() {
+    let flags = Params {
+        languages: Some(vec!["rust".to_string(), "toml".to_string()]),
+        include_tests: false,
+        ..Params::default()
+    };
+    let selection = FileSelection::new(&flags).unwrap();
+
+    assert_eq!(selection.deselection("src/lib.rs"), None);
+    assert_eq!(selection.deselection("Cargo.toml"), None);
+    assert_eq!(
+        selection.deselection("tests/app.rs"),
+        Some(Deselection::Category(Category::Tests))
+    );
+    assert_eq!(
+        selection.deselection("README.md"),
+        Some(Deselection::Language(Some("markdown")))
+    );
+    assert_eq!(
+        selection.deselection("LICENSE").unwrap().to_string(),
+        "language is not recognised by --lang"
+    );
+    assert_eq!(selection.excluded_directory("Tests"), Some(Category::Tests));
+    assert_eq!(selection.excluded_directory("docs"), None);
+}
+
+#[test]
+fn test_selection_rejects_unknown_configured_language() {
+    let flags = Params {
+        languages: Some(vec!["cobol".to_string()]),
+        ..Params::default()
+    };
+
+    let error = FileSelection::new(&flags).err().unwrap();
+
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,87 @@
+use super::*;
+use std::fs::{self, File, FileTimes};
+use tempfile::TempDir;
+
+#[test]
+fn test_parse_file_size_accepts_binary_units() {
+    let cases = [
+        ("512", 512),
+        ("500K", 500 * 1024),
+        ("10MB", 10 * 1024 * 1024),
+        ("2 gib", 2 * 1024 * 1024 * 1024),
+        ("7b", 7),
+    ];
+
+    for (value, expected) in cases {
+        assert_eq!(parse_file_size(value), Ok(expected), "{value}");
+    }
+    for value in ["", "MB", "10TB", "1.5M", "-1"] {
+        assert!(parse_file_size(value).is_err(), "{value}");
+    }
+}
+
+#[test]
+fn test_parse_age_and_line_count() {
+    assert_eq!(parse_age("90m"), Ok(90 * 60));
+    assert_eq!(parse_age("12h"), Ok(12 * 60 * 60));
+    assert_eq!(parse_age("30d"), Ok(30 * 24 * 60 * 60));
+    assert_eq!(parse_age("2w"), Ok(14 * 24 * 60 * 60));
+    assert!(parse_age("30").is_err());
+    assert!(parse_age("1y").is_err());
+
+    assert_eq!(parse_line_count("2000"), Ok(2000));
+    assert!(parse_line_count("0").is_err());
+    assert!(parse_line_count("lots").is_err());
+}
+
+#[test]
+fn test_limits_report_first_broken_limit() {
+    let temp_dir = TempDir::new().unwrap();
+    let large = temp_dir.path().join("dump.sql");
+    let long = temp_dir.path().join("long.txt");
+    let old = temp_dir.path().join("old.rs");
+    let small = temp_dir.path().join("small.rs");
+    fs::write(&large, "x".repeat(2048)).unwrap();
+    fs::write(&long, "1\n2\n3\n4").unwrap();
+    fs::write(&old, "fn old() {}\n").unwrap();
+    fs::write(&small, "a\nb\nc").unwrap();
+    let week_ago = SystemTime::now() - Duration::from_secs(7 * 24 * 60 * 60);
+    File::options()
+        .write(true)
+        .open(&old)
+        .unwrap()
+        .set_times(FileTimes::new().set_modified(week_ago))
+        .unwrap();
+    let flags = Params {
+        max_file_size: Some(1024),
+        max_file_lines: Some(3),
+        modified_within: Some(24 * 60 * 60),
+        ..Params::default()
+    };
+    let limits = FileLimits::new(&flags);
+    let root = temp_dir.path();
+
+    assert_eq!(
+        limits.check(root, "dump.sql").unwrap(),
+        Some(SkipReason::Size)
+    );
+    assert_eq!(
+        limits.check(root, "long.txt").unwrap(),
+        Some(SkipReason::Lines)
+    );
+    assert_eq!(limits.check(root, "old.rs").unwrap(), Some(SkipReason::Age));
+    assert_eq!(limits.check(root, "small.rs").unwrap(), None);
+    assert_eq!(
+        FileLimits::new(&Params::default())
+            .check(root, "dump.sql")
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_skip_reason_attribute_values() {
+    assert_eq!(SkipReason::Size.as_str(), "too-large");
+    assert_eq!(SkipReason::Lines.as_str(), "too-many-lines");
+    assert_eq!(SkipReason::Age.as_str(), "too-old");
+}
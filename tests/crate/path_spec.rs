@@ -0,0 +1,144 @@
+use super::*;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_parse_path_spec_reads_line_ranges() {
+    assert_eq!(
+        parse_path_spec("src/xml_output.rs:300-420"),
+        Ok(PathSpec {
+            path: "src/xml_output.rs".to_string(),
+            lines: Some(LineRange {
+                start: 300,
+                end: 420,
+            }),
+            symbol: None,
+        })
+    );
+    assert_eq!(
+        parse_path_spec("src/main.rs:12").unwrap().lines,
+        Some(LineRange { start: 12, end: 12 })
+    );
+
+    for value in [
+        "src",
+        "notes:draft.md",
+        "C:\\repo\\src",
+        "a.rs:-4",
+        "a.rs::1x",
+        "::main",
+    ] {
+        assert_eq!(
+            parse_path_spec(value),
+            Ok(PathSpec {
+                path: value.to_string(),
+                lines: None,
+                symbol: None,
+            }),
+            "{value}"
+        );
+    }
+    for value in ["src/main.rs:0-4", "src/main.rs:20-10"] {
+        assert!(parse_path_spec(value).is_err(), "{value}");
+    }
+}
+
+#[test]
+fn test_parse_path_spec_reads_symbols() {
+    assert_eq!(
+        parse_path_spec("src/main.rs::run_application"),
+        Ok(PathSpec {
+            path: "src/main.rs".to_string(),
+            lines: None,
+            symbol: Some("run_application".to_string()),
+        })
+    );
+    let spec = parse_path_spec("src/structs.rs::Params::default").unwrap();
+    assert_eq!(spec.path, "src/structs.rs");
+    assert_eq!(spec.symbol.as_deref(), Some("Params::default"));
+    assert!(spec.is_partial());
+}
+
+#[test]
+fn test_line_ranges_resolve_symbols() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        "use std::io;\n\n/// Reads.\npub fn read() {\n    todo!()\n}\n",
+    )
+    .unwrap();
+    let flags = Params {
+        files_from: Some(vec![
+            "lib.rs::read".to_string(),
+            "missing.rs::read".to_string(),
+        ]),
+        ..Params::default()
+    };
+
+    let ranges = line_ranges(temp_dir.path(), &flags).unwrap();
+    assert_eq!(
+        ranges.into_iter().collect::<Vec<_>>(),
+        vec![("lib.rs".to_string(), vec![LineRange { start: 3, end: 6 }])]
+    );
+
+    let flags = Params {
+        paths: Some(vec!["lib.rs::write".to_string()]),
+        ..Params::default()
+    };
+    let error = line_ranges(temp_dir.path(), &flags).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    assert_eq!(error.to_string(), "'write' is not defined in 'lib.rs'");
+}
+
+#[test]
+fn test_line_ranges_defer_to_whole_file_selection() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+    fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(temp_dir.path().join("lib.rs"), "pub fn lib() {}\n").unwrap();
+    let flags = Params {
+        paths: Some(vec![
+            "lib.rs:20-30".to_string(),
+            "lib.rs:1-5".to_string(),
+            "src/main.rs:1-2".to_string(),
+            "src".to_string(),
+        ]),
+        ..Params::default()
+    };
+
+    let ranges = line_ranges(temp_dir.path(), &flags).unwrap();
+    assert_eq!(
+        ranges.into_iter().collect::<Vec<_>>(),
+        vec![(
+            "lib.rs".to_string(),
+            vec![
+                LineRange { start: 1, end: 5 },
+                LineRange { start: 20, end: 30 },
+            ],
+        )]
+    );
+}
+
+#[test]
+fn test_render_line_range_keeps_original_numbers() {
+    let lines = (1..=12)
+        .map(|line| format!("line {line}"))
+        .collect::<Vec<_>>();
+    let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+
+    assert_eq!(
+        render_line_range(&lines, LineRange { start: 9, end: 20 }, true),
+        Some((
+            " 9  line 9\n10  line 10\n11  line 11\n12  line 12\n".to_string(),
+            12
+        ))
+    );
+    assert_eq!(
+        render_line_range(&lines, LineRange { start: 2, end: 3 }, false),
+        Some(("line 2\nline 3\n".to_string(), 3))
+    );
+    assert_eq!(
+        render_line_range(&lines, LineRange { start: 13, end: 14 }, false),
+        None
+    );
+}
@@ -1,4 +1,5 @@
 use super::*;
+use crate::source::FileSource;
 use git2::{Error, ErrorClass, ErrorCode, Oid, Signature};
 use std::fs;
 use tempfile::tempdir;
@@ -57,20 +58,311 @@ fn test_repository_check_rejects_explicit_non_repository_path() {
 
     let error = check_repository_at(temp_dir.path(), &params).unwrap_err();
 
-    assert_eq!(error.message(), "Not a git repository");
+    assert_eq!(
+        error.message(),
+        format!(
+            "No git repository found at '{}'. Use --no-git to bundle it as a plain directory.",
+            temp_dir.path().display()
+        )
+    );
 }
 
 #[test]
-fn test_clone_repo_rejects_invalid_repository_input() {
-    let destination_dir = tempdir().unwrap();
+fn test_repository_check_accepts_plain_directory_without_git() {
+    let temp_dir = tempdir().unwrap();
+    let params = Params {
+        stdout: true,
+        no_git: true,
+        ..Params::default()
+    };
+
+    assert!(check_repository_at(temp_dir.path(), &params).is_ok());
+}
+
+#[test]
+fn test_local_repository_path_accepts_directories_and_file_urls() {
+    let temp_dir = tempdir().unwrap();
+    let file_url = Url::from_file_path(temp_dir.path()).unwrap();
+
+    assert_eq!(
+        local_repository_path(&temp_dir.path().to_string_lossy()),
+        Some(temp_dir.path().to_path_buf())
+    );
+    assert_eq!(
+        local_repository_path(file_url.as_str()),
+        Some(temp_dir.path().to_path_buf())
+    );
+    assert_eq!(local_repository_path("seapagan/bundle-repo"), None);
+    assert_eq!(
+        local_repository_path("https://github.com/seapagan/bundle-repo"),
+        None
+    );
+}
+
+fn commit_files(repo: &Repository, files: &[(&str, &str)]) -> Oid {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+    for (path, content) in files {
+        let full_path = workdir.join(path);
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        fs::write(full_path, content).unwrap();
+        index.add_path(Path::new(path)).unwrap();
+    }
+    index.write().unwrap();
+    let parents = repo
+        .head()
+        .ok()
+        .map(|head| head.peel_to_commit().unwrap())
+        .into_iter()
+        .collect::<Vec<_>>();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("Test", "test@example.com").unwrap();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "test",
+        &tree,
+        &parents.iter().collect::<Vec<_>>(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_read_ref_reads_the_commit_not_the_working_tree() {
+    let temp_dir = tempdir().unwrap();
+    let repo = Repository::init(temp_dir.path().join("repo")).unwrap();
+    let first = commit_files(
+        &repo,
+        &[("src/lib.rs", "pub fn v1() {}\n"), ("README.md", "# v1\n")],
+    );
+    repo.tag_lightweight(
+        "v1.0.0",
+        &repo.find_object(first, None).unwrap(),
+        false,
+    )
+    .unwrap();
+    commit_files(&repo, &[("src/lib.rs", "pub fn v2() {}\n")]);
+    fs::write(repo.workdir().unwrap().join("README.md"), "dirty\n").unwrap();
+    let params = Params {
+        stdout: true,
+        ..Params::default()
+    };
+
+    let tree = read_ref_at(
+        &repo.workdir().unwrap().join("src"),
+        "v1.0.0",
+        false,
+        &params,
+    )
+    .unwrap();
+    assert_eq!(tree.prefix(), "src");
+    assert_eq!(tree.read_to_string("lib.rs").unwrap(), "pub fn v1() {}\n");
+
+    let tree =
+        read_ref_at(repo.workdir().unwrap(), "HEAD", true, &params).unwrap();
+    assert_eq!(
+        tree.read_to_string("src/lib.rs").unwrap(),
+        "pub fn v2() {}\n"
+    );
+    assert_eq!(tree.read_to_string("README.md").unwrap(), "# v1\n");
+
+    let tree = read_ref_at(
+        &repo.workdir().unwrap().join("src"),
+        "HEAD",
+        true,
+        &params,
+    )
+    .unwrap();
+    assert_eq!(tree.prefix(), "");
+    assert_eq!(tree.read_to_string("README.md").unwrap(), "# v1\n");
+}
+
+#[test]
+fn test_clone_repo_reads_requested_ref() {
+    let source = tempdir().unwrap();
+    let repo = Repository::init(source.path()).unwrap();
+    let first = commit_files(&repo, &[("lib.rs", "pub fn v1() {}\n")]);
+    repo.tag_lightweight(
+        "v1.0.0",
+        &repo.find_object(first, None).unwrap(),
+        false,
+    )
+    .unwrap();
+    commit_files(&repo, &[("lib.rs", "pub fn v2() {}\n")]);
+    let cache_dir = tempdir().unwrap();
+    let params = Params {
+        stdout: true,
+        git_ref: Some("v1.0.0".to_string()),
+        cache_dir: Some(cache_dir.path().to_string_lossy().into_owned()),
+        ..Params::default()
+    };
+    let source_url = Url::from_file_path(source.path()).unwrap();
+
+    let tree =
+        clone_repo(&params, source_url.as_str(), None, cache_dir.path())
+            .unwrap();
+
+    assert_eq!(tree.read_to_string("lib.rs").unwrap(), "pub fn v1() {}\n");
+}
+
+#[test]
+fn test_clone_repo_reuses_the_cached_clone_offline() {
+    let source = tempdir().unwrap();
+    let repo = Repository::init(source.path()).unwrap();
+    commit_files(&repo, &[("lib.rs", "pub fn v1() {}\n")]);
+    let cache_dir = tempdir().unwrap();
+    let source_url = Url::from_file_path(source.path()).unwrap();
+    let params = Params {
+        stdout: true,
+        cache_dir: Some(cache_dir.path().to_string_lossy().into_owned()),
+        ..Params::default()
+    };
+    let offline = Params {
+        offline: true,
+        ..params
+    };
+
+    let error =
+        clone_repo(&offline, source_url.as_str(), None, cache_dir.path())
+            .err()
+            .unwrap();
+    assert_eq!(
+        error.message(),
+        format!(
+            "The repository '{source_url}' is not in the clone cache. Run once without --offline to fetch it."
+        )
+    );
+
+    let params = Params {
+        offline: false,
+        ..offline
+    };
+    clone_repo(&params, source_url.as_str(), None, cache_dir.path()).unwrap();
+    commit_files(&repo, &[("lib.rs", "pub fn v2() {}\n")]);
+    let offline = Params {
+        offline: true,
+        ..params
+    };
+    let tree =
+        clone_repo(&offline, source_url.as_str(), None, cache_dir.path())
+            .unwrap();
+    assert_eq!(tree.read_to_string("lib.rs").unwrap(), "pub fn v1() {}\n");
+
+    let params = Params {
+        offline: false,
+        ..offline
+    };
+    let tree =
+        clone_repo(&params, source_url.as_str(), None, cache_dir.path())
+            .unwrap();
+    assert_eq!(tree.read_to_string("lib.rs").unwrap(), "pub fn v2() {}\n");
+}
+
+#[test]
+fn test_clone_repo_bundles_the_requested_branch() {
+    let source = tempdir().unwrap();
+    let repo = Repository::init(source.path()).unwrap();
+    let first = commit_files(&repo, &[("lib.rs", "pub fn v1() {}\n")]);
+    repo.branch("stable", &repo.find_commit(first).unwrap(), false)
+        .unwrap();
+    commit_files(&repo, &[("lib.rs", "pub fn v2() {}\n")]);
+    let cache_dir = tempdir().unwrap();
+    let source_url = Url::from_file_path(source.path()).unwrap();
+    let params = Params {
+        stdout: true,
+        branch: Some("stable".to_string()),
+        cache_dir: Some(cache_dir.path().to_string_lossy().into_owned()),
+        ..Params::default()
+    };
+
+    let tree =
+        clone_repo(&params, source_url.as_str(), None, cache_dir.path())
+            .unwrap();
+    assert_eq!(tree.read_to_string("lib.rs").unwrap(), "pub fn v1() {}\n");
+
+    let params = Params {
+        branch: Some("missing".to_string()),
+        ..params
+    };
+    let error =
+        clone_repo(&params, source_url.as_str(), None, cache_dir.path())
+            .err()
+            .unwrap();
+    assert_eq!(
+        error.message(),
+        "The specified branch 'missing' does not exist in the repository."
+    );
+}
+
+#[test]
+fn test_clone_repo_without_cache_uses_a_temporary_clone() {
+    let source = tempdir().unwrap();
+    let repo = Repository::init(source.path()).unwrap();
+    commit_files(&repo, &[("lib.rs", "pub fn v1() {}\n")]);
+    let cache_dir = tempdir().unwrap();
+    let temp_dir = tempdir().unwrap();
+    let source_url = Url::from_file_path(source.path()).unwrap();
     let params = Params {
         stdout: true,
+        no_cache: true,
+        cache_dir: Some(cache_dir.path().to_string_lossy().into_owned()),
         ..Params::default()
     };
 
+    let tree = clone_repo(&params, source_url.as_str(), None, temp_dir.path())
+        .unwrap();
+
+    assert_eq!(tree.read_to_string("lib.rs").unwrap(), "pub fn v1() {}\n");
+    assert!(temp_dir.path().join("repo_clone").is_dir());
+    assert!(fs::read_dir(cache_dir.path()).unwrap().next().is_none());
+
+    let offline = Params {
+        offline: true,
+        ..params
+    };
     let error =
-        clone_repo(&params, "not a repository", None, destination_dir.path())
-            .unwrap_err();
+        clone_repo(&offline, source_url.as_str(), None, temp_dir.path())
+            .err()
+            .unwrap();
+    assert_eq!(
+        error.message(),
+        "--offline reads the clone cache, which --no-cache turns off"
+    );
+}
+
+#[test]
+fn test_read_ref_reports_missing_ref() {
+    let temp_dir = tempdir().unwrap();
+    let repo = Repository::init(temp_dir.path().join("repo")).unwrap();
+    commit_files(&repo, &[("README.md", "# v1\n")]);
+    let params = Params {
+        stdout: true,
+        ..Params::default()
+    };
+
+    let error = read_ref_at(repo.workdir().unwrap(), "v9.9.9", true, &params)
+        .err()
+        .unwrap();
+
+    assert_eq!(
+        error.message(),
+        "The ref 'v9.9.9' does not exist in the repository."
+    );
+}
+
+#[test]
+fn test_clone_repo_rejects_invalid_repository_input() {
+    let params = Params {
+        stdout: true,
+        ..Params::default()
+    };
+
+    let temp_dir = tempdir().unwrap();
+
+    let error = clone_repo(&params, "not a repository", None, temp_dir.path())
+        .err()
+        .unwrap();
 
     assert_eq!(error.message(), "Invalid repository shorthand");
 }
@@ -102,6 +394,23 @@ fn test_clone_error_reports_network_context() {
     );
 }
 
+#[test]
+fn test_clone_error_keeps_the_authentication_methods_tried() {
+    let authentication = Error::new(
+        ErrorCode::Auth,
+        ErrorClass::Ssh,
+        "Authentication failed for 'git@github.com:org/repo.git' after trying the SSH agent.",
+    );
+    assert_eq!(
+        clone_error_message(
+            "git@github.com:org/repo.git",
+            None,
+            &authentication
+        ),
+        "Authentication failed for 'git@github.com:org/repo.git' after trying the SSH agent."
+    );
+}
+
 #[test]
 fn test_clone_error_reports_authentication_guidance() {
     let authentication = Error::new(
@@ -124,3 +433,199 @@ fn test_clone_error_preserves_unexpected_details() {
         format!("Failed to clone: {unexpected}")
     );
 }
+
+#[test]
+fn test_is_bare_repository() {
+    let temp_dir = tempdir().unwrap();
+    Repository::init(temp_dir.path().join("work")).unwrap();
+    Repository::init_bare(temp_dir.path().join("mirror.git")).unwrap();
+
+    assert!(is_bare_repository(&temp_dir.path().join("mirror.git")));
+    assert!(!is_bare_repository(&temp_dir.path().join("work")));
+    assert!(!is_bare_repository(temp_dir.path()));
+}
+
+#[test]
+fn test_remote_url_expands_github_shorthands() {
+    let hosts = BTreeMap::new();
+    assert_eq!(
+        remote_url("owner/repo", &hosts).unwrap(),
+        "https://github.com/owner/repo.git"
+    );
+    assert_eq!(
+        remote_url("vercel/next.js", &hosts).unwrap(),
+        "https://github.com/vercel/next.js.git"
+    );
+    assert_eq!(
+        remote_url("https://example.com/owner/repo.git", &hosts).unwrap(),
+        "https://example.com/owner/repo.git"
+    );
+    assert_eq!(
+        remote_url("not a repository", &hosts).unwrap_err(),
+        "Invalid repository shorthand"
+    );
+    assert_eq!(
+        remote_url("owner/group/repo", &hosts).unwrap_err(),
+        "Invalid repository shorthand"
+    );
+}
+
+#[test]
+fn test_remote_url_expands_forge_shorthands() {
+    let hosts = BTreeMap::new();
+    assert_eq!(
+        remote_url("gitlab:group/subgroup/repo", &hosts).unwrap(),
+        "https://gitlab.com/group/subgroup/repo.git"
+    );
+    assert_eq!(
+        remote_url("bitbucket:team/repo.git", &hosts).unwrap(),
+        "https://bitbucket.org/team/repo.git"
+    );
+    assert_eq!(
+        remote_url("codeberg:user/repo", &hosts).unwrap(),
+        "https://codeberg.org/user/repo.git"
+    );
+    assert_eq!(
+        remote_url("github:owner/repo", &hosts).unwrap(),
+        "https://github.com/owner/repo.git"
+    );
+    assert_eq!(
+        remote_url("codeberg:user/group/repo", &hosts).unwrap_err(),
+        "Invalid repository shorthand 'codeberg:user/group/repo', expected 'codeberg:owner/repo'"
+    );
+    assert_eq!(
+        remote_url("gitlab:group/../repo", &hosts).unwrap_err(),
+        "Invalid repository shorthand 'gitlab:group/../repo', expected 'gitlab:group/.../repo'"
+    );
+    assert!(
+        remote_url("corp:team/repo", &hosts)
+            .unwrap_err()
+            .starts_with("Unknown forge 'corp' in 'corp:team/repo'.")
+    );
+}
+
+#[test]
+fn test_remote_host_names_the_host_fetched_from() {
+    let hosts = BTreeMap::from([(
+        "corp".to_string(),
+        "https://Git.Corp.Example".to_string(),
+    )]);
+    assert_eq!(
+        remote_host("owner/repo", &hosts).as_deref(),
+        Some("github.com")
+    );
+    assert_eq!(
+        remote_host("gitlab:group/repo", &hosts).as_deref(),
+        Some("gitlab.com")
+    );
+    assert_eq!(
+        remote_host("corp:team/repo", &hosts).as_deref(),
+        Some("git.corp.example")
+    );
+    assert_eq!(
+        remote_host("git@codeberg.org:user/repo.git", &hosts).as_deref(),
+        Some("codeberg.org")
+    );
+    assert_eq!(remote_host("not a repository", &hosts), None);
+}
+
+#[test]
+fn test_remote_url_accepts_scp_like_ssh_addresses() {
+    let hosts = BTreeMap::new();
+    assert_eq!(
+        remote_url("git@github.com:org/repo.git", &hosts).unwrap(),
+        "git@github.com:org/repo.git"
+    );
+    assert_eq!(
+        remote_url("ssh://git@gitlab.com/group/repo.git", &hosts).unwrap(),
+        "ssh://git@gitlab.com/group/repo.git"
+    );
+    assert_eq!(
+        scp_url("git@github.com:org/repo.git").as_deref(),
+        Some("ssh://git@github.com/org/repo.git")
+    );
+    assert_eq!(
+        scp_url("git.corp.example:/srv/repo.git").as_deref(),
+        Some("ssh://git.corp.example/srv/repo.git")
+    );
+    assert_eq!(scp_url("gitlab:group/repo"), None);
+    assert_eq!(scp_url("https://github.com/org/repo"), None);
+
+    let params = Params {
+        cache_dir: Some("/cache".to_string()),
+        ..Params::default()
+    };
+    assert_eq!(
+        cached_clone_path(&params, "git@github.com:org/repo.git").unwrap(),
+        Path::new("/cache/github.com/org/repo")
+    );
+}
+
+#[test]
+fn test_remote_url_expands_configured_hosts() {
+    let hosts = BTreeMap::from([
+        ("corp".to_string(), "https://git.corp.example/".to_string()),
+        (
+            "gitlab".to_string(),
+            "https://gitlab.corp.example".to_string(),
+        ),
+    ]);
+    assert_eq!(
+        remote_url("corp:team/repo", &hosts).unwrap(),
+        "https://git.corp.example/team/repo.git"
+    );
+    assert_eq!(
+        remote_url("corp:platform/tools/repo", &hosts).unwrap(),
+        "https://git.corp.example/platform/tools/repo.git"
+    );
+    assert_eq!(
+        remote_url("gitlab:group/repo", &hosts).unwrap(),
+        "https://gitlab.corp.example/group/repo.git"
+    );
+}
+
+#[test]
+fn test_configured_host_prefixes_win_over_scp_addresses() {
+    let hosts = BTreeMap::from([(
+        "git.example.com".to_string(),
+        "https://git.example.com".to_string(),
+    )]);
+    assert_eq!(
+        remote_url("git.example.com:owner/repo", &hosts).unwrap(),
+        "https://git.example.com/owner/repo.git"
+    );
+    assert_eq!(
+        remote_host("git.example.com:owner/repo", &hosts).as_deref(),
+        Some("git.example.com")
+    );
+    assert_eq!(
+        remote_url("git.example.com:owner/repo", &BTreeMap::new()).unwrap(),
+        "git.example.com:owner/repo"
+    );
+}
+
+#[test]
+fn test_local_directory_shadowing_a_shorthand_is_warned_about() {
+    let hosts = BTreeMap::from([(
+        "corp".to_string(),
+        "https://git.corp.example".to_string(),
+    )]);
+    assert_eq!(
+        shadowed_shorthand_warning("owner/repo", &hosts).unwrap(),
+        "warning: 'owner/repo' is a local directory and is bundled as one, not as the remote repository. Use 'github:owner/repo' for the remote repository, or './owner/repo' to silence this warning."
+    );
+    assert!(
+        shadowed_shorthand_warning("corp:team/repo", &hosts)
+            .unwrap()
+            .contains("Use 'https://git.corp.example/team/repo.git'")
+    );
+    assert_eq!(shadowed_shorthand_warning("./owner/repo", &hosts), None);
+    assert_eq!(shadowed_shorthand_warning("../owner/repo", &hosts), None);
+    assert_eq!(shadowed_shorthand_warning("src", &hosts), None);
+    assert_eq!(shadowed_shorthand_warning("a/b/c", &hosts), None);
+    let absolute = tempdir().unwrap();
+    assert_eq!(
+        shadowed_shorthand_warning(&absolute.path().to_string_lossy(), &hosts),
+        None
+    );
+}
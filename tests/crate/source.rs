@@ -0,0 +1,116 @@
+use super::*;
+use crate::filelist::list_files_in_repo;
+use crate::structs::Params;
+use git2::{Oid, Signature};
+use tempfile::tempdir;
+
+fn commit_files(repo: &Repository, files: &[(&str, &str)]) -> Oid {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+    for (path, content) in files {
+        let full_path = workdir.join(path);
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        fs::write(full_path, content).unwrap();
+        index.add_path(Path::new(path)).unwrap();
+    }
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("Test", "test@example.com").unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "test", &tree, &[])
+        .unwrap()
+}
+
+#[test]
+fn test_tree_source_reads_blobs_from_the_object_database() {
+    let temp_dir = tempdir().unwrap();
+    let repo = Repository::init(temp_dir.path()).unwrap();
+    commit_files(
+        &repo,
+        &[
+            ("src/lib.rs", "pub fn lib() {}\n"),
+            ("README.md", "# Readme\n"),
+        ],
+    );
+    fs::remove_dir_all(temp_dir.path().join("src")).unwrap();
+
+    let tree = TreeSource::new(repo, "HEAD", "").unwrap();
+
+    assert_eq!(tree.directory(), None);
+    assert_eq!(tree.kind("").unwrap(), EntryKind::Directory);
+    assert_eq!(tree.kind("src").unwrap(), EntryKind::Directory);
+    assert!(tree.has_file("src/lib.rs"));
+    assert!(!tree.has_file("src"));
+    assert!(tree.has_dir("src"));
+    let mut entries = tree.entries("").unwrap();
+    entries.sort();
+    assert_eq!(
+        entries,
+        vec![
+            ("README.md".to_string(), EntryKind::File),
+            ("src".to_string(), EntryKind::Directory),
+        ]
+    );
+    assert_eq!(
+        tree.read_to_string("src/lib.rs").unwrap(),
+        "pub fn lib() {}\n"
+    );
+    assert_eq!(tree.file_size("README.md").unwrap(), 9);
+    assert_eq!(tree.describe("src/lib.rs"), "HEAD:src/lib.rs");
+    assert_eq!(
+        tree.open_file("missing.rs").err().unwrap().kind(),
+        io::ErrorKind::NotFound
+    );
+}
+
+#[test]
+fn test_tree_source_below_a_prefix() {
+    let temp_dir = tempdir().unwrap();
+    let repo = Repository::init(temp_dir.path()).unwrap();
+    commit_files(
+        &repo,
+        &[
+            ("src/lib.rs", "pub fn lib() {}\n"),
+            ("README.md", "# Readme\n"),
+        ],
+    );
+
+    let repo_workdir = repo.workdir().map(Path::to_path_buf);
+    let tree = TreeSource::new(repo, "HEAD", "src").unwrap();
+    assert_eq!(tree.prefix(), "src");
+    assert_eq!(tree.git_ref(), "HEAD");
+    assert_eq!(tree.repository().workdir(), repo_workdir.as_deref());
+    assert!(tree.has_file("lib.rs"));
+    assert!(!tree.has_file("README.md"));
+    assert_eq!(tree.describe("lib.rs"), "HEAD:src/lib.rs");
+
+    let repo = Repository::open(temp_dir.path()).unwrap();
+    let missing = TreeSource::new(repo, "HEAD", "docs").unwrap();
+    assert!(missing.entries("").unwrap().is_empty());
+}
+
+#[test]
+fn test_tree_listing_honours_committed_ignore_files() {
+    let temp_dir = tempdir().unwrap();
+    let repo = Repository::init(temp_dir.path()).unwrap();
+    commit_files(
+        &repo,
+        &[
+            ("lib.rs", "pub fn lib() {}\n"),
+            (".gitignore", "*.log\n"),
+            ("debug.log", "log"),
+            ("docs/.bundlerepoignore", "draft.md\n"),
+            ("docs/draft.md", "draft"),
+            ("docs/guide.md", "guide"),
+        ],
+    );
+    let tree = TreeSource::new(repo, "HEAD", "").unwrap();
+    let flags = Params {
+        bundlerepoignore: true,
+        ..Params::default()
+    };
+
+    let file_list = list_files_in_repo(&tree, &flags).unwrap();
+
+    assert_eq!(file_list.files, vec!["docs/guide.md", "lib.rs"]);
+    assert!(file_list.bundlerepoignore);
+}
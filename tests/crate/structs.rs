@@ -1,4 +1,5 @@
 use super::*;
+use clap::Parser;
 use config::{Config, File, FileFormat};
 
 #[test]
@@ -115,6 +116,17 @@ fn test_params_default() {
     assert!(!params.utf8);
     assert!(!params.gzip);
     assert_eq!(params.gzip_level, 6);
+    assert!(params.bundlerepoignore);
+    assert!(!params.tracked_only);
+    assert!(!params.include_ignored);
+    assert_eq!(params.paths, None);
+    assert_eq!(params.languages, None);
+    assert!(params.include_tests);
+    assert!(params.include_docs);
+    assert!(params.include_examples);
+    assert_eq!(params.max_file_size, None);
+    assert_eq!(params.max_file_lines, None);
+    assert_eq!(params.modified_within, None);
 }
 
 #[test]
@@ -127,8 +139,34 @@ fn test_params_from_config() {
             line_numbers = true
             token = "secret-token"
             branch = "main"
+            offline = true
+            no_cache = true
+            ssh_key = "~/.ssh/deploy_key"
+            cache_dir = "/var/cache/bundlerepo"
             extend_exclude = ["target", "node_modules"]
             exclude = ["custom.xml"]
+            bundlerepoignore = false
+            tracked_only = true
+            include_ignored = true
+            paths = ["services/billing"]
+            packages = ["billing"]
+            package_path_dependencies = true
+            from_root = true
+            history = 10
+            last_commit = true
+            order = "hotspots"
+            languages = ["rust"]
+            include_tests = false
+            include_docs = false
+            include_examples = false
+            max_file_size = "1MB"
+            max_file_lines = 500
+            modified_within = "2w"
+            truncate_lines = 400
+            truncate_tokens = 8000
+
+            [hosts]
+            corp = "https://git.corp.example"
         "#;
     let config = Config::builder()
         .add_source(File::from_str(config_str, FileFormat::Toml))
@@ -143,12 +181,42 @@ fn test_params_from_config() {
     assert!(params.line_numbers);
     assert_eq!(params.token, Some("secret-token".to_string()));
     assert_eq!(params.branch, Some("main".to_string()));
+    assert_eq!(params.ssh_key.as_deref(), Some("~/.ssh/deploy_key"));
+    assert!(params.offline);
+    assert!(params.no_cache);
+    assert_eq!(params.cache_dir.as_deref(), Some("/var/cache/bundlerepo"));
     assert_eq!(
         params.extend_exclude,
         Some(vec!["target".to_string(), "node_modules".to_string()])
     );
     assert_eq!(params.exclude, Some(vec!["custom.xml".to_string()]));
     assert!(!params.utf8);
+    assert!(!params.bundlerepoignore);
+    assert!(params.tracked_only);
+    assert!(params.include_ignored);
+    assert_eq!(params.paths, Some(vec!["services/billing".to_string()]));
+    assert_eq!(params.packages, Some(vec!["billing".to_string()]));
+    assert!(params.package_path_dependencies);
+    assert!(params.from_root);
+    assert_eq!(params.history, Some(10));
+    assert!(params.last_commit);
+    assert_eq!(params.order.as_deref(), Some("hotspots"));
+    assert_eq!(params.languages, Some(vec!["rust".to_string()]));
+    assert!(!params.include_tests);
+    assert!(!params.include_docs);
+    assert!(!params.include_examples);
+    assert_eq!(params.max_file_size, Some(1024 * 1024));
+    assert_eq!(params.max_file_lines, Some(500));
+    assert_eq!(params.modified_within, Some(14 * 24 * 60 * 60));
+    assert_eq!(params.truncate_lines, Some(400));
+    assert_eq!(params.truncate_tokens, Some(8000));
+    assert_eq!(
+        params.hosts,
+        BTreeMap::from([(
+            "corp".to_string(),
+            "https://git.corp.example".to_string()
+        )])
+    );
 }
 
 #[test]
@@ -423,3 +491,104 @@ fn test_invalid_gzip_config_levels_are_ignored() {
         assert_eq!(params.gzip_level, 6);
     }
 }
+
+#[test]
+fn test_remote_repository_bundles_its_branch_from_the_cache() {
+    let args = cli::Flags::parse_from(["program", "owner/repo", "--offline"]);
+    let params = Params::from_args_and_config(&args, Params::default());
+    assert_eq!(params.git_ref.as_deref(), Some("HEAD"));
+    assert!(params.offline);
+    assert!(params.no_git);
+
+    let config = Params {
+        branch: Some("develop".to_string()),
+        ..Params::default()
+    };
+    let params = Params::from_args_and_config(&args, config);
+    assert_eq!(params.git_ref.as_deref(), Some("develop"));
+
+    let args =
+        cli::Flags::parse_from(["program", "owner/repo", "--ref", "v1"]);
+    let params = Params::from_args_and_config(&args, Params::default());
+    assert_eq!(params.git_ref.as_deref(), Some("v1"));
+}
+
+#[test]
+fn test_from_root_applies_to_refs() {
+    let args =
+        cli::Flags::parse_from(["program", "--ref", "HEAD", "--from-root"]);
+    let params = Params::from_args_and_config(&args, Params::default());
+    assert!(params.from_root);
+
+    let config = Params {
+        from_root: true,
+        ..Params::default()
+    };
+    let args = cli::Flags::parse_from(["program", "--no-git"]);
+    let params = Params::from_args_and_config(&args, config);
+    assert!(!params.from_root);
+}
+
+#[test]
+fn test_hosts_config_must_be_a_table() {
+    let config = Config::builder()
+        .add_source(File::from_str("hosts = \"corp\"", FileFormat::Toml))
+        .build()
+        .unwrap();
+
+    let error = BTreeMap::<String, String>::load_from_config(&config, "hosts")
+        .unwrap_err();
+    assert!(matches!(error, ConfigError::TypeError { .. }));
+
+    let params: Params = config.into();
+    assert!(params.hosts.is_empty());
+}
+
+#[test]
+fn test_tokens_config_is_keyed_by_host_name() {
+    let config_str = r#"
+            token = "fallback"
+
+            [tokens]
+            "github.com" = "github-token"
+            "git.corp.example" = "corp-token"
+        "#;
+    let config = Config::builder()
+        .add_source(File::from_str(config_str, FileFormat::Toml))
+        .build()
+        .unwrap();
+
+    let params: Params = config.into();
+    assert_eq!(
+        params.tokens,
+        BTreeMap::from([
+            ("git.corp.example".to_string(), "corp-token".to_string()),
+            ("github.com".to_string(), "github-token".to_string()),
+        ])
+    );
+    assert_eq!(params.token.as_deref(), Some("fallback"));
+}
+
+#[test]
+fn test_token_is_picked_for_the_repository_host() {
+    let config = Params {
+        token: Some("fallback".to_string()),
+        tokens: BTreeMap::from([(
+            "codeberg.org".to_string(),
+            "codeberg-token".to_string(),
+        )]),
+        ..Params::default()
+    };
+    let args = cli::Flags::parse_from(["program", "codeberg:user/repo"]);
+    let params = Params::from_args_and_config(&args, config);
+    assert_eq!(params.token.as_deref(), Some("codeberg-token"));
+
+    let config = Params {
+        token: Some("fallback".to_string()),
+        ..Params::default()
+    };
+    let args =
+        cli::Flags::parse_from(["program", "codeberg:user/repo", "-t", "cli"]);
+    let params = Params::from_args_and_config(&args, config);
+    assert_eq!(params.token.as_deref(), Some("cli"));
+}
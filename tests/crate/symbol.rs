@@ -0,0 +1,122 @@
+use super::*;
+
+const RUST: &str = r#"use std::fmt;
+
+/// A point.
+#[derive(Debug)]
+pub struct Point {
+    x: i32,
+}
+
+impl Point {
+    /// Makes a point.
+    pub const fn new(x: i32) -> Self {
+        Self { x }
+    }
+
+    fn label<'a>(&self, name: &'a str) -> String {
+        format!("{name} {{ '}}' {}", self.x)
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "}}")
+    }
+}
+
+pub(crate) type Points = Vec<Point>;
+
+fn main() {
+    let _ = Point::new(1);
+}
+"#;
+
+const PYTHON: &str = r#"import os
+
+
+@dataclass
+class Config:
+    """Settings.
+
+Kept across runs.
+"""
+
+    def load(
+        self,
+        path,
+    ):
+        return os.path.join(path, "}")
+
+    def save(self): pass
+
+
+def main():
+    Config().load("x")
+"#;
+
+const TYPESCRIPT: &str = r#"import { api } from "./api";
+
+/**
+ * A client.
+ */
+export class Client {
+  private base = "/";
+
+  async fetch(path: string): Promise<string> {
+    return api(`${this.base}{${path}`);
+  }
+}
+
+export const TIMEOUT = 30
+
+export function connect() {
+  return new Client();
+}
+"#;
+
+fn lines(path: &str, content: &str, symbol: &str) -> (usize, usize) {
+    let range = symbol_lines(path, content, symbol).unwrap();
+    (range.start, range.end)
+}
+
+#[test]
+fn test_symbol_lines_in_rust() {
+    assert_eq!(lines("lib.rs", RUST, "Point"), (3, 7));
+    assert_eq!(lines("lib.rs", RUST, "Point::new"), (10, 13));
+    assert_eq!(lines("lib.rs", RUST, "Point::label"), (15, 17));
+    assert_eq!(lines("lib.rs", RUST, "Point::fmt"), (21, 23));
+    assert_eq!(lines("lib.rs", RUST, "Points"), (26, 26));
+    assert_eq!(lines("lib.rs", RUST, "main"), (28, 30));
+}
+
+#[test]
+fn test_symbol_lines_in_python() {
+    assert_eq!(lines("app.py", PYTHON, "Config"), (4, 17));
+    assert_eq!(lines("app.py", PYTHON, "Config::load"), (11, 15));
+    assert_eq!(lines("app.py", PYTHON, "Config::save"), (17, 17));
+    assert_eq!(lines("app.py", PYTHON, "main"), (20, 21));
+}
+
+#[test]
+fn test_symbol_lines_in_typescript() {
+    assert_eq!(lines("client.ts", TYPESCRIPT, "Client"), (3, 12));
+    assert_eq!(lines("client.ts", TYPESCRIPT, "Client::fetch"), (9, 11));
+    assert_eq!(lines("client.ts", TYPESCRIPT, "Client::base"), (7, 7));
+    assert_eq!(lines("client.ts", TYPESCRIPT, "TIMEOUT"), (14, 14));
+    assert_eq!(lines("client.ts", TYPESCRIPT, "connect"), (16, 18));
+}
+
+#[test]
+fn test_symbol_lines_reports_missing_and_unsupported_symbols() {
+    // Nested definitions are only found through their parent.
+    let error = symbol_lines("lib.rs", RUST, "new").unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    assert_eq!(error.to_string(), "'new' is not defined in 'lib.rs'");
+    assert!(symbol_lines("lib.rs", RUST, "Point::missing").is_err());
+    assert!(symbol_lines("lib.rs", "", "main").is_err());
+
+    let error =
+        symbol_lines("main.go", "func main() {}\n", "main").unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
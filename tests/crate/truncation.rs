@@ -0,0 +1,84 @@
+use super::*;
+use crate::tokenizer::Model;
+
+fn numbered_lines(count: usize) -> String {
+    (1..=count).map(|line| format!("line {line}\n")).collect()
+}
+
+#[test]
+fn test_line_budget_keeps_head_and_tail() {
+    let tokenizer = Model::GPT4.to_tokenizer().unwrap();
+    let text = numbered_lines(10);
+    let lines = text.lines().collect::<Vec<_>>();
+
+    let truncation = plan_truncation(&text, &lines, Some(5), None, &tokenizer)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        truncation,
+        Truncation {
+            head: 3,
+            tail: 2,
+            original_lines: 10,
+        }
+    );
+    assert_eq!(truncation.elided(), 5);
+    assert_eq!(
+        render_truncated(&lines, &truncation, false),
+        "line 1\nline 2\nline 3\n... [5 lines elided] ...\nline 9\nline 10\n"
+    );
+
+    assert_eq!(
+        plan_truncation(&text, &lines, Some(10), None, &tokenizer).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_token_budget_applies_only_to_files_over_it() {
+    let tokenizer = Model::GPT4.to_tokenizer().unwrap();
+    let text = numbered_lines(100);
+    let lines = text.lines().collect::<Vec<_>>();
+    let total = tokenizer.count_tokens(&text).unwrap();
+
+    assert_eq!(
+        plan_truncation(&text, &lines, None, Some(total), &tokenizer).unwrap(),
+        None
+    );
+
+    let truncation =
+        plan_truncation(&text, &lines, None, Some(total / 4), &tokenizer)
+            .unwrap()
+            .unwrap();
+    assert!(truncation.head > 0 && truncation.tail > 0);
+    assert!(truncation.head - truncation.tail <= 1);
+    let kept = lines[..truncation.head]
+        .iter()
+        .chain(&lines[100 - truncation.tail..])
+        .map(|line| tokenizer.count_tokens(line).unwrap() + 1)
+        .sum::<usize>();
+    assert!(kept <= total / 4);
+}
+
+#[test]
+fn test_render_truncated_keeps_original_line_numbers() {
+    let text = numbered_lines(12);
+    let lines = text.lines().collect::<Vec<_>>();
+    let truncation = Truncation {
+        head: 2,
+        tail: 2,
+        original_lines: 12,
+    };
+
+    assert_eq!(
+        render_truncated(&lines, &truncation, true),
+        " 1  line 1\n 2  line 2\n    ... [8 lines elided] ...\n11  line 11\n12  line 12\n"
+    );
+}
+
+#[test]
+fn test_parse_token_count() {
+    assert_eq!(parse_token_count("4000"), Ok(4000));
+    assert!(parse_token_count("0").is_err());
+    assert!(parse_token_count("many").is_err());
+}
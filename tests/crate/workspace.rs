@@ -0,0 +1,181 @@
+use super::*;
+use git2::Repository;
+use std::fs;
+use tempfile::TempDir;
+
+fn create_files(files: &[(&str, &str)]) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    for (path, content) in files {
+        let path = temp_dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    temp_dir
+}
+
+fn cargo_workspace() -> TempDir {
+    create_files(&[
+        (
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\", \"tools/cli\"]\nexclude = [\"crates/scratch\"]\n\n[workspace.dependencies]\ncore = { path = \"crates/core\" }\n",
+        ),
+        (
+            "crates/app/Cargo.toml",
+            "[package]\nname = \"app\"\n\n[dependencies]\ncore = { workspace = true }\nserde = \"1\"\n\n[dev-dependencies]\ntesting = { path = \"../testing\" }\n",
+        ),
+        ("crates/app/src/main.rs", "fn main() {}\n"),
+        ("crates/core/Cargo.toml", "[package]\nname = \"core\"\n"),
+        ("crates/core/src/lib.rs", "pub fn core() {}\n"),
+        (
+            "crates/testing/Cargo.toml",
+            "[package]\nname = \"testing\"\n",
+        ),
+        (
+            "crates/scratch/Cargo.toml",
+            "[package]\nname = \"scratch\"\n",
+        ),
+        ("tools/cli/Cargo.toml", "[package]\nname = \"cli\"\n"),
+    ])
+}
+
+fn scopes_for(
+    temp_dir: &TempDir,
+    packages: &[&str],
+    deps: bool,
+) -> Vec<String> {
+    let flags = Params {
+        packages: Some(packages.iter().map(|name| name.to_string()).collect()),
+        package_path_dependencies: deps,
+        ..Params::default()
+    };
+    package_scopes(temp_dir.path(), &flags).unwrap()
+}
+
+#[test]
+fn test_cargo_workspace_members_are_packages() {
+    let temp_dir = cargo_workspace();
+
+    let packages = workspace_packages(temp_dir.path())
+        .into_iter()
+        .map(|package| (package.name, package.directory))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        packages,
+        [
+            ("app".to_string(), "crates/app".to_string()),
+            ("core".to_string(), "crates/core".to_string()),
+            ("testing".to_string(), "crates/testing".to_string()),
+            ("cli".to_string(), "tools/cli".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_package_scopes_include_root_manifest_and_path_dependencies() {
+    let temp_dir = cargo_workspace();
+
+    assert_eq!(
+        scopes_for(&temp_dir, &["app"], false),
+        ["crates/app", "Cargo.toml"]
+    );
+    assert_eq!(
+        scopes_for(&temp_dir, &["app"], true),
+        [
+            "crates/app",
+            "Cargo.toml",
+            "crates/core",
+            "Cargo.toml",
+            "crates/testing",
+            "Cargo.toml",
+        ]
+    );
+}
+
+#[test]
+fn test_unknown_package_lists_available_names() {
+    let temp_dir = cargo_workspace();
+    let flags = Params {
+        packages: Some(vec!["missing".to_string()]),
+        ..Params::default()
+    };
+
+    assert_eq!(
+        package_scopes(temp_dir.path(), &flags)
+            .unwrap_err()
+            .to_string(),
+        "Unknown package 'missing'. Workspace packages: app, cli, core, testing"
+    );
+}
+
+#[test]
+fn test_npm_and_pnpm_workspaces_are_packages() {
+    let temp_dir = create_files(&[
+        (
+            "package.json",
+            r#"{"name": "root", "private": true, "workspaces": {"packages": ["packages/*", "!packages/legacy"]}}"#,
+        ),
+        ("pnpm-workspace.yaml", "packages:\n  - 'apps/**'\n"),
+        (
+            "packages/ui/package.json",
+            r#"{"name": "@acme/ui", "dependencies": {"@acme/utils": "workspace:*", "react": "^18"}}"#,
+        ),
+        ("packages/utils/package.json", r#"{"name": "@acme/utils"}"#),
+        ("packages/legacy/package.json", r#"{"name": "legacy"}"#),
+        (
+            "apps/web/package.json",
+            r#"{"name": "web", "devDependencies": {"shared": "file:../../shared"}}"#,
+        ),
+        ("shared/package.json", r#"{"name": "shared"}"#),
+        ("shared/index.js", ""),
+        ("README.md", "# Acme\n"),
+    ]);
+
+    let mut names = workspace_packages(temp_dir.path())
+        .into_iter()
+        .map(|package| package.name)
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["@acme/ui", "@acme/utils", "root", "web"]);
+
+    assert_eq!(
+        scopes_for(&temp_dir, &["@acme/ui"], true),
+        [
+            "packages/ui",
+            "package.json",
+            "pnpm-workspace.yaml",
+            "packages/utils",
+            "package.json",
+            "pnpm-workspace.yaml",
+        ]
+    );
+    assert_eq!(scopes_for(&temp_dir, &["web"], true)[3], "shared");
+    assert_eq!(
+        scopes_for(&temp_dir, &["root"], false),
+        [
+            "README.md",
+            "package.json",
+            "packages/legacy",
+            "pnpm-workspace.yaml",
+            "shared",
+            "package.json",
+            "pnpm-workspace.yaml",
+        ]
+    );
+}
+
+#[test]
+fn test_single_crate_is_its_own_package() {
+    let temp_dir = create_files(&[
+        ("Cargo.toml", "[package]\nname = \"bundle_repo\"\n"),
+        (".gitignore", "target/\n"),
+        ("src/main.rs", "fn main() {}\n"),
+        ("target/debug/app", ""),
+    ]);
+    Repository::init(temp_dir.path()).unwrap();
+
+    assert_eq!(
+        scopes_for(&temp_dir, &["bundle_repo"], false),
+        [".gitignore", "Cargo.toml", "src", "Cargo.toml"]
+    );
+}
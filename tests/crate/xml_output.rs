@@ -1,5 +1,6 @@
 use super::*;
-use crate::filelist::FileTree;
+use crate::filelist::{FileList, FileTree, group_files_by_directory};
+use crate::limits::SkipReason;
 use crate::test_fixtures::{
     ENCODING_FIXTURES, UTF16BE_BYTES, UTF16LE_BYTES, WINDOWS_1252_BYTES,
 };
@@ -176,6 +177,7 @@ fn serialize_single_file(content: &[u8], line_numbers: bool) -> Vec<u8> {
         &flags,
         &tree,
         temp_dir.path(),
+        &Model::GPT4.to_tokenizer().unwrap(),
         &mut reporter,
         &mut ProcessingTimings::default(),
     )
@@ -198,8 +200,13 @@ fn serialize_text_entry(path: &str, content: &str) -> Vec<u8> {
     writer
         .write(XmlEvent::start_element("repository_files"))
         .unwrap();
-    write_text_file_entry(&mut writer, path, content.len() as u64, content)
-        .unwrap();
+    write_text_file_entry(
+        &mut writer,
+        path.into(),
+        content.len() as u64,
+        content,
+    )
+    .unwrap();
     writer.write(XmlEvent::end_element()).unwrap();
     writer.write(XmlEvent::end_element()).unwrap();
     writer.into_inner().into_inner()
@@ -221,7 +228,7 @@ fn serialize_read_error_entry(path: &str, diagnostic: &str) -> Vec<u8> {
     writer
         .write(XmlEvent::start_element("repository_files"))
         .unwrap();
-    write_read_error_file_entry(&mut writer, path, diagnostic).unwrap();
+    write_read_error_file_entry(&mut writer, path.into(), diagnostic).unwrap();
     writer.write(XmlEvent::end_element()).unwrap();
     writer.write(XmlEvent::end_element()).unwrap();
     writer.into_inner().into_inner()
@@ -395,6 +402,38 @@ fn test_complete_document_has_one_root_and_literal_file_instruction() {
     assert!(!element_names.contains(&".py"));
 }
 
+#[test]
+fn test_notes_record_bundlerepoignore_only_when_applied() {
+    for applied in [true, false] {
+        let mut writer = EmitterConfig::new()
+            .write_document_declaration(false)
+            .create_writer(Cursor::new(Vec::new()));
+        write_file_summary(&mut writer, &Params::default(), applied).unwrap();
+        let summary =
+            String::from_utf8(writer.into_inner().into_inner()).unwrap();
+
+        assert_eq!(summary.contains(".bundlerepoignore rules"), applied);
+    }
+}
+
+#[test]
+fn test_notes_record_path_scopes() {
+    let flags = Params {
+        paths: Some(vec!["services/billing".to_string(), "libs".to_string()]),
+        ..Params::default()
+    };
+    let mut writer = EmitterConfig::new()
+        .write_document_declaration(false)
+        .create_writer(Cursor::new(Vec::new()));
+    write_file_summary(&mut writer, &flags, false).unwrap();
+    let summary = String::from_utf8(writer.into_inner().into_inner()).unwrap();
+
+    assert!(summary.contains(
+        "Only these paths within the repository have been included: \
+         services/billing, libs."
+    ));
+}
+
 #[test]
 fn test_cdata_content_matrix_round_trips_through_complete_documents() {
     let cases = [
@@ -455,6 +494,125 @@ fn test_line_metadata_uses_xml_logical_lines() {
     }
 }
 
+#[test]
+fn test_truncated_file_records_original_lines() {
+    let temp_dir = tempdir().unwrap();
+    let content = (1..=12)
+        .map(|line| format!("line {line}\n"))
+        .collect::<String>();
+    fs::write(temp_dir.path().join("test.txt"), content).unwrap();
+    let mut tree = FileTree::default();
+    tree.file_paths.push("test.txt".to_string());
+    let flags = Params {
+        line_numbers: true,
+        truncate_lines: Some(4),
+        ..Params::default()
+    };
+    let mut reporter = ProgressReporter::new(Vec::new(), Vec::new(), true);
+    let xml = serialize_repository_xml(
+        &flags,
+        &tree,
+        temp_dir.path(),
+        &Model::GPT4.to_tokenizer().unwrap(),
+        &mut reporter,
+        &mut ProcessingTimings::default(),
+    )
+    .unwrap();
+
+    let file = parse_file(&xml, "test.txt");
+    assert_eq!(
+        file.text,
+        " 1  line 1\n 2  line 2\n    ... [8 lines elided] ...\n11  line 11\n12  line 12\n"
+    );
+    assert_eq!(attribute(&file, "truncated"), "true");
+    assert_eq!(attribute(&file, "original_lines"), "12");
+    assert_eq!(attribute(&file, "lines"), "5");
+    assert!(
+        String::from_utf8(xml)
+            .unwrap()
+            .contains("truncated=\"true\"")
+    );
+}
+
+#[test]
+fn test_line_ranges_write_partial_file_entries() {
+    let temp_dir = tempdir().unwrap();
+    let content = (1..=12)
+        .map(|line| format!("line {line}\n"))
+        .collect::<String>();
+    fs::write(temp_dir.path().join("test.txt"), content).unwrap();
+    let mut tree = FileTree::default();
+    tree.file_paths.push("test.txt".to_string());
+    let flags = Params {
+        line_numbers: true,
+        paths: Some(vec![
+            "test.txt:10-40".to_string(),
+            "test.txt:2-3".to_string(),
+        ]),
+        ..Params::default()
+    };
+    let mut reporter = ProgressReporter::new(Vec::new(), Vec::new(), true);
+    let xml = serialize_repository_xml(
+        &flags,
+        &tree,
+        temp_dir.path(),
+        &Model::GPT4.to_tokenizer().unwrap(),
+        &mut reporter,
+        &mut ProcessingTimings::default(),
+    )
+    .unwrap();
+
+    let xml = String::from_utf8(xml).unwrap();
+    assert!(xml.contains(
+        "<file path=\"test.txt\" language=\"text\" size=\"87\" lines=\"2\" start_line=\"2\" end_line=\"3\">"
+    ));
+    assert!(xml.contains("<![CDATA[ 2  line 2\n 3  line 3\n]]>"));
+    assert!(xml.contains("start_line=\"10\" end_line=\"12\""));
+    assert!(
+        xml.contains("<![CDATA[10  line 10\n11  line 11\n12  line 12\n]]>")
+    );
+}
+
+#[test]
+fn test_grep_context_writes_matching_regions() {
+    let temp_dir = tempdir().unwrap();
+    let content = (1..=20)
+        .map(|line| {
+            if line == 8 {
+                "let reporter = ProgressReporter::new();\n".to_string()
+            } else {
+                format!("line {line}\n")
+            }
+        })
+        .collect::<String>();
+    fs::write(temp_dir.path().join("test.txt"), content).unwrap();
+    let mut tree = FileTree::default();
+    tree.file_paths.push("test.txt".to_string());
+    let flags = Params {
+        grep: Some("ProgressReporter".to_string()),
+        grep_context: Some(1),
+        ..Params::default()
+    };
+    let mut reporter = ProgressReporter::new(Vec::new(), Vec::new(), true);
+    let xml = serialize_repository_xml(
+        &flags,
+        &tree,
+        temp_dir.path(),
+        &Model::GPT4.to_tokenizer().unwrap(),
+        &mut reporter,
+        &mut ProcessingTimings::default(),
+    )
+    .unwrap();
+
+    let file = parse_file(&xml, "test.txt");
+    assert_eq!(
+        file.text,
+        "line 7\nlet reporter = ProgressReporter::new();\nline 9\n"
+    );
+    assert_eq!(attribute(&file, "start_line"), "7");
+    assert_eq!(attribute(&file, "end_line"), "9");
+}
+
 #[test]
 fn test_empty_file_remains_empty_with_and_without_line_numbers() {
     for line_numbers in [false, true] {
@@ -475,7 +633,8 @@ fn test_xml_forbidden_text_is_omitted_without_reclassification() {
         fs::write(&path, content.as_bytes()).unwrap();
         assert!(matches!(
             read_classify_and_decode(
-                &path,
+                temp_dir.path(),
+                "test.txt",
                 false,
                 &mut ProcessingTimings::default()
             )
@@ -511,6 +670,7 @@ fn test_xml_forbidden_text_warning_respects_quiet_reporter() {
             &Params::default(),
             &tree,
             temp_dir.path(),
+            &Model::GPT4.to_tokenizer().unwrap(),
             &mut reporter,
             &mut ProcessingTimings::default(),
         )
@@ -537,7 +697,8 @@ fn test_sparse_del_remains_text_and_round_trips_as_xml10() {
     fs::write(&path, content).unwrap();
     assert!(matches!(
         read_classify_and_decode(
-            &path,
+            temp_dir.path(),
+            "test.txt",
             false,
             &mut ProcessingTimings::default()
         )
@@ -563,6 +724,7 @@ fn test_xml_sensitive_metadata_round_trips_in_structure_and_file_entries() {
         &Params::default(),
         &tree,
         temp_dir.path(),
+        &Model::GPT4.to_tokenizer().unwrap(),
         &mut reporter,
         &mut ProcessingTimings::default(),
     )
@@ -604,6 +766,7 @@ fn test_parse_file_selects_repository_content_entry_on_path_collision() {
         &Params::default(),
         &tree,
         temp_dir.path(),
+        &Model::GPT4.to_tokenizer().unwrap(),
         &mut reporter,
         &mut ProcessingTimings::default(),
     )
@@ -634,6 +797,7 @@ fn test_nested_repository_structure_round_trips_with_hierarchy() {
         &Params::default(),
         &tree,
         temp_dir.path(),
+        &Model::GPT4.to_tokenizer().unwrap(),
         &mut reporter,
         &mut ProcessingTimings::default(),
     )
@@ -671,6 +835,7 @@ fn test_lf_and_cr_metadata_round_trip_exactly() {
         &Params::default(),
         &tree,
         temp_dir.path(),
+        &Model::GPT4.to_tokenizer().unwrap(),
         &mut reporter,
         &mut ProcessingTimings::default(),
     )
@@ -741,7 +906,13 @@ fn test_read_classify_and_decode_distinguishes_text_and_binary() {
     let text_path = temp_dir.path().join("test.txt");
     fs::write(&text_path, "Hello, World!").unwrap();
     assert!(matches!(
-        read_classify_and_decode(&text_path, false, &mut timings).unwrap(),
+        read_classify_and_decode(
+            temp_dir.path(),
+            "test.txt",
+            false,
+            &mut timings
+        )
+        .unwrap(),
         ProcessedFile::Text(_)
     ));
 
@@ -749,7 +920,13 @@ fn test_read_classify_and_decode_distinguishes_text_and_binary() {
     let binary_path = temp_dir.path().join("test.bin");
     fs::write(&binary_path, [0u8, 159u8, 146u8, 150u8]).unwrap();
     assert!(matches!(
-        read_classify_and_decode(&binary_path, false, &mut timings).unwrap(),
+        read_classify_and_decode(
+            temp_dir.path(),
+            "test.bin",
+            false,
+            &mut timings
+        )
+        .unwrap(),
         ProcessedFile::Binary(_)
     ));
 }
@@ -861,6 +1038,52 @@ fn test_output_repo_with_line_numbers() {
     assert!(xml_content.contains("3  Line 3"));
 }
 
+#[test]
+fn test_file_entries_record_recognised_language() {
+    let temp_dir = tempdir().unwrap();
+    let output_file = temp_dir.path().join("output.xml");
+    fs::write(temp_dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(temp_dir.path().join("NOTICE"), "notice\n").unwrap();
+    let params = Params {
+        output_file: Some(output_file.to_str().unwrap().to_string()),
+        ..Params::default()
+    };
+    let mut file_tree = FileTree::default();
+    file_tree.file_paths.push("main.rs".to_string());
+    file_tree.file_paths.push("NOTICE".to_string());
+    let tokenizer = Model::GPT4.to_tokenizer().unwrap();
+
+    output_repo_as_xml(&params, file_tree, temp_dir.path(), &tokenizer)
+        .unwrap();
+
+    let xml_content = fs::read(output_file).unwrap();
+    let rust = parse_file(&xml_content, "main.rs");
+    assert_eq!(attribute(&rust, "language"), "rust");
+    let notice = parse_file(&xml_content, "NOTICE");
+    assert!(!notice.attributes.iter().any(|(name, _)| name == "language"));
+}
+
+#[test]
+fn test_structure_lists_skipped_files_with_reason() {
+    let mut tree = FileTree::default();
+    tree.folder_node.files.push("lib.rs".to_string());
+    tree.folder_node
+        .skipped
+        .push(("dump.sql".to_string(), SkipReason::Size));
+    let mut writer = EmitterConfig::new()
+        .write_document_declaration(false)
+        .create_writer(Cursor::new(Vec::new()));
+
+    write_repository_structure(&mut writer, &tree.folder_node).unwrap();
+
+    let structure =
+        String::from_utf8(writer.into_inner().into_inner()).unwrap();
+    assert!(structure.contains(r#"<file path="lib.rs" />"#));
+    assert!(
+        structure.contains(r#"<file path="dump.sql" skipped="too-large" />"#)
+    );
+}
+
 #[test]
 fn test_binary_file_handling() {
     let temp_dir = tempdir().unwrap();
@@ -1177,7 +1400,10 @@ fn test_malformed_utf8_bom_is_silent_with_quiet_reporter() {
         b"\xef\xbb\xbfmalformed \xff text",
     )
     .unwrap();
-    let paths = vec!["malformed.txt".to_string()];
+    let file_tree = group_files_by_directory(FileList {
+        files: vec!["malformed.txt".to_string()],
+        ..FileList::default()
+    });
     let params = Params {
         stdout: true,
         utf8: true,
@@ -1191,9 +1417,10 @@ fn test_malformed_utf8_bom_is_silent_with_quiet_reporter() {
 
     write_repository_files_to_xml(
         &mut writer,
-        &paths,
+        &file_tree,
         temp_dir.path(),
         &params,
+        &Model::GPT4.to_tokenizer().unwrap(),
         &mut reporter,
         &mut ProcessingTimings::default(),
     )
@@ -1533,6 +1760,7 @@ fn test_all_testable_destinations_use_canonical_serialization_bytes() {
         &Params::default(),
         &expected_tree,
         temp_dir.path(),
+        &Model::GPT4.to_tokenizer().unwrap(),
         &mut reporter,
         &mut ProcessingTimings::default(),
     )